@@ -1,7 +1,10 @@
 // Trusted Setup Demo for 5-Party SP Consortium
 // Pre-generates ZKP keys for all containers to use
+use sp_blockchain::zkp::powers_of_tau::{PowersOfTau, CONSORTIUM_PHASE1_DOMAIN_SIZE};
 use sp_blockchain::zkp::trusted_setup::TrustedSetupCeremony;
-use ark_std::rand::thread_rng;
+use ark_std::rand::{thread_rng, RngCore};
+use ed25519_dalek::{Signer, SigningKey};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[tokio::main]
@@ -22,15 +25,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut rng = thread_rng();
 
+    // Phase 1: every member adds its secret to the powers of tau. A deployment would
+    // instead start from a public transcript such as the Perpetual Powers of Tau.
+    println!("🌀 Running powers-of-tau phase for domains up to {} points...", CONSORTIUM_PHASE1_DOMAIN_SIZE);
+    let mut powers_of_tau = PowersOfTau::new(CONSORTIUM_PHASE1_DOMAIN_SIZE);
+    for provider in ["T-Mobile-DE", "Vodafone-UK", "Orange-FR", "Telefónica-ES", "SFR-FR"] {
+        let previous = powers_of_tau.clone();
+        let contribution = powers_of_tau.contribute(&mut rng);
+        if !PowersOfTau::verify_contribution(&previous, &powers_of_tau, &contribution, &mut rng) {
+            return Err(format!("Powers of tau contribution from {} failed verification", provider).into());
+        }
+        println!("   ✅ {} contributed to the powers of tau", provider);
+    }
+
+    // Each member keeps its consortium signing key to itself (an HSM in production) and
+    // only publishes the public half; the ceremony asks members to sign their contributions
+    let member_keys: HashMap<String, SigningKey> = ["T-Mobile-DE", "Vodafone-UK", "Orange-FR", "Telefónica-ES", "SFR-FR"]
+        .iter()
+        .map(|provider| {
+            let mut secret = [0u8; 32];
+            rng.fill_bytes(&mut secret);
+            (provider.to_string(), SigningKey::from_bytes(&secret))
+        })
+        .collect();
+    let public_keys: HashMap<_, _> = member_keys.iter()
+        .map(|(provider, key)| (provider.clone(), key.verifying_key()))
+        .collect();
+    let sign = |provider: &str, message: &[u8]| {
+        member_keys.get(provider).map(|key| key.sign(message).to_bytes().to_vec())
+    };
+
     // Generate individual keys for each provider
     let provider_key_dirs = TrustedSetupCeremony::generate_individual_provider_keys(
         base_keys_dir.clone(),
+        &powers_of_tau,
+        &public_keys,
+        sign,
         &mut rng
     ).await?;
 
     // For backward compatibility, also create a "shared" transcript
     let mut ceremony = TrustedSetupCeremony::sp_5node_consortium_ceremony(base_keys_dir.clone());
-    let transcript = ceremony.run_ceremony(&mut rng).await?;
+    ceremony.set_powers_of_tau(powers_of_tau).await?;
+    for (provider, public_key) in &public_keys {
+        ceremony.register_participant_key(provider, *public_key);
+    }
+    let transcript = ceremony.run_ceremony(sign, &mut rng).await?;
 
     println!("✅ Ceremony completed successfully!");
     println!("📋 Ceremony ID: {}", transcript.ceremony_id);
@@ -50,6 +90,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📊 Ceremony Statistics:");
     println!("   • CDR Privacy Circuit: Keys generated and verified");
     println!("   • Settlement Calculation Circuit: Keys generated and verified");
+    println!("   • Block Total Circuit: Keys generated and verified");
 
     // Test key loading for each provider to ensure they're working
     println!("🧪 Testing individual key loading for each provider...");
@@ -66,6 +107,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let (_pk, _vk) = provider_ceremony.load_circuit_keys("settlement_calculation").await?;
             println!("🔑 Successfully loaded Settlement Calculation circuit keys for {}", provider_id);
         }
+
+        if provider_ceremony.keys_exist("rate_validation").await {
+            let (_pk, _vk) = provider_ceremony.load_circuit_keys("rate_validation").await?;
+            println!("🔑 Successfully loaded Rate Validation circuit keys for {}", provider_id);
+        }
+
+        if provider_ceremony.keys_exist("block_total").await {
+            let (_pk, _vk) = provider_ceremony.load_circuit_keys("block_total").await?;
+            println!("🔑 Successfully loaded Block Total circuit keys for {}", provider_id);
+        }
+
+        if provider_ceremony.keys_exist("balance_conservation").await {
+            let (_pk, _vk) = provider_ceremony.load_circuit_keys("balance_conservation").await?;
+            println!("🔑 Successfully loaded Balance Conservation circuit keys for {}", provider_id);
+        }
     }
 
     println!("🎉 Individual trusted setup complete! Each provider has their own keys.");
@@ -0,0 +1,118 @@
+// Conversion of record charges into the consortium settlement currency
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+use crate::simple_blockchain::DEFAULT_CURRENCY;
+
+#[derive(Error, Debug)]
+pub enum CurrencyError {
+    #[error("No exchange rate for {0}")]
+    UnknownCurrency(String),
+    #[error("Invalid exchange rate for {0}: {1}")]
+    InvalidRate(String, f64),
+    #[error("Converted amount out of range: {0}")]
+    Overflow(String),
+    #[error("Failed to load exchange rates: {0}")]
+    Load(String),
+}
+
+/// Converts minor-unit amounts into the settlement currency using a fixed rate table
+#[derive(Debug, Clone)]
+pub struct CurrencyConverter {
+    settlement_currency: String,
+    // Settlement-currency units per one unit of the keyed currency
+    rates: HashMap<String, f64>,
+}
+
+impl Default for CurrencyConverter {
+    fn default() -> Self {
+        Self::new(DEFAULT_CURRENCY)
+    }
+}
+
+impl CurrencyConverter {
+    /// Converter with no foreign rates; only the settlement currency is accepted
+    pub fn new(settlement_currency: &str) -> Self {
+        Self {
+            settlement_currency: settlement_currency.to_string(),
+            rates: HashMap::new(),
+        }
+    }
+
+    /// Converter with a rate table of settlement-currency units per foreign unit
+    pub fn with_rates(settlement_currency: &str, rates: HashMap<String, f64>) -> Result<Self, CurrencyError> {
+        if let Some((currency, rate)) = rates.iter().find(|(_, rate)| !rate.is_finite() || **rate <= 0.0) {
+            return Err(CurrencyError::InvalidRate(currency.clone(), *rate));
+        }
+
+        Ok(Self {
+            settlement_currency: settlement_currency.to_string(),
+            rates,
+        })
+    }
+
+    /// Load a JSON rate table such as `{"GBP": 1.17, "USD": 0.92}`
+    pub fn load_from_file(settlement_currency: &str, path: &Path) -> Result<Self, CurrencyError> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| CurrencyError::Load(format!("{}: {}", path.display(), e)))?;
+        let rates: HashMap<String, f64> = serde_json::from_str(&data)
+            .map_err(|e| CurrencyError::Load(format!("{}: {}", path.display(), e)))?;
+
+        Self::with_rates(settlement_currency, rates)
+    }
+
+    pub fn settlement_currency(&self) -> &str {
+        &self.settlement_currency
+    }
+
+    pub fn rates(&self) -> &HashMap<String, f64> {
+        &self.rates
+    }
+
+    /// Convert an amount in minor units of `currency` to settlement-currency minor units,
+    /// rounding to the nearest unit
+    pub fn convert_cents(&self, amount_cents: u32, currency: &str) -> Result<u32, CurrencyError> {
+        if currency == self.settlement_currency {
+            return Ok(amount_cents);
+        }
+
+        let rate = self.rates.get(currency)
+            .ok_or_else(|| CurrencyError::UnknownCurrency(currency.to_string()))?;
+        let converted = (amount_cents as f64 * rate).round();
+
+        if converted > u32::MAX as f64 {
+            return Err(CurrencyError::Overflow(format!("{} {} cents", amount_cents, currency)));
+        }
+        Ok(converted as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_currency_passthrough() {
+        let converter = CurrencyConverter::default();
+        assert_eq!(converter.convert_cents(12_345, "EUR").unwrap(), 12_345);
+        assert!(matches!(
+            converter.convert_cents(100, "GBP"),
+            Err(CurrencyError::UnknownCurrency(ref c)) if c == "GBP"
+        ));
+    }
+
+    #[test]
+    fn test_cross_currency_conversion() {
+        let converter = CurrencyConverter::with_rates(
+            "EUR",
+            HashMap::from([("GBP".to_string(), 1.17)]),
+        ).unwrap();
+
+        assert_eq!(converter.convert_cents(10_000, "GBP").unwrap(), 11_700);
+        // Rounded to the nearest cent
+        assert_eq!(converter.convert_cents(3, "GBP").unwrap(), 4);
+
+        assert!(CurrencyConverter::with_rates("EUR", HashMap::from([("USD".to_string(), 0.0)])).is_err());
+    }
+}
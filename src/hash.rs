@@ -1,6 +1,7 @@
 use blake2_rfc::blake2b::Blake2b;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use thiserror::Error;
 
 /// Simplified Blake2b hash (32 bytes) extracted from Albatross
 const BLAKE2B_LENGTH: usize = 32;
@@ -10,6 +11,14 @@ pub struct Blake2bHash([u8; BLAKE2B_LENGTH]);
 
 pub struct Blake2bHasher(Blake2b);
 
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum HashError {
+    #[error("Invalid hash length: expected {expected} hex characters, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Invalid hex digit in hash: {0}")]
+    InvalidHex(String),
+}
+
 impl Serialize for Blake2bHash {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -25,13 +34,7 @@ impl<'de> Deserialize<'de> for Blake2bHash {
         D: serde::Deserializer<'de>,
     {
         let hex_str = String::deserialize(deserializer)?;
-        let bytes = hex::decode(hex_str).map_err(serde::de::Error::custom)?;
-        if bytes.len() != BLAKE2B_LENGTH {
-            return Err(serde::de::Error::custom("Invalid hash length"));
-        }
-        let mut hash = [0u8; BLAKE2B_LENGTH];
-        hash.copy_from_slice(&bytes);
-        Ok(Blake2bHash(hash))
+        Blake2bHash::from_hex(&hex_str).map_err(serde::de::Error::custom)
     }
 }
 
@@ -52,11 +55,38 @@ impl Blake2bHash {
         &self.0
     }
 
+    /// Constant-time equality. Use this instead of `==` when comparing a recomputed
+    /// hash against an expected key hash, commitment or MAC: the derived `PartialEq`
+    /// returns at the first differing byte, so response timing would reveal how many
+    /// leading bytes an attacker-supplied value got right.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        let diff = self.0.iter()
+            .zip(other.0.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        std::hint::black_box(diff) == 0
+    }
+
+    /// Parse a hash from its 64-character hex encoding
+    pub fn from_hex(hex_str: &str) -> Result<Self, HashError> {
+        if hex_str.len() != BLAKE2B_LENGTH * 2 {
+            return Err(HashError::InvalidLength { expected: BLAKE2B_LENGTH * 2, actual: hex_str.len() });
+        }
+
+        let mut hash = [0u8; BLAKE2B_LENGTH];
+        hex::decode_to_slice(hex_str, &mut hash).map_err(|e| HashError::InvalidHex(e.to_string()))?;
+        Ok(Self(hash))
+    }
+
+    /// Lowercase hex encoding, the inverse of `from_hex`
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
     /// Hash data using Blake2b (simplified from Albatross)
     pub fn hash<T: AsRef<[u8]>>(data: T) -> Self {
         let mut hasher = Blake2bHasher::new();
         hasher.update(data.as_ref());
-        hasher.finish()
+        hasher.finalize()
     }
 
     pub fn len() -> usize {
@@ -69,16 +99,24 @@ impl Blake2bHasher {
         Blake2bHasher(Blake2b::new(BLAKE2B_LENGTH))
     }
 
+    /// Feed the next chunk of input; hashing input in chunks gives the same result as
+    /// `Blake2bHash::hash` over all of it, without holding it in memory at once
     pub fn update(&mut self, data: &[u8]) {
         self.0.update(data);
     }
 
-    pub fn finish(self) -> Blake2bHash {
+    pub fn finalize(self) -> Blake2bHash {
         let result = self.0.finalize();
         Blake2bHash::from(result.as_bytes())
     }
 }
 
+impl Default for Blake2bHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Default for Blake2bHash {
     fn default() -> Self {
         Self::new()
@@ -104,4 +142,61 @@ impl From<&[u8]> for Blake2bHash {
         hash[..len].copy_from_slice(&bytes[..len]);
         Self(hash)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = Blake2bHash::hash(b"settlement block");
+        let encoded = hash.to_hex();
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(encoded, hash.to_string());
+        assert_eq!(Blake2bHash::from_hex(&encoded).unwrap(), hash);
+
+        // Upper-case input parses to the same hash
+        assert_eq!(Blake2bHash::from_hex(&encoded.to_uppercase()).unwrap(), hash);
+    }
+
+    #[test]
+    fn test_ct_eq_matches_equality() {
+        let hash = Blake2bHash::hash(b"verifying key");
+        assert!(hash.ct_eq(&Blake2bHash::hash(b"verifying key")));
+        assert!(!hash.ct_eq(&Blake2bHash::hash(b"tampered key")));
+
+        // A difference in the final byte only is still detected
+        let mut bytes = *hash.as_bytes();
+        bytes[31] ^= 1;
+        assert!(!hash.ct_eq(&Blake2bHash::from_bytes(bytes)));
+    }
+
+    #[test]
+    fn test_from_hex_rejects_malformed_input() {
+        assert_eq!(
+            Blake2bHash::from_hex("abcd"),
+            Err(HashError::InvalidLength { expected: 64, actual: 4 })
+        );
+        assert!(matches!(Blake2bHash::from_hex(&"zz".repeat(32)), Err(HashError::InvalidHex(_))));
+
+        // Serde uses the same validation
+        assert!(serde_json::from_str::<Blake2bHash>("\"abcd\"").is_err());
+    }
+
+    #[test]
+    fn test_streaming_hash_matches_one_shot() {
+        let data: Vec<u8> = (0..10_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let expected = Blake2bHash::hash(&data);
+
+        for chunk_size in [1, 7, 128, 4096, data.len()] {
+            let mut hasher = Blake2bHasher::new();
+            for chunk in data.chunks(chunk_size) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), expected, "chunk size {}", chunk_size);
+        }
+
+        // No input hashes like empty input
+        assert_eq!(Blake2bHasher::new().finalize(), Blake2bHash::hash(b""));
+    }
+}
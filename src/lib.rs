@@ -1,4 +1,7 @@
 pub mod hash;
+pub mod merkle;
+pub mod currency;
+pub mod request_metrics;
 pub mod storage;
 pub mod simple_blockchain;
 pub mod network;
@@ -1,22 +1,30 @@
 use axum::{
-    extract::{Request, State},
-    http::StatusCode,
+    body::Body,
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
     middleware,
-    response::{Json, Html},
+    response::{Json, Html, Response, sse::{Event, KeepAlive, Sse}},
     routing::{get, post},
     Router,
 };
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio;
-use log::{info, error};
-
-use sp_blockchain::simple_blockchain::{SimpleBlockchain, BceRecord};
-use sp_blockchain::network::p2p::P2PNetwork;
+use log::{debug, info, warn, error};
+
+use sp_blockchain::simple_blockchain::{settlement_report_csv_header, SimpleBlockchain, BceRecord, BillingPeriodConfig, BlockchainError, ConsensusEvent, ContractExecutionRecord, ContractInfo, OperatorBalancePoint, OperatorPosition, SettlementBlock, DEFAULT_SNAPSHOT_BLOCKS, ProofReverification, RecordValidation, ResourceLimits, ResourceReport, SettlementStatus, SignaturePolicy, VerificationFailure};
+use sp_blockchain::network::consensus::{ConsensusSettings, RoundStatus, TieBreakRule};
+use sp_blockchain::network::DiscoveryMode;
+use sp_blockchain::network::p2p::{resolve_peer_address, P2PNetwork};
+use sp_blockchain::currency::CurrencyConverter;
+use sp_blockchain::storage::rocks_store::{PruneCheckpoint, RocksError};
+use sp_blockchain::request_metrics::{request_metrics_middleware, RequestMetrics, RequestMetricsSnapshot};
 use sp_blockchain::zkp::{FivePartySettlementFactory, SettlementProofSystem};
-use sp_blockchain::security::{SpAuthentication, middleware::{*, AuthenticatedSpExtension}};
+use sp_blockchain::zkp::settlement_proofs::ZkpError;
+use sp_blockchain::security::{SpAuthentication, SpCredentials, CredentialError, CredentialManager, AuditLog, AuditEntry, AuditQuery, middleware::{*, AuthenticatedSpExtension}};
 use sp_blockchain::smart_contracts::contract_api::{ContractAPI, SettlementRequest, RateValidationRequest, DisputeRequest};
 
 #[derive(Parser)]
@@ -29,22 +37,164 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Start {
+    /// Run the node: API server, P2P network and consensus
+    Start(NodeConfig),
+    /// Write the node's blocks and records to a newline-delimited JSON file
+    Export {
         #[arg(long, default_value = "./data")]
         data_dir: PathBuf,
-        #[arg(long, default_value = "8080")]
-        api_port: u16,
-        #[arg(long, default_value = "0.0.0.0")]
-        api_host: String,
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Load an exported chain into an empty data directory
+    Import {
+        #[arg(long, default_value = "./data")]
+        data_dir: PathBuf,
+        #[arg(long)]
+        input: PathBuf,
         #[arg(long, default_value = "sp-node")]
         node_id: String,
-        #[arg(long, default_value = "100.0")]
-        settlement_threshold_eur: f64,
-        #[arg(long, default_value = "30303")]
-        p2p_port: u16,
-        #[arg(long)]
-        bootstrap_peers: Option<String>,
     },
+    /// Record content digests for blocks written before block hashes were canonical, so
+    /// --verify-block-hashes can check them. Only run on a store known to be intact.
+    MigrateBlockHashes {
+        #[arg(long, default_value = "./data")]
+        data_dir: PathBuf,
+    },
+}
+
+// Settings of a running node, from the `start` command line. Not a doc comment, which
+// clap would show as the command's help text.
+#[derive(Args)]
+struct NodeConfig {
+    #[arg(long, default_value = "./data")]
+    data_dir: PathBuf,
+    #[arg(long, default_value = "8080")]
+    api_port: u16,
+    #[arg(long, default_value = "0.0.0.0")]
+    api_host: String,
+    #[arg(long, default_value = "sp-node")]
+    node_id: String,
+    #[arg(long, default_value = "100.0")]
+    settlement_threshold_eur: f64,
+    #[arg(long, default_value = "30303")]
+    p2p_port: u16,
+    /// Interface IP the P2P network binds to, e.g. an internal VLAN address
+    #[arg(long, default_value = "0.0.0.0")]
+    p2p_host: IpAddr,
+    #[arg(long)]
+    bootstrap_peers: Option<String>,
+    #[arg(long, default_value = "mdns")]
+    discovery: DiscoveryMode,
+    #[arg(long, default_value = "10")]
+    max_concurrent_rounds: usize,
+    #[arg(long, default_value = "lowest-hash")]
+    tie_break: TieBreakRule,
+    #[arg(long, default_value = "3")]
+    min_validators: usize,
+    /// Per-validator vote weights as id=weight, e.g. tmobile-de=5,vodafone-uk=2; unlisted validators weigh 1
+    #[arg(long, value_delimiter = ',')]
+    validator_weights: Vec<String>,
+    #[arg(long, default_value = "256")]
+    block_cache_size: usize,
+    #[arg(long, default_value = "5")]
+    key_backup_retention: usize,
+    #[arg(long, default_value = "2592000")]
+    billing_period_secs: u64,
+    #[arg(long, default_value = "259200")]
+    billing_grace_secs: u64,
+    #[arg(long, default_value = "300")]
+    max_clock_skew_secs: u64,
+    #[arg(long)]
+    require_proofs: bool,
+    #[arg(long, default_value = "1000000")]
+    high_value_threshold_cents: u32,
+    #[arg(long, default_value = "0")]
+    min_consortium_signatures: usize,
+    #[arg(long, default_value = "50")]
+    charge_tolerance_cents: u32,
+    #[arg(long, default_value = "100")]
+    max_proof_records: usize,
+    #[arg(long, default_value = "100000")]
+    max_pending_records: usize,
+    #[arg(long, default_value = "4")]
+    max_concurrent_proofs: usize,
+    #[arg(long, default_value = "16")]
+    max_queued_proofs_per_operator: usize,
+    #[arg(long, default_value = "64")]
+    record_tree_cache_size: usize,
+    /// BCE proofs reused for records with identical usage and rates. Reused proofs are
+    /// byte-identical and so link those records; 0 proves every record afresh.
+    #[arg(long, default_value = "1024")]
+    proof_cache_size: usize,
+    #[arg(long, default_value = "1000000")]
+    contract_gas_limit: u64,
+    #[arg(long, default_value = "EUR")]
+    settlement_currency: String,
+    #[arg(long)]
+    exchange_rates_file: Option<PathBuf>,
+    /// JSON object of consortium member to hex-encoded Ed25519 public key; record
+    /// signatures only verify against these keys
+    #[arg(long)]
+    consortium_keys_file: Option<PathBuf>,
+    #[arg(long)]
+    verify_block_hashes: bool,
+    /// Scan storage for missing blocks, broken links and dangling records before starting
+    #[arg(long)]
+    verify_on_start: bool,
+    #[arg(long)]
+    mock_zkp: bool,
+    #[arg(long)]
+    strict_zkp: bool,
+    /// Abandon a proof that takes longer than this and reject its record
+    #[arg(long, default_value = "60")]
+    proof_timeout_secs: u64,
+    #[arg(long, default_value = "0")]
+    pending_record_ttl_secs: u64,
+    #[arg(long, default_value = "86400")]
+    idempotency_window_secs: u64,
+    /// Blocks that must be finalized on top of a block before its records are settled
+    #[arg(long, default_value = "0")]
+    confirmation_depth: u64,
+    /// Origins allowed to call the API from a browser (comma separated). Same-origin only
+    /// when unset; `*` lets any website call the API and should not be used in production
+    #[arg(long, value_delimiter = ',')]
+    cors_allow_origin: Vec<String>,
+    /// JSON list of SP credentials whose `roles` grant e.g. the admin role; providers
+    /// not listed are operators
+    #[arg(long)]
+    credentials_file: Option<PathBuf>,
+}
+
+impl NodeConfig {
+    fn resource_limits(&self) -> ResourceLimits {
+        ResourceLimits {
+            max_pending_records: self.max_pending_records,
+            max_concurrent_proofs: self.max_concurrent_proofs,
+            max_queued_proofs_per_operator: self.max_queued_proofs_per_operator,
+            block_cache_size: self.block_cache_size,
+            record_tree_cache_size: self.record_tree_cache_size,
+            proof_cache_size: self.proof_cache_size,
+            max_concurrent_rounds: self.max_concurrent_rounds,
+            contract_gas_limit: self.contract_gas_limit,
+            max_proof_records: self.max_proof_records,
+        }
+    }
+
+    fn billing_period(&self) -> BillingPeriodConfig {
+        BillingPeriodConfig {
+            period_seconds: self.billing_period_secs,
+            grace_seconds: self.billing_grace_secs,
+            max_clock_skew_seconds: self.max_clock_skew_secs,
+        }
+    }
+
+    fn signature_policy(&self) -> SignaturePolicy {
+        SignaturePolicy {
+            high_value_threshold_cents: self.high_value_threshold_cents,
+            min_signatures: self.min_consortium_signatures,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -62,6 +212,10 @@ struct HealthResponse {
     records_processed: u32,
     total_blocks: usize,
     pending_records: usize,
+    peer_count: usize,
+    min_validators: usize,
+    can_reach_consensus: bool,
+    zkp_system_operational: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -71,6 +225,8 @@ struct StatsResponse {
     pending_records: usize,
     total_settlement_amount_eur: f64,
     last_block_time: Option<chrono::DateTime<chrono::Utc>>,
+    tip_height: u64,
+    finalized_height: u64,
 }
 
 struct AppState {
@@ -79,7 +235,10 @@ struct AppState {
     settlement_threshold_eur: f64,
     authentication: Arc<SpAuthentication>,
     zkp_system: Arc<SettlementProofSystem>,
+    // False when the node fell back to a placeholder proof system at startup
+    zkp_system_ready: bool,
     contract_api: Arc<ContractAPI>,
+    request_metrics: Arc<RequestMetrics>,
 }
 
 /// Deploy sample settlement smart contracts for demonstration
@@ -118,54 +277,122 @@ async fn deploy_sample_settlement_contracts(
     Ok(())
 }
 
+/// How long shutdown waits for open API connections, such as event streams, to close
+const SERVER_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How often records whose proof could not be recovered are tried again
+const PROOF_RECOVERY_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
+    // Records are followed through submit, proof, consensus and finalization spans;
+    // `log` output is forwarded into them. RUST_LOG takes filter directives such as
+    // `info,sp_blockchain::network=debug`, errors only by default.
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
 
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Start {
-            data_dir,
-            api_port,
-            api_host,
-            node_id,
-            settlement_threshold_eur,
-            p2p_port,
-            bootstrap_peers
-        } => {
-            start_node(
-                data_dir,
-                api_port,
-                api_host,
-                node_id,
-                settlement_threshold_eur,
-                p2p_port,
-                bootstrap_peers
+        Commands::Start(config) => {
+            start_node(config).await?;
+        }
+        Commands::Export { data_dir, output } => {
+            let (blockchain, _network_rx) = SimpleBlockchain::new(
+                &data_dir.to_string_lossy(), "export".to_string(), 0, 100.0,
             ).await?;
+            let file = std::io::BufWriter::new(std::fs::File::create(&output)?);
+            let summary = blockchain.export_chain(file).await?;
+            println!("📤 Exported {} blocks and {} records to {}", summary.blocks, summary.records, output.display());
+            println!("🔑 Export checksum: {}", summary.checksum);
+        }
+        Commands::Import { data_dir, input, node_id } => {
+            let (blockchain, _network_rx) = SimpleBlockchain::new(
+                &data_dir.to_string_lossy(), node_id, 0, 100.0,
+            ).await?;
+            let file = std::io::BufReader::new(std::fs::File::open(&input)?);
+            let summary = blockchain.import_chain(file).await?;
+            blockchain.shutdown().await?;
+            println!("📥 Imported {} blocks and {} records from {}", summary.blocks, summary.records, input.display());
+            println!("🔑 Import checksum: {} (matches the export checksum if the file is unchanged)", summary.checksum);
+        }
+        Commands::MigrateBlockHashes { data_dir } => {
+            let (blockchain, _network_rx) = SimpleBlockchain::new(
+                &data_dir.to_string_lossy(), "migrate".to_string(), 0, 100.0,
+            ).await?;
+            let recorded = blockchain.storage.record_legacy_block_digests()?;
+            blockchain.storage.set_verify_block_hashes(true);
+            let checked = blockchain.storage.verify_stored_blocks()?;
+            blockchain.shutdown().await?;
+            println!("🏷️  Recorded {} legacy block digests; {} blocks now verify", recorded, checked);
         }
     }
 
     Ok(())
 }
 
-async fn start_node(
-    data_dir: PathBuf,
-    api_port: u16,
-    api_host: String,
-    node_id: String,
-    settlement_threshold_eur: f64,
-    p2p_port: u16,
-    bootstrap_peers: Option<String>,
-) -> Result<(), Box<dyn std::error::Error>> {
+/// Parse `id=weight` validator weight entries from the command line
+fn parse_validator_weights(entries: &[String]) -> Result<std::collections::HashMap<String, u64>, String> {
+    let mut weights = std::collections::HashMap::new();
+    for entry in entries.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let (validator_id, weight) = entry.split_once('=')
+            .ok_or_else(|| format!("Invalid validator weight '{}': expected id=weight", entry))?;
+        let weight: u64 = weight.trim().parse()
+            .map_err(|_| format!("Invalid validator weight '{}': weight must be a whole number", entry))?;
+        if weight == 0 {
+            return Err(format!("Invalid validator weight '{}': weight must be at least 1", entry));
+        }
+        weights.insert(validator_id.trim().to_string(), weight);
+    }
+    Ok(weights)
+}
+
+async fn start_node(config: NodeConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let resource_limits = config.resource_limits();
+    let billing_period = config.billing_period();
+    let signature_policy = config.signature_policy();
+    let NodeConfig {
+        data_dir,
+        api_port,
+        api_host,
+        node_id,
+        settlement_threshold_eur,
+        p2p_port,
+        p2p_host,
+        bootstrap_peers,
+        discovery,
+        tie_break,
+        min_validators,
+        validator_weights,
+        key_backup_retention,
+        require_proofs,
+        charge_tolerance_cents,
+        settlement_currency,
+        exchange_rates_file,
+        consortium_keys_file,
+        verify_block_hashes,
+        verify_on_start,
+        mock_zkp,
+        strict_zkp,
+        proof_timeout_secs,
+        pending_record_ttl_secs,
+        idempotency_window_secs,
+        confirmation_depth,
+        cors_allow_origin,
+        credentials_file,
+        ..
+    } = config;
+
     println!("🚀 Starting SP BCE Node: {}", node_id);
     println!("💰 Settlement Threshold: {} EUR", settlement_threshold_eur);
     println!("🌐 API: {}:{}", api_host, api_port);
-    println!("📡 P2P: {}", p2p_port);
+    let p2p_listen_addr = SocketAddr::new(p2p_host, p2p_port);
+    println!("📡 P2P: {}", p2p_listen_addr);
 
     // Parse bootstrap peers
-    let peers = bootstrap_peers
-        .map(|p| p.split(',').map(|s| s.trim().to_string()).collect())
+    let peers: Vec<String> = bootstrap_peers
+        .map(|p| p.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
         .unwrap_or_else(Vec::new);
 
     // Initialize SP blockchain with RocksDB storage
@@ -176,45 +403,196 @@ async fn start_node(
         settlement_threshold_eur,
     ).await?;
 
+    // Bound proof pool, caches, pending backlog, consensus rounds and contract gas
+    info!("⚖️  Resource limits: {} pending records, {} concurrent proofs ({} queued per operator), {} concurrent rounds, {} gas per contract",
+          resource_limits.max_pending_records, resource_limits.max_concurrent_proofs, resource_limits.max_queued_proofs_per_operator,
+          resource_limits.max_concurrent_rounds, resource_limits.contract_gas_limit);
+    info!("🧱 Cache sizes: {} blocks, {} record trees, {} proofs; max {} records per inclusion-proof request",
+          resource_limits.block_cache_size, resource_limits.record_tree_cache_size, resource_limits.proof_cache_size,
+          resource_limits.max_proof_records);
+    blockchain.apply_resource_limits(resource_limits).await?;
+
+    // Every node must use the same rule to converge on competing same-height blocks
+    blockchain.set_tie_break_rule(tie_break).await;
+    info!("⚔️  Same-height tie-break rule: {:?}", tie_break);
+
+    // Small test/dev deployments need fewer votes than the full consortium to finalize
+    blockchain.set_min_validators(min_validators).await?;
+    let consensus_settings = blockchain.get_consensus_settings().await;
+    info!("🗳️  Consensus needs {} votes and {:.0}% approval ({} known validators)",
+          consensus_settings.min_validators, consensus_settings.approval_threshold * 100.0,
+          consensus_settings.known_validators);
+    if min_validators > consensus_settings.known_validators {
+        warn!("⚠️  min_validators exceeds the {} known validators - blocks can never finalize",
+              consensus_settings.known_validators);
+    }
+
+    // Weighted approval is measured against the weight of every active validator
+    let validator_weights = parse_validator_weights(&validator_weights)?;
+    if !validator_weights.is_empty() {
+        info!("🗳️  Validator weights: {:?}", validator_weights);
+        blockchain.set_validator_weights(validator_weights).await?;
+    }
+
+    // Keep the last N ZKP key backups taken before rotation/reload
+    blockchain.set_key_backup_retention(key_backup_retention)?;
+    info!("🗄️  ZKP key backups retained: {}", key_backup_retention);
+
+    // Records must fall within the active billing period
+    info!("📅 Billing period: {}s (grace {}s, max clock skew {}s)",
+          billing_period.period_seconds, billing_period.grace_seconds, billing_period.max_clock_skew_seconds);
+    blockchain.set_billing_period(billing_period)?;
+
+    // High-value records must be co-signed by enough consortium members
+    let min_signatures = signature_policy.min_signatures;
+    if min_signatures > 0 {
+        info!("✍️  Records of {} cents or more need {} consortium signatures",
+              signature_policy.high_value_threshold_cents, signature_policy.min_signatures);
+    }
+    blockchain.set_signature_policy(signature_policy)?;
+
+    // Consortium signatures are checked against each member's registered key
+    if let Some(path) = consortium_keys_file {
+        let mut crypto_verifier = (*blockchain.crypto_verifier).clone();
+        let loaded = crypto_verifier.load_member_keys_file(&path)?;
+        blockchain.crypto_verifier = Arc::new(crypto_verifier);
+        info!("🔏 Loaded {} consortium member signing keys from {}", loaded, path.display());
+    } else if min_signatures > 0 {
+        warn!("⚠️  No --consortium-keys-file: high-value records cannot be co-signed");
+    }
+
+    // Detect on-disk corruption or tampering of stored blocks
+    if verify_block_hashes {
+        blockchain.storage.set_verify_block_hashes(true);
+        // A chain with a block that does not verify is not served at all
+        let checked = blockchain.storage.verify_stored_blocks()?;
+        info!("🔍 Verified the hashes of {} stored blocks; blocks are re-verified when loaded from storage", checked);
+    }
+
+    // Refuse to serve a chain with missing blocks, broken links or missing records
+    if verify_on_start {
+        let report = blockchain.storage.verify_integrity()?;
+        if !report.is_consistent() {
+            for (expected, found) in &report.gaps {
+                error!("🚨 Storage gap: expected block #{}, found #{}", expected, found);
+            }
+            for block_number in &report.broken_links {
+                error!("🚨 Block #{} does not link to the block before it", block_number);
+            }
+            for (block_number, record_id) in &report.dangling_records {
+                error!("🚨 Block #{} references missing record {}", block_number, record_id);
+            }
+            return Err(format!(
+                "storage integrity check failed: {} gaps, {} broken links, {} dangling records",
+                report.gaps.len(), report.broken_links.len(), report.dangling_records.len()
+            ).into());
+        }
+        info!("🩺 Storage integrity verified across {} blocks", report.blocks_checked);
+    }
+
+    // Placeholder proofs are a development aid only; production nodes fail instead
+    blockchain.set_mock_zkp(mock_zkp);
+    blockchain.set_strict_zkp(strict_zkp);
+    if mock_zkp && strict_zkp {
+        warn!("⚠️  --strict-zkp overrides --mock-zkp: records without a real proof are rejected");
+    }
+    blockchain.set_proof_timeout(std::time::Duration::from_secs(proof_timeout_secs))?;
+    info!("⏱️ Proof generation timeout: {}s", proof_timeout_secs);
+
+    // Records that never reach a settlement block are expired instead of lingering forever
+    blockchain.set_pending_record_ttl(pending_record_ttl_secs);
+    if pending_record_ttl_secs > 0 {
+        info!("⌛ Pending records expire after {}s", pending_record_ttl_secs);
+    }
+
+    // Retried submissions carrying the same Idempotency-Key are answered from storage
+    blockchain.set_idempotency_window(idempotency_window_secs)?;
+    info!("🔁 Idempotency keys remembered for {}s", idempotency_window_secs);
+
+    // Records settle only once their block is buried under enough later blocks
+    blockchain.set_confirmation_depth(confirmation_depth);
+    let caught_up = blockchain.settle_confirmed_blocks().await?;
+    if caught_up > 0 {
+        info!("🏁 Settled {} records of blocks already below the finalized height", caught_up);
+    }
+
+    // Accepted difference between calculated and stated wholesale charges
+    blockchain.set_charge_tolerance_cents(charge_tolerance_cents);
+    info!("🧮 Charge validation tolerance: {} cents", charge_tolerance_cents);
+
+    // Charges are converted to the settlement currency using a rate table loaded once
+    let currency_converter = match exchange_rates_file {
+        Some(path) => CurrencyConverter::load_from_file(&settlement_currency, &path)?,
+        None => CurrencyConverter::new(&settlement_currency),
+    };
+    info!("💱 Settlement currency: {} ({} exchange rates loaded)",
+          settlement_currency, currency_converter.rates().len());
+    blockchain.set_currency_converter(currency_converter);
+
     // Load pre-generated ZKP keys for 5-party consortium
     let zkp_keys_dir = std::path::PathBuf::from("/app/zkp_keys");
 
     if zkp_keys_dir.exists() && zkp_keys_dir.join("ceremony_transcript.json").exists() {
         if let Err(e) = blockchain.load_zkp_keys(zkp_keys_dir.clone()).await {
-            println!("⚠️  Failed to load ZKP keys: {}", e);
-            println!("💡 Run 'cargo run --bin trusted-setup-demo' first to generate keys");
+            warn!("⚠️  Failed to load ZKP keys: {}", e);
+            warn!("💡 Run 'cargo run --bin trusted-setup-demo' first to generate keys");
         } else {
-            println!("🔐 ZKP keys loaded successfully for 5-party consortium");
-            println!("✅ Ready for zero-knowledge proof generation and verification");
+            info!("🔐 ZKP keys loaded successfully for 5-party consortium");
+            info!("✅ Ready for zero-knowledge proof generation and verification");
         }
     } else {
-        println!("⚠️  ZKP keys not found at: {:?}", zkp_keys_dir);
-        println!("💡 Run 'cargo run --bin trusted-setup-demo' first to generate keys");
+        warn!("⚠️  ZKP keys not found at: {:?}", zkp_keys_dir);
+        warn!("💡 Run 'cargo run --bin trusted-setup-demo' first to generate keys");
     }
 
     // Initialize ZKP system for privacy-preserving settlement proofs
-    println!("🛡️  Initializing Zero-Knowledge Proof system...");
-    let zkp_system = match SettlementProofSystem::new(&node_id) {
+    info!("🛡️  Initializing Zero-Knowledge Proof system...");
+    let (zkp_system, zkp_system_ready) = match SettlementProofSystem::new(&node_id) {
         Ok(system) => {
-            println!("✅ ZKP system initialized successfully");
-            Arc::new(system)
+            info!("✅ ZKP system initialized successfully");
+            (Arc::new(system), true)
         }
         Err(e) => {
             error!("❌ Failed to initialize ZKP system: {}", e);
-            println!("⚠️  Warning: ZKP system disabled - settlement proofs will not be generated");
-            println!("   System will continue with basic functionality");
+            warn!("⚠️  Warning: ZKP system disabled - settlement proofs will not be generated");
+            warn!("   System will continue with basic functionality");
             // Create a fallback system that won't panic the application
-            Arc::new(SettlementProofSystem::default())
+            (Arc::new(SettlementProofSystem::default()), false)
         }
     };
 
-    // Set the settlement proof system for ZKP integration
-    blockchain.set_settlement_proof_system(zkp_system.clone());
-    println!("🛡️  Settlement proof system integrated into blockchain");
+    // Set the settlement proof system for ZKP integration. The fallback system
+    // cannot verify anything, so under a proof-required policy it is not used
+    // and submissions are rejected with 503 instead.
+    blockchain.set_require_proofs(require_proofs);
+    if zkp_system_ready || !require_proofs {
+        blockchain.set_settlement_proof_system(zkp_system.clone());
+        info!("🛡️  Settlement proof system integrated into blockchain");
+    } else {
+        warn!("⛔ Proofs required but ZKP system unavailable - BCE submissions will be rejected");
+    }
 
     // Initialize P2P network
-    let mut p2p_network = P2PNetwork::new(node_id.clone(), p2p_port).await
+    let mut p2p_network = P2PNetwork::with_discovery(node_id.clone(), p2p_listen_addr, discovery).await
         .map_err(|e| -> Box<dyn std::error::Error> { e })?;
+    info!("🧭 Peer discovery: {:?}", discovery);
+
+    // Dial the configured peers; discovery finds the rest of the mesh from them
+    for peer in &peers {
+        match resolve_peer_address(peer).await {
+            Ok(addr) => {
+                if let Err(e) = p2p_network.add_peer(addr.clone()) {
+                    warn!("⚠️  Failed to dial bootstrap peer {} ({}): {}", peer, addr, e);
+                } else {
+                    info!("🔗 Dialing bootstrap peer {} ({})", peer, addr);
+                }
+            }
+            Err(e) => warn!("⚠️  Could not resolve bootstrap peer {}: {}", peer, e),
+        }
+    }
+    if discovery == DiscoveryMode::Kademlia && peers.is_empty() {
+        warn!("⚠️  Kademlia discovery without --bootstrap-peers only finds nodes that dial us");
+    }
 
     // Connect P2P network to blockchain message handler (incoming messages)
     p2p_network.set_message_callback(blockchain.network_tx.clone());
@@ -225,12 +603,87 @@ async fn start_node(
     // Set P2P sender on blockchain for outbound messages
     blockchain.set_p2p_sender(p2p_message_sender);
 
-    println!("✅ SP Blockchain with ZKP initialized successfully");
-    println!("🌐 P2P Network initialized and connected");
+    // Health reporting reads the live peer count from the network
+    blockchain.set_peer_count_handle(p2p_network.peer_count_handle());
+    blockchain.set_peers_configured(!peers.is_empty());
+
+    info!("✅ SP Blockchain with ZKP initialized successfully");
+    info!("🌐 P2P Network initialized and connected");
 
     // Clone blockchain for message processing before moving into AppState
     let blockchain = Arc::new(blockchain);
     let blockchain_for_messages = blockchain.clone();
+    let blockchain_for_shutdown = blockchain.clone();
+
+    // Flipped once on shutdown; every background task stops when it sees it
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let mut background_tasks = Vec::new();
+
+    // Periodically expire pending records older than the TTL
+    if pending_record_ttl_secs > 0 {
+        let blockchain = blockchain.clone();
+        let mut shutdown = shutdown_rx.clone();
+        let check_every = std::time::Duration::from_secs((pending_record_ttl_secs / 4).clamp(1, 60));
+        background_tasks.push(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(check_every);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.changed() => break,
+                }
+                if let Err(e) = blockchain.expire_stale_pending_records(chrono::Utc::now().timestamp() as u64).await {
+                    error!("❌ Failed to expire stale pending records: {}", e);
+                }
+            }
+        }));
+    }
+
+    // A crash between storing a record and proving it leaves the record unproven; prove such
+    // records in the background and retry the ones that fail until every one is proven
+    {
+        let blockchain = blockchain.clone();
+        let mut shutdown = shutdown_rx.clone();
+        background_tasks.push(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(PROOF_RECOVERY_RETRY_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.changed() => break,
+                }
+                match blockchain.recover_unproven_records().await {
+                    Ok(recovery) if recovery.failed.is_empty() => {
+                        if recovery.unproven > 0 {
+                            info!("🩹 Recovered proofs for {}/{} unproven records", recovery.proven, recovery.unproven);
+                        }
+                        break;
+                    }
+                    Ok(recovery) => {
+                        warn!("⚠️  {} records are still unproven, retrying in {:?}: {:?}",
+                              recovery.failed.len(), PROOF_RECOVERY_RETRY_INTERVAL, recovery.failed);
+                    }
+                    Err(e) => error!("❌ Failed to scan for unproven records: {}", e),
+                }
+            }
+        }));
+    }
+
+    // Periodically drop idempotency keys older than the window
+    {
+        let blockchain = blockchain.clone();
+        let mut shutdown = shutdown_rx.clone();
+        background_tasks.push(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(3600));
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.changed() => break,
+                }
+                if let Err(e) = blockchain.purge_expired_idempotency_keys(chrono::Utc::now().timestamp() as u64).await {
+                    error!("❌ Failed to purge expired idempotency keys: {}", e);
+                }
+            }
+        }));
+    }
 
     // Sample contract deployment disabled for clean demo
     // tokio::spawn({
@@ -243,12 +696,26 @@ async fn start_node(
     // });
 
     // Initialize SP authentication system for the 5-party consortium
-    let authentication = Arc::new(SpAuthentication::new_consortium());
-    println!("🔐 SP Authentication system initialized for 5-party consortium");
+    // Roles and rotated signing keys persist in the data directory across restarts
+    let mut credential_manager = CredentialManager::open(data_dir.join("sp_credentials.json"))?;
+    if let Some(path) = credentials_file {
+        let credentials: Vec<SpCredentials> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        credential_manager.load_credentials(credentials);
+        credential_manager.save()?;
+        info!("🎭 SP credentials loaded from {}", path.display());
+    }
+    let mut authentication = SpAuthentication::new_consortium();
+    authentication.set_credential_manager(credential_manager);
+    authentication.set_audit_log(AuditLog::open(data_dir.join("audit_log.jsonl"))?);
+    let authentication = Arc::new(authentication);
+    info!("🔐 SP Authentication system initialized for 5-party consortium");
 
     // Initialize Contract API for smart contract management using existing blockchain
     let contract_api = Arc::new(ContractAPI::with_blockchain(blockchain.clone()));
-    println!("📋 ZKP Smart Contract API initialized with existing blockchain");
+    info!("📋 ZKP Smart Contract API initialized with existing blockchain");
+
+    // Per-endpoint request counts and latencies, shared between the middleware and the API
+    let request_metrics = Arc::new(RequestMetrics::new());
 
     // Create app state
     let state = AppState {
@@ -257,7 +724,9 @@ async fn start_node(
         settlement_threshold_eur,
         authentication: authentication.clone(),
         zkp_system,
+        zkp_system_ready,
         contract_api,
+        request_metrics: request_metrics.clone(),
     };
 
     // Build API routes with security middleware
@@ -265,12 +734,26 @@ async fn start_node(
     // So authorization_middleware runs first, then auth_middleware
     let protected_routes = Router::new()
         .route("/api/v1/bce/submit", post(submit_bce_record))
+        .route("/api/v1/bce/submit_batch", post(submit_bce_batch))
+        .route("/api/v1/bce/validate", post(validate_bce_record))
         .route("/api/v1/bce/stats", get(get_stats))
         .route("/api/v1/blockchain/blocks", get(get_blocks))
+        .route("/api/v1/blockchain/blocks/:number", get(get_block_by_number))
+        .route("/api/v1/blockchain/blocks/by_hash/:hash", get(get_block_by_hash))
         .route("/api/v1/blockchain/stats", get(get_blockchain_stats))
+        .route("/api/v1/snapshot", get(get_snapshot))
+        .route("/api/v1/blockchain/stream", get(stream_finalized_blocks))
+        .route("/api/v1/ws", get(consensus_events_ws))
+        .route("/api/v1/blockchain/block/:hash/provenance", get(get_block_provenance))
+        .route("/api/v1/blockchain/block/:hash/balance_proof/:operator", get(get_balance_proof))
+        .route("/api/v1/blockchain/verify_balance_proof", post(verify_balance_proof))
+        .route("/api/v1/blockchain/block/:hash/record_proofs", post(get_record_proofs))
+        .route("/api/v1/blockchain/state_root", get(get_state_root))
+        .route("/api/v1/blockchain/state_proof/:operator", get(get_state_proof))
         .route("/api/v1/zkp/stats", get(get_zkp_stats))
         .route("/api/v1/zkp/generate_proof", post(generate_zkp_proof))
         .route("/api/v1/zkp/verify_proof", post(verify_zkp_proof))
+        .route("/api/v1/zkp/batch_verify", post(batch_verify_zkp_proofs))
         .route("/api/v1/zkp/system_status", get(get_zkp_system_status))
         .route("/api/v1/zkp/setup_info", get(get_zkp_setup_info))
         .route("/api/v1/zkp/metrics", get(get_zkp_metrics))
@@ -278,31 +761,78 @@ async fn start_node(
         .route("/api/v1/zkp/health", get(get_zkp_health_check))
         .route("/api/v1/zkp/reset_metrics", post(reset_zkp_metrics))
         .route("/api/v1/zkp/test_integration", post(test_zkp_integration))
+        .route("/api/v1/zkp/verification_failures", get(get_verification_failures))
+        .route("/api/v1/zkp/reverify/:record_id", post(reverify_record_proof))
+        .route("/api/v1/zkp/keys/backups", get(list_zkp_key_backups))
+        .route("/api/v1/zkp/keys/restore", post(restore_zkp_keys))
+        .route("/api/v1/zkp/keys/reload", post(reload_zkp_keys))
         .route("/api/v1/read/bce_records", get(get_bce_records))
+        .route("/api/v1/read/bce_records/:record_id/settlement", get(get_record_settlement))
         .route("/api/v1/read/settlement_blocks", get(get_settlement_blocks))
+        .route("/api/v1/read/operator/:operator/position", get(get_operator_position))
+        .route("/api/v1/read/operator/:operator/history", get(get_operator_history))
+        .route("/api/v1/read/settlement_report.csv", get(export_settlement_report_csv))
         .route("/api/v1/contracts/deploy", post(deploy_smart_contract))
         .route("/api/v1/contracts/list", get(list_smart_contracts))
         .route("/api/v1/contracts/execute", post(execute_smart_contract))
         .route("/api/v1/contracts/stats", get(get_contract_stats))
+        .route("/api/v1/contracts/:address", get(get_smart_contract))
+        .route("/api/v1/contracts/:address/executions", get(get_contract_executions))
+        .route("/api/v1/admin/resources", get(get_resources))
+        .route("/api/v1/admin/metrics", get(get_request_metrics))
+        .route("/api/v1/admin/create_block", post(force_create_block))
+        .route("/api/v1/admin/prune", get(get_prune_checkpoint).post(prune_blocks))
+        .route("/api/v1/admin/reindex", post(rebuild_derived_state))
+        .route("/api/v1/admin/credentials/:provider_id/rotate", post(rotate_credential))
+        .route("/api/v1/admin/audit_log", get(get_audit_log))
+        .route("/api/v1/consensus/status", get(get_consensus_status))
+        .route("/api/v1/consensus/config", get(get_consensus_config))
         .route("/dashboard", get(dashboard_handler))
         .layer(middleware::from_fn(authorization_middleware))
         .layer(middleware::from_fn_with_state(authentication.clone(), auth_middleware));
 
+    // Outermost so preflight requests are answered before authentication
+    let cors = cors_layer(&cors_allow_origin)?;
+    if cors_allow_origin.is_empty() {
+        info!("🌍 CORS: same-origin only");
+    } else {
+        info!("🌍 CORS allowed origins: {}", cors_allow_origin.join(", "));
+    }
+
     let app = Router::new()
         .route("/health", get(health_handler))
         .merge(protected_routes)
+        .layer(middleware::from_fn_with_state(request_metrics, request_metrics_middleware))
         .layer(middleware::from_fn(security_headers_middleware))
+        .layer(cors)
         .with_state(Arc::new(state));
 
     // Start all services concurrently
     let listener = tokio::net::TcpListener::bind(format!("{}:{}", api_host, api_port)).await?;
-    println!("🎯 API server listening on {}:{}", api_host, api_port);
+    info!("🎯 API server listening on {}:{}", api_host, api_port);
+
+    // API Server: stops accepting connections on shutdown and lets in-flight requests finish
+    let mut server_task = {
+        let mut shutdown = shutdown_rx.clone();
+        let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async move {
+                let _ = shutdown.changed().await;
+            });
+        tokio::spawn(async move { server.await })
+    };
+
+    // Blockchain Message Processing
+    let mut message_task = tokio::spawn(handle_blockchain_messages(blockchain_for_messages, network_rx, shutdown_rx));
 
+    let mut server_stopped = false;
+    let mut messages_stopped = false;
     tokio::select! {
-        // API Server
-        result = axum::serve(listener, app) => {
-            if let Err(e) = result {
-                error!("API server error: {}", e);
+        result = &mut server_task => {
+            server_stopped = true;
+            match result {
+                Ok(Err(e)) => error!("API server error: {}", e),
+                Err(e) => error!("API server task failed: {}", e),
+                Ok(Ok(())) => {}
             }
         }
 
@@ -313,14 +843,57 @@ async fn start_node(
             }
         }
 
-        // Blockchain Message Processing
-        result = handle_blockchain_messages(blockchain_for_messages, network_rx) => {
+        result = &mut message_task => {
+            messages_stopped = true;
             if let Err(e) = result {
-                error!("Blockchain message processing error: {}", e);
+                error!("Blockchain message processing task failed: {}", e);
+            }
+        }
+
+        // Ctrl-C
+        result = tokio::signal::ctrl_c() => {
+            match result {
+                Ok(()) => info!("🛑 Shutdown signal received"),
+                Err(e) => error!("Failed to listen for shutdown signal: {}", e),
             }
         }
     }
 
+    // Stop the API server and background loops before touching the blockchain
+    let _ = shutdown_tx.send(true);
+    if !server_stopped {
+        // Streaming connections never finish on their own, so don't wait for them forever
+        match tokio::time::timeout(SERVER_SHUTDOWN_TIMEOUT, &mut server_task).await {
+            Ok(Ok(Err(e))) => error!("API server error: {}", e),
+            Ok(Err(e)) => error!("API server task failed: {}", e),
+            Ok(Ok(Ok(()))) => info!("🔌 API server stopped"),
+            Err(_) => {
+                warn!("⚠️  API server connections still open after {:?}, closing them", SERVER_SHUTDOWN_TIMEOUT);
+                server_task.abort();
+            }
+        }
+    }
+    if !messages_stopped {
+        if let Err(e) = message_task.await {
+            error!("Blockchain message processing task failed: {}", e);
+        }
+    }
+    for task in background_tasks {
+        if let Err(e) = task.await {
+            error!("Background task failed: {}", e);
+        }
+    }
+
+    // Nothing else touches the blockchain now; drain finalizations and flush storage
+    if let Err(e) = blockchain_for_shutdown.shutdown().await {
+        error!("Failed to shut down blockchain cleanly: {}", e);
+    }
+
+    // Dropping the swarm closes all peer connections
+    drop(p2p_network);
+    info!("🔌 P2P network closed");
+
+    info!("✅ Shutdown complete");
     Ok(())
 }
 
@@ -328,42 +901,59 @@ async fn start_node(
 async fn handle_blockchain_messages(
     blockchain: Arc<SimpleBlockchain>,
     mut network_rx: tokio::sync::mpsc::UnboundedReceiver<sp_blockchain::network::NetworkMessage>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    println!("📨 Starting blockchain message processing loop");
-
-    while let Some(message) = network_rx.recv().await {
+    mut shutdown: tokio::sync::watch::Receiver<bool>,
+) {
+    info!("📨 Starting blockchain message processing loop");
+
+    loop {
+        let message = tokio::select! {
+            message = network_rx.recv() => message,
+            _ = shutdown.changed() => break,
+        };
+        let Some(message) = message else {
+            break;
+        };
         if let Err(e) = blockchain.handle_network_message(message).await {
             error!("Failed to process network message: {}", e);
         }
     }
 
-    Ok(())
+    info!("📨 Blockchain message processing loop stopped");
 }
 
+/// Node health; 503 when the node cannot finalize blocks or has lost all configured peers
 async fn health_handler(
     State(state): State<Arc<AppState>>
-) -> Result<Json<HealthResponse>, StatusCode> {
+) -> Result<(StatusCode, Json<HealthResponse>), StatusCode> {
     let stats = match state.blockchain.get_stats().await {
         Ok(stats) => stats,
         Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
     };
+    let network = state.blockchain.get_network_health().await;
+    let healthy = network.can_reach_consensus && !network.partitioned;
 
     let response = HealthResponse {
-        status: "healthy".to_string(),
+        status: if healthy { "healthy" } else { "unhealthy" }.to_string(),
         node_id: state.node_id.clone(),
         settlement_threshold_eur: state.settlement_threshold_eur,
         records_processed: stats.total_records,
         total_blocks: stats.total_blocks,
         pending_records: stats.pending_records,
+        peer_count: network.peer_count,
+        min_validators: network.min_validators,
+        can_reach_consensus: network.can_reach_consensus,
+        zkp_system_operational: state.zkp_system_ready && state.zkp_system.is_operational(),
     };
 
-    Ok(Json(response))
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    Ok((status, Json(response)))
 }
 
 async fn submit_bce_record(
     State(state): State<Arc<AppState>>,
     authenticated_sp: AuthenticatedSpExtension,
-    Json(record): Json<BceRecord>,
+    headers: HeaderMap,
+    Json(mut record): Json<BceRecord>,
 ) -> Result<Json<ApiResponse<String>>, StatusCode> {
     info!("📝 Received BCE record submission: {} from SP: {}",
           record.record_id, authenticated_sp.0.provider_name);
@@ -381,7 +971,28 @@ async fn submit_bce_record(
     info!("✅ SP {} authorized to submit record as visited network: {}",
           authenticated_sp.0.provider_id, record.visited_operator);
 
-    match state.blockchain.submit_bce_record(record).await {
+    // Attribute the record to the authenticated submitter for provenance
+    record.submitter_id = Some(authenticated_sp.0.provider_id.clone());
+
+    // Optional Idempotency-Key lets clients safely retry a submission of unknown outcome
+    let idempotency_key = match headers.get("idempotency-key").map(|value| value.to_str()) {
+        Some(Ok(key)) if !key.trim().is_empty() => Some(key.trim().to_string()),
+        Some(_) => {
+            return Ok(Json(ApiResponse {
+                success: false,
+                data: None,
+                message: "Invalid Idempotency-Key header".to_string(),
+            }));
+        }
+        None => None,
+    };
+
+    let result = match idempotency_key {
+        Some(key) => state.blockchain.submit_bce_record_idempotent(record, &key).await,
+        None => state.blockchain.submit_bce_record(record).await,
+    };
+
+    match result {
         Ok(record_id) => {
             info!("✅ BCE record processed successfully: {}", record_id);
             Ok(Json(ApiResponse {
@@ -390,6 +1001,26 @@ async fn submit_bce_record(
                 message: "BCE record stored and broadcasted to validators".to_string(),
             }))
         }
+        Err(BlockchainError::ProofSystemUnavailable(reason)) => {
+            error!("❌ Rejecting BCE record, proof verification unavailable: {}", reason);
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(BlockchainError::ResourceExhausted(reason)) => {
+            error!("❌ Rejecting BCE record, node at capacity: {}", reason);
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(BlockchainError::ProofQueueFull(reason)) => {
+            error!("❌ Rejecting BCE record, submitter is over its proof quota: {}", reason);
+            Err(StatusCode::TOO_MANY_REQUESTS)
+        }
+        Err(BlockchainError::Proof(e @ ZkpError::Timeout(_))) => {
+            error!("❌ Rejecting BCE record: {}", e);
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
+        Err(BlockchainError::ShuttingDown) => {
+            error!("❌ Rejecting BCE record, node is shutting down");
+            Err(StatusCode::SERVICE_UNAVAILABLE)
+        }
         Err(e) => {
             error!("❌ Failed to process BCE record: {}", e);
             Ok(Json(ApiResponse {
@@ -401,6 +1032,104 @@ async fn submit_bce_record(
     }
 }
 
+async fn validate_bce_record(
+    State(state): State<Arc<AppState>>,
+    authenticated_sp: AuthenticatedSpExtension,
+    Json(mut record): Json<BceRecord>,
+) -> Result<Json<ApiResponse<RecordValidation>>, StatusCode> {
+    info!("🛫 Received BCE record pre-flight validation: {} from SP: {}",
+          record.record_id, authenticated_sp.0.provider_name);
+
+    // Same authorization as a real submission (SP must be the visited network)
+    let unauthorized = state.authentication
+        .authorize_bce_submission(&authenticated_sp.0, &record.visited_operator)
+        .err()
+        .map(|e| format!("Authorization failed: {}", e));
+
+    record.submitter_id = Some(authenticated_sp.0.provider_id.clone());
+
+    let validation = state.blockchain.validate_without_submitting(&record, unauthorized).await;
+    let message = match &validation.rejection {
+        None => "BCE record would be accepted".to_string(),
+        Some(reason) => format!("BCE record would be rejected: {}", reason),
+    };
+    Ok(Json(ApiResponse {
+        success: validation.accepted,
+        data: Some(validation),
+        message,
+    }))
+}
+
+#[derive(Debug, Serialize)]
+struct BatchRecordResult {
+    record_id: String,
+    success: bool,
+    error: Option<String>,
+}
+
+async fn submit_bce_batch(
+    State(state): State<Arc<AppState>>,
+    authenticated_sp: AuthenticatedSpExtension,
+    Json(records): Json<Vec<BceRecord>>,
+) -> Result<Json<ApiResponse<Vec<BatchRecordResult>>>, StatusCode> {
+    info!("📦 Received batch of {} BCE records from SP: {}",
+          records.len(), authenticated_sp.0.provider_name);
+
+    // Authorize every record up front (SP must be the visited network for each one)
+    let mut results: Vec<Option<BatchRecordResult>> = Vec::with_capacity(records.len());
+    let mut authorized = Vec::new();
+    for (index, mut record) in records.into_iter().enumerate() {
+        match state.authentication.authorize_bce_submission(&authenticated_sp.0, &record.visited_operator) {
+            Ok(()) => {
+                record.submitter_id = Some(authenticated_sp.0.provider_id.clone());
+                authorized.push((index, record));
+                results.push(None);
+            }
+            Err(e) => {
+                error!("❌ Authorization failed for SP {} on record {}: {}",
+                       authenticated_sp.0.provider_id, record.record_id, e);
+                results.push(Some(BatchRecordResult {
+                    record_id: record.record_id,
+                    success: false,
+                    error: Some(format!("Authorization failed: {}", e)),
+                }));
+            }
+        }
+    }
+
+    let (indices, authorized_records): (Vec<usize>, Vec<BceRecord>) = authorized.into_iter().unzip();
+    let record_ids: Vec<String> = authorized_records.iter().map(|r| r.record_id.clone()).collect();
+
+    let outcomes = match state.blockchain.submit_bce_records(authorized_records).await {
+        Ok(outcomes) => outcomes,
+        Err(e) => {
+            error!("❌ Failed to process BCE batch: {}", e);
+            return Ok(Json(ApiResponse {
+                success: false,
+                data: None,
+                message: format!("Failed to process BCE batch: {}", e),
+            }));
+        }
+    };
+
+    for ((index, record_id), outcome) in indices.into_iter().zip(record_ids).zip(outcomes) {
+        results[index] = Some(match outcome {
+            Ok(record_id) => BatchRecordResult { record_id, success: true, error: None },
+            Err(e) => BatchRecordResult { record_id, success: false, error: Some(e.to_string()) },
+        });
+    }
+
+    let results: Vec<BatchRecordResult> = results.into_iter().flatten().collect();
+    let accepted = results.iter().filter(|r| r.success).count();
+    info!("✅ BCE batch processed: {}/{} records accepted", accepted, results.len());
+
+    Ok(Json(ApiResponse {
+        success: accepted == results.len(),
+        message: format!("{} of {} BCE records accepted", accepted, results.len()),
+        data: Some(results),
+    }))
+}
+
 async fn get_stats(
     State(state): State<Arc<AppState>>
 ) -> Result<Json<ApiResponse<StatsResponse>>, StatusCode> {
@@ -415,6 +1144,8 @@ async fn get_stats(
         pending_records: stats.pending_records,
         total_settlement_amount_eur: stats.total_settlement_amount_cents as f64 / 100.0,
         last_block_time: stats.last_block_time,
+        tip_height: stats.tip_height,
+        finalized_height: stats.finalized_height,
     };
 
     Ok(Json(ApiResponse {
@@ -424,6 +1155,16 @@ async fn get_stats(
     }))
 }
 
+fn block_summary(block: &SettlementBlock) -> serde_json::Value {
+    serde_json::json!({
+        "block_number": block.block_number,
+        "block_hash": hex::encode(block.block_hash.as_bytes()),
+        "timestamp": block.timestamp,
+        "records_count": block.record_count,
+        "total_amount_cents": block.settlement_summary.total_amount_cents,
+    })
+}
+
 async fn get_blocks(
     State(state): State<Arc<AppState>>
 ) -> Result<Json<ApiResponse<Vec<serde_json::Value>>>, StatusCode> {
@@ -433,50 +1174,600 @@ async fn get_blocks(
     };
 
     // Convert blocks to JSON for API response
-    let block_summaries: Vec<serde_json::Value> = blocks.iter().map(|block| {
-        serde_json::json!({
-            "block_number": block.block_number,
-            "block_hash": hex::encode(block.block_hash.as_bytes()),
-            "timestamp": block.timestamp,
-            "records_count": block.record_count,
-            "total_amount_cents": block.settlement_summary.total_amount_cents,
-        })
-    }).collect();
+    let block_summaries: Vec<serde_json::Value> = blocks.iter().map(block_summary).collect();
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(block_summaries),
+        message: "Blocks retrieved successfully".to_string(),
+    }))
+}
+
+/// Most recent blocks a snapshot embeds, however many are asked for
+const MAX_SNAPSHOT_BLOCKS: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct SnapshotQuery {
+    blocks: Option<usize>,
+}
+
+/// Stats, recent blocks, ZKP and peer/consensus status captured together, so the dashboard
+/// renders one consistent view instead of stitching several endpoints together
+async fn get_snapshot(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let max_blocks = query.blocks.unwrap_or(DEFAULT_SNAPSHOT_BLOCKS).min(MAX_SNAPSHOT_BLOCKS);
+    let snapshot = match state.blockchain.get_snapshot(max_blocks).await {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            error!("❌ Failed to capture snapshot: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let response = serde_json::json!({
+        "captured_at": snapshot.captured_at,
+        "stats": snapshot.stats,
+        "recent_blocks": snapshot.recent_blocks.iter().map(block_summary).collect::<Vec<_>>(),
+        "zkp": snapshot.zkp,
+        "network": snapshot.network,
+        "consensus": {
+            "metrics": snapshot.consensus_metrics,
+            "rounds": snapshot.consensus_rounds,
+        },
+    });
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(response),
+        message: "Snapshot captured successfully".to_string(),
+    }))
+}
+
+/// Get full detail of settlement block #number
+async fn get_block_by_number(
+    State(state): State<Arc<AppState>>,
+    Path(block_number): Path<u64>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let block = match state.blockchain.get_block_by_number(block_number).await {
+        Ok(Some(block)) => block,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let mut block_json = serde_json::to_value(&block).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Null when the block carries no total proof or this node has no proof system
+    block_json["block_proof_valid"] = serde_json::json!(state.blockchain.verify_block_proof(&block).await);
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(block_json),
+        message: format!("Block #{} retrieved successfully", block_number),
+    }))
+}
+
+async fn get_block_by_hash(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let block_hash = parse_block_hash(&hash)?;
+
+    let block = match state.blockchain.get_block_by_hash(&block_hash).await {
+        Ok(Some(block)) => block,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let mut block_json = serde_json::to_value(&block).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Null when the block carries no total proof or this node has no proof system
+    block_json["block_proof_valid"] = serde_json::json!(state.blockchain.verify_block_proof(&block).await);
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(block_json),
+        message: format!("Block {} retrieved successfully", block_hash.to_hex()),
+    }))
+}
+
+/// Server-Sent Events stream of newly finalized blocks
+async fn stream_finalized_blocks(
+    State(state): State<Arc<AppState>>
+) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    let receiver = state.blockchain.subscribe_block_events();
+
+    let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+        let event = match receiver.recv().await {
+            Ok(block_event) => Event::default()
+                .event("block_finalized")
+                .json_data(&block_event)
+                .unwrap_or_else(|_| Event::default().comment("failed to encode block event")),
+            // Slow client - tell it how many events it missed and keep going
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                Event::default().event("lagged").data(skipped.to_string())
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+        };
+        Some((Ok(event), receiver))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// WebSocket stream of record submission and consensus events, one JSON frame per event
+async fn consensus_events_ws(
+    State(state): State<Arc<AppState>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let receiver = state.blockchain.subscribe_consensus_events();
+    ws.on_upgrade(move |socket| forward_consensus_events(socket, receiver))
+}
+
+async fn forward_consensus_events(
+    mut socket: WebSocket,
+    mut receiver: tokio::sync::broadcast::Receiver<ConsensusEvent>,
+) {
+    loop {
+        let frame = tokio::select! {
+            event = receiver.recv() => match event {
+                Ok(event) => match serde_json::to_string(&event) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        error!("❌ Failed to encode consensus event: {}", e);
+                        continue;
+                    }
+                },
+                // Slow client - tell it how many events it missed and keep going
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    serde_json::json!({ "type": "lagged", "payload": { "skipped": skipped } }).to_string()
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            },
+            // Clients only listen; any close or error ends the stream
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        if socket.send(Message::Text(frame)).await.is_err() {
+            break;
+        }
+    }
+    debug!("🔌 Consensus event WebSocket closed");
+}
+
+/// Configured resource limits and live utilization of this node
+async fn get_resources(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<ResourceReport>>, StatusCode> {
+    let report = state.blockchain.get_resource_report().await;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(report),
+        message: "Resource limits and utilization retrieved successfully".to_string(),
+    }))
+}
+
+/// Per-endpoint request counts and latency histograms since node start
+async fn get_request_metrics(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<RequestMetricsSnapshot>>, StatusCode> {
+    let snapshot = state.request_metrics.snapshot();
+    let endpoints = snapshot.endpoints.len();
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(snapshot),
+        message: format!("Request metrics for {} endpoints", endpoints),
+    }))
+}
+
+/// Propose a settlement block from the pending records without waiting for the threshold
+async fn force_create_block(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.force_create_settlement_block().await {
+        Ok(block_hash) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "block_hash": hex::encode(block_hash.as_bytes())
+            })),
+            message: "Settlement block proposed for consensus".to_string(),
+        })),
+        Err(e @ (BlockchainError::Consensus(_) | BlockchainError::ConsensusBusy(_) | BlockchainError::Broadcast(_))) => {
+            error!("❌ Settlement block creation failed: {}", e);
+            Err(block_error_status(&e))
+        }
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: Some(serde_json::json!({
+                "error": e.to_string()
+            })),
+            message: "Settlement block creation failed".to_string(),
+        })),
+    }
+}
+
+/// HTTP status for a settlement block that is missing, malformed, could not be agreed on
+/// or could not be sent to the validators
+fn block_error_status(error: &BlockchainError) -> StatusCode {
+    match error {
+        BlockchainError::Consensus(_) => StatusCode::CONFLICT,
+        BlockchainError::ConsensusBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
+        BlockchainError::Broadcast(_) => StatusCode::BAD_GATEWAY,
+        BlockchainError::BlockNotFound(_) => StatusCode::NOT_FOUND,
+        BlockchainError::InvalidBlock(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PruneRequest {
+    /// Blocks numbered below this are removed; the tip is always kept
+    before_block: u64,
+}
+
+fn prune_checkpoint_json(checkpoint: &PruneCheckpoint) -> serde_json::Value {
+    serde_json::json!({
+        "pruned_before": checkpoint.pruned_before(),
+        "checkpoint_block_number": checkpoint.boundary_block.block_number,
+        "checkpoint_block_hash": hex::encode(checkpoint.boundary_block.block_hash.as_bytes()),
+        "pruned_block_count": checkpoint.aggregates.block_count,
+        "pruned_record_count": checkpoint.aggregates.total_records,
+        "pruned_amount_eur": checkpoint.aggregates.total_amount_cents as f64 / 100.0,
+    })
+}
+
+/// Remove finalized blocks below `before_block` and their settled records to bound disk usage
+async fn prune_blocks(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PruneRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.prune_blocks_before(request.before_block).await {
+        Ok(checkpoint) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(prune_checkpoint_json(&checkpoint)),
+            message: format!("Pruned blocks below #{}", request.before_block),
+        })),
+        Err(e @ BlockchainError::Storage(RocksError::PruneRejected(_))) => Ok(Json(ApiResponse {
+            success: false,
+            data: Some(serde_json::json!({
+                "error": e.to_string()
+            })),
+            message: "Prune rejected".to_string(),
+        })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Recompute running totals and record indexes after they drifted from the stored chain
+async fn rebuild_derived_state(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.rebuild_derived_state().await {
+        Ok(rebuild) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "block_count": rebuild.aggregates.block_count,
+                "total_records": rebuild.aggregates.total_records,
+                "total_amount_eur": rebuild.aggregates.total_amount_cents as f64 / 100.0,
+                "indexed_records": rebuild.indexed_records,
+                "tip_height": rebuild.tip_height,
+                "state_root": hex::encode(rebuild.state_root.as_bytes()),
+            })),
+            message: format!("Derived state rebuilt from {} blocks", rebuild.aggregates.block_count),
+        })),
+        Err(BlockchainError::ShuttingDown) => Err(StatusCode::SERVICE_UNAVAILABLE),
+        Err(e) => {
+            error!("❌ Failed to rebuild derived state: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Checkpoint left by the last prune, if the chain has been pruned
+async fn get_prune_checkpoint(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.get_prune_checkpoint().await {
+        Ok(Some(checkpoint)) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(prune_checkpoint_json(&checkpoint)),
+            message: "Prune checkpoint retrieved successfully".to_string(),
+        })),
+        Ok(None) => Ok(Json(ApiResponse {
+            success: true,
+            data: None,
+            message: "Chain has not been pruned".to_string(),
+        })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RotateCredentialRequest {
+    /// Hex-encoded 32-byte Ed25519 public key
+    public_key: String,
+}
+
+/// Replace an SP's signing key; the old key keeps verifying during the grace period
+async fn rotate_credential(
+    State(state): State<Arc<AppState>>,
+    Path(provider_id): Path<String>,
+    Json(request): Json<RotateCredentialRequest>,
+) -> Result<Json<ApiResponse<sp_blockchain::security::KeyRotation>>, StatusCode> {
+    let public_key = hex::decode(&request.public_key).ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok());
+    let Some(public_key) = public_key else {
+        return Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            message: "public_key must be a hex-encoded 32-byte Ed25519 key".to_string(),
+        }));
+    };
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    match state.authentication.rotate_credential(&provider_id, &public_key, now) {
+        Ok(rotation) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("Signing key of {} rotated", provider_id),
+            data: Some(rotation),
+        })),
+        Err(CredentialError::UnknownProvider(_)) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("❌ Credential rotation for {} failed: {}", provider_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Authentication audit entries, optionally limited to a `from`/`to` time range and a provider
+async fn get_audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<ApiResponse<Vec<AuditEntry>>>, StatusCode> {
+    let authentication = state.authentication.clone();
+    let entries = tokio::task::spawn_blocking(move || authentication.audit_log().query(&query))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match entries {
+        Ok(entries) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("{} audit log entries", entries.len()),
+            data: Some(entries),
+        })),
+        Err(e) => {
+            error!("❌ Failed to read audit log: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// In-flight consensus rounds with per-validator votes, for debugging blocks that do not finalize
+async fn get_consensus_status(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<Vec<RoundStatus>>>, StatusCode> {
+    let rounds = state.blockchain.get_consensus_status().await;
+    let open = rounds.iter().filter(|r| !r.finalized).count();
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(rounds),
+        message: format!("{} open consensus rounds", open),
+    }))
+}
+
+/// Votes and approval required to finalize a block, and the known validator set size
+async fn get_consensus_config(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<ConsensusSettings>>, StatusCode> {
+    let settings = state.blockchain.get_consensus_settings().await;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(settings),
+        message: "Consensus configuration retrieved successfully".to_string(),
+    }))
+}
+
+async fn get_blockchain_stats(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let stats = match state.blockchain.get_stats().await {
+        Ok(stats) => stats,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let blocks = match state.blockchain.get_all_blocks().await {
+        Ok(blocks) => blocks,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let consensus_metrics = state.blockchain.get_consensus_metrics().await;
+    let block_cache_stats = state.blockchain.storage.get_block_cache_stats();
+
+    let response = serde_json::json!({
+        "total_blocks": stats.total_blocks,
+        "total_records": stats.total_records,
+        "pending_records": stats.pending_records,
+        "total_settlement_amount_eur": stats.total_settlement_amount_cents as f64 / 100.0,
+        "last_block_time": stats.last_block_time,
+        "latest_block_hash": blocks.last().map(|b| hex::encode(b.block_hash.as_bytes())),
+        "chain_length": blocks.len(),
+        "consensus": consensus_metrics,
+        "block_cache": block_cache_stats,
+        "charge_tolerance_cents": state.blockchain.get_charge_tolerance_cents(),
+        "record_tree_cache": state.blockchain.get_record_tree_cache_stats(),
+    });
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(response),
+        message: "Blockchain stats retrieved successfully".to_string(),
+    }))
+}
+
+/// Parse a hex-encoded block hash from a path parameter
+fn parse_block_hash(hash: &str) -> Result<sp_blockchain::hash::Blake2bHash, StatusCode> {
+    sp_blockchain::hash::Blake2bHash::from_hex(hash).map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+/// Generate an inclusion proof of one operator's balance in a block
+async fn get_balance_proof(
+    State(state): State<Arc<AppState>>,
+    Path((hash, operator)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let block_hash = parse_block_hash(&hash)?;
+
+    let block = match state.blockchain.get_block_by_hash(&block_hash).await {
+        Ok(Some(block)) => block,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    match state.blockchain.generate_balance_proof(&block_hash, &operator).await {
+        Ok(proof) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "block_hash": hash,
+                "balance_root": block.balance_root,
+                "proof": proof,
+            })),
+            message: "Balance proof generated successfully".to_string(),
+        })),
+        Err(e @ (BlockchainError::BlockNotFound(_) | BlockchainError::InvalidBlock(_))) => {
+            error!("❌ Balance proof for block {} failed: {}", hash, e);
+            Err(block_error_status(&e))
+        }
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            message: format!("Failed to generate balance proof: {}", e),
+        })),
+    }
+}
+
+async fn get_state_root(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let state_root = state.blockchain.get_state_root().await;
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(serde_json::json!({ "state_root": state_root })),
+        message: "State root retrieved successfully".to_string(),
+    }))
+}
+
+async fn get_state_proof(
+    State(state): State<Arc<AppState>>,
+    Path(operator): Path<String>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.generate_state_proof(&operator).await {
+        Ok((state_root, proof)) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "state_root": state_root,
+                "proof": proof,
+            })),
+            message: "State proof generated successfully".to_string(),
+        })),
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            message: format!("Failed to generate state proof: {}", e),
+        })),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyBalanceProofRequest {
+    balance_root: sp_blockchain::hash::Blake2bHash,
+    proof: sp_blockchain::merkle::BalanceProof,
+}
+
+/// Verify an operator balance proof against a balance root
+async fn verify_balance_proof(
+    Json(request): Json<VerifyBalanceProofRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let valid = sp_blockchain::merkle::verify_balance_proof(&request.balance_root, &request.proof);
 
     Ok(Json(ApiResponse {
         success: true,
-        data: Some(block_summaries),
-        message: "Blocks retrieved successfully".to_string(),
+        data: Some(serde_json::json!({
+            "valid": valid,
+            "operator": request.proof.operator,
+            "balance_cents": request.proof.balance_cents,
+        })),
+        message: if valid {
+            "Balance proof is valid".to_string()
+        } else {
+            "Balance proof is invalid".to_string()
+        },
     }))
 }
 
-async fn get_blockchain_stats(
-    State(state): State<Arc<AppState>>
-) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
-    let stats = match state.blockchain.get_stats().await {
-        Ok(stats) => stats,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
-    let blocks = match state.blockchain.get_all_blocks().await {
-        Ok(blocks) => blocks,
-        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
-    };
+#[derive(Debug, Deserialize)]
+struct RecordProofsRequest {
+    record_ids: Vec<String>,
+}
 
-    let response = serde_json::json!({
-        "total_blocks": stats.total_blocks,
-        "total_records": stats.total_records,
-        "pending_records": stats.pending_records,
-        "total_settlement_amount_eur": stats.total_settlement_amount_cents as f64 / 100.0,
-        "last_block_time": stats.last_block_time,
-        "latest_block_hash": blocks.last().map(|b| hex::encode(b.block_hash.as_bytes())),
-        "chain_length": blocks.len(),
-    });
+/// Generate inclusion proofs for records settled in a block
+async fn get_record_proofs(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+    Json(request): Json<RecordProofsRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let block_hash = parse_block_hash(&hash)?;
+
+    match state.blockchain.generate_record_proofs(&block_hash, &request.record_ids).await {
+        Ok((records_root, proofs)) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "block_hash": hash,
+                "records_root": records_root,
+                "proofs": proofs,
+            })),
+            message: format!("Generated {} record inclusion proofs", proofs.len()),
+        })),
+        Err(BlockchainError::ProofRequestTooLarge { requested, max }) => {
+            error!("❌ Record proof request for {} records exceeds cap of {}", requested, max);
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Err(e @ (BlockchainError::BlockNotFound(_) | BlockchainError::InvalidBlock(_))) => {
+            error!("❌ Record proofs for block {} failed: {}", hash, e);
+            Err(block_error_status(&e))
+        }
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            message: format!("Failed to generate record proofs: {}", e),
+        })),
+    }
+}
 
-    Ok(Json(ApiResponse {
-        success: true,
-        data: Some(response),
-        message: "Blockchain stats retrieved successfully".to_string(),
-    }))
+/// Get the submitters and trace ids of the records settled in a block
+async fn get_block_provenance(
+    State(state): State<Arc<AppState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let block_hash = parse_block_hash(&hash)?;
+
+    match state.blockchain.get_block_provenance(&block_hash).await {
+        Ok(Some(entries)) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "block_hash": hash,
+                "record_count": entries.len(),
+                "provenance": entries,
+            })),
+            message: "Block provenance retrieved successfully".to_string(),
+        })),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
 }
 
 /// Get ZKP and smart contract statistics
@@ -517,7 +1808,8 @@ async fn generate_zkp_proof(
     };
 
     // Generate ZKP proof using the settlement proof system
-    match state.zkp_system.generate_proof(proof_params) {
+    // Proved in one of the node's proof slots and abandoned after the proof timeout
+    match state.blockchain.generate_settlement_proof(Arc::clone(&state.zkp_system), proof_params).await {
         Ok(settlement_proof) => {
             Ok(Json(ApiResponse {
                 success: true,
@@ -610,6 +1902,80 @@ async fn verify_zkp_proof(
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct BatchVerifyItem {
+    proof: String,
+    public_inputs: Vec<String>,
+}
+
+// Far larger than any settlement proof or its public inputs
+const MAX_BATCH_PROOF_HEX_LEN: usize = 2048;
+const MAX_BATCH_PUBLIC_INPUTS: usize = 16;
+
+/// Verify many ZKP proofs in one request; results are in request order
+async fn batch_verify_zkp_proofs(
+    State(state): State<Arc<AppState>>,
+    Json(items): Json<Vec<BatchVerifyItem>>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    use sp_blockchain::zkp::settlement_proofs::{SettlementProof, MAX_BATCH_VERIFY_PROOFS};
+
+    if items.is_empty() || items.len() > MAX_BATCH_VERIFY_PROOFS {
+        return Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            message: format!("Batch must contain between 1 and {} proofs, got {}", MAX_BATCH_VERIFY_PROOFS, items.len()),
+        }));
+    }
+    if let Some(index) = items.iter().position(|item|
+        item.proof.len() > MAX_BATCH_PROOF_HEX_LEN
+            || item.public_inputs.len() > MAX_BATCH_PUBLIC_INPUTS
+            || item.public_inputs.iter().any(|input| input.len() > MAX_BATCH_PROOF_HEX_LEN))
+    {
+        return Ok(Json(ApiResponse {
+            success: false,
+            data: None,
+            message: format!("Proof #{} exceeds the allowed proof or public input size", index),
+        }));
+    }
+
+    // Undecodable hex becomes an empty proof, which verifies as invalid
+    let proofs: Vec<SettlementProof> = items.into_iter()
+        .map(|item| SettlementProof {
+            proof_bytes: hex::decode(&item.proof).unwrap_or_default(),
+            public_inputs: item.public_inputs,
+        })
+        .collect();
+
+    let zkp_system = state.zkp_system.clone();
+    let verification = tokio::task::spawn_blocking(move || zkp_system.batch_verify(&proofs))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match verification {
+        Ok(results) => {
+            let valid_count = results.iter().filter(|&&valid| valid).count();
+            Ok(Json(ApiResponse {
+                success: true,
+                data: Some(serde_json::json!({
+                    "results": results,
+                    "total": results.len(),
+                    "valid_count": valid_count,
+                    "invalid_count": results.len() - valid_count,
+                    "verified_at": chrono::Utc::now().timestamp()
+                })),
+                message: format!("{}/{} ZKP proofs valid", valid_count, results.len()),
+            }))
+        }
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: Some(serde_json::json!({
+                "error": format!("Batch verification failed: {}", e)
+            })),
+            message: "ZKP batch verification failed".to_string(),
+        })),
+    }
+}
+
 /// Get ZKP system status
 async fn get_zkp_system_status(
     State(state): State<Arc<AppState>>
@@ -773,6 +2139,122 @@ async fn reset_zkp_metrics(
     }
 }
 
+/// List recorded proof verification failures for investigation
+async fn get_verification_failures(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<Vec<VerificationFailure>>>, StatusCode> {
+    match state.blockchain.get_verification_failures().await {
+        Ok(failures) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("{} verification failures recorded", failures.len()),
+            data: Some(failures),
+        })),
+        Err(e) => {
+            error!("Failed to read verification failures: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Verify a stored record's proof again against the current verifying key
+async fn reverify_record_proof(
+    State(state): State<Arc<AppState>>,
+    Path(record_id): Path<String>,
+) -> Result<Json<ApiResponse<ProofReverification>>, StatusCode> {
+    match state.blockchain.reverify_record_proof(&record_id).await {
+        Ok(Some(result)) => {
+            let (success, message) = match result.verified {
+                Some(true) => (true, format!("Proof of record {} verifies", record_id)),
+                Some(false) => (true, format!("Proof of record {} does NOT verify", record_id)),
+                None => (false, format!("Record {} has no proof present", record_id)),
+            };
+            Ok(Json(ApiResponse { success, message, data: Some(result) }))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("❌ Failed to re-verify proof of record {}: {}", record_id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RestoreKeysRequest {
+    /// Backup to restore; the most recent backup is used when omitted
+    backup_id: Option<String>,
+}
+
+/// List ZKP key backups available for restore
+async fn list_zkp_key_backups(
+    State(state): State<Arc<AppState>>
+) -> Result<Json<ApiResponse<Vec<String>>>, StatusCode> {
+    match state.blockchain.list_zkp_key_backups().await {
+        Ok(backups) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("{} key backups available", backups.len()),
+            data: Some(backups),
+        })),
+        Err(e) => {
+            error!("Failed to list ZKP key backups: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Restore ZKP keys from a backup
+async fn restore_zkp_keys(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RestoreKeysRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.restore_zkp_keys(request.backup_id.as_deref()).await {
+        Ok(backup_id) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "restored_backup": backup_id,
+                "restored_at": chrono::Utc::now().timestamp()
+            })),
+            message: "ZKP keys restored from backup".to_string(),
+        })),
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: Some(serde_json::json!({
+                "error": e.to_string()
+            })),
+            message: "ZKP key restore failed".to_string(),
+        })),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReloadKeysRequest {
+    /// Directory holding the new keys, e.g. the output of a trusted setup rotation
+    source_dir: PathBuf,
+}
+
+/// Swap in ZKP keys from another directory, backing up the current keys first
+async fn reload_zkp_keys(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ReloadKeysRequest>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    match state.blockchain.reload_zkp_keys(request.source_dir).await {
+        Ok(backup_dir) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "previous_keys_backup": backup_dir,
+                "reloaded_at": chrono::Utc::now().timestamp()
+            })),
+            message: "ZKP keys reloaded".to_string(),
+        })),
+        Err(e) => Ok(Json(ApiResponse {
+            success: false,
+            data: Some(serde_json::json!({
+                "error": e.to_string()
+            })),
+            message: "ZKP key reload failed".to_string(),
+        })),
+    }
+}
+
 /// Run comprehensive ZKP integration test
 async fn test_zkp_integration(
     State(state): State<Arc<AppState>>
@@ -797,19 +2279,49 @@ async fn test_zkp_integration(
     }
 }
 
-/// Get all BCE records
+#[derive(Debug, Deserialize)]
+struct BceRecordQuery {
+    status: Option<String>, // e.g. "pending", "disputed"
+    home: Option<String>,    // Home operator; requires `visited`
+    visited: Option<String>, // Visited operator; requires `home`
+}
+
+/// Get all BCE records, optionally only those in one settlement status
+/// and/or between one home/visited operator pair
 async fn get_bce_records(
-    State(state): State<Arc<AppState>>
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<BceRecordQuery>,
 ) -> Result<Json<ApiResponse<Vec<serde_json::Value>>>, StatusCode> {
-    println!("🔍 DEBUG: get_bce_records endpoint called");
+    debug!("🔍 get_bce_records endpoint called");
+
+    let status = match filter.status.as_deref().map(str::parse::<SettlementStatus>).transpose() {
+        Ok(status) => status,
+        Err(e) => {
+            debug!("⚠️  Rejected BCE record query: {}", e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let result = match (filter.home.as_deref(), filter.visited.as_deref(), status) {
+        (Some(home), Some(visited), status) => state.blockchain.get_records_for_pair(home, visited).await
+            .map(|records| records.into_iter()
+                .filter(|record| status.as_ref().is_none_or(|s| &record.settlement_status == s))
+                .collect()),
+        (None, None, Some(status)) => state.blockchain.get_records_by_status(status).await,
+        (None, None, None) => state.blockchain.get_all_bce_records().await,
+        _ => {
+            debug!("⚠️  Rejected BCE record query: home and visited must be given together");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
 
-    let records = match state.blockchain.get_all_bce_records().await {
+    let records = match result {
         Ok(records) => {
-            println!("🔍 DEBUG: Successfully retrieved {} BCE records", records.len());
+            debug!("🔍 Successfully retrieved {} BCE records", records.len());
             records
         },
         Err(e) => {
-            println!("🔍 DEBUG: Error retrieving BCE records: {:?}", e);
+            error!("❌ Error retrieving BCE records: {:?}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         },
     };
@@ -834,10 +2346,13 @@ async fn get_bce_records(
             "roaming_data_rate_cents": record.roaming_data_rate_cents,
             "network_pair_hash": record.network_pair_hash,
             "proof_verified": record.proof_verified,
+            "settlement_status": record.settlement_status,
+            "record_type": record.record_type,
+            "corrects_record_id": record.corrects_record_id,
         })
     }).collect();
 
-    println!("🔍 DEBUG: Returning {} BCE record summaries", record_summaries.len());
+    debug!("🔍 Returning {} BCE record summaries", record_summaries.len());
 
     Ok(Json(ApiResponse {
         success: true,
@@ -846,19 +2361,124 @@ async fn get_bce_records(
     }))
 }
 
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct SettlementReportQuery {
+    from: Option<i64>, // Unix seconds, inclusive
+    to: Option<i64>,   // Unix seconds, inclusive
+}
+
+/// Stream a CSV settlement report with one row per finalized block, reading blocks
+/// one at a time so the whole chain is never held in memory
+async fn export_settlement_report_csv(
+    State(state): State<Arc<AppState>>,
+    Query(range): Query<SettlementReportQuery>,
+) -> Result<Response, StatusCode> {
+    let operators: Vec<String> = state.blockchain.crypto_verifier.get_consortium_members().to_vec();
+    let header_row = settlement_report_csv_header(&operators);
+    let rows = match state.blockchain.settlement_report_rows(range.from, range.to).await {
+        Ok(rows) => futures::StreamExt::map(rows, |row| row.map_err(|e| std::io::Error::other(e.to_string()))),
+        Err(e) => {
+            error!("❌ Failed to start settlement report: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let body = futures::StreamExt::chain(
+        futures::stream::once(async move { Ok::<_, std::io::Error>(header_row) }),
+        rows,
+    );
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/csv")
+        .header(header::CONTENT_DISPOSITION, "attachment; filename=\"settlement_report.csv\"")
+        .body(Body::from_stream(body))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Which block a record was settled in, with a link to that block
+async fn get_record_settlement(
+    State(state): State<Arc<AppState>>,
+    Path(record_id): Path<String>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
+    let settlement = match state.blockchain.get_record_settlement(&record_id).await {
+        Ok(Some(settlement)) => settlement,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("❌ Failed to look up settlement of record {}: {}", record_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let block_link = settlement.settled_in_block.as_ref().or(settlement.settlement_id.as_ref())
+        .map(|hash| format!("/api/v1/blockchain/blocks/by_hash/{}", hash));
+    let message = match (&settlement.settlement_status, &block_link) {
+        (SettlementStatus::Pending, _) => format!("Record {} is pending and not yet in a settlement block", record_id),
+        (SettlementStatus::Settled, Some(_)) => format!("Record {} is settled", record_id),
+        (_, Some(_)) => format!("Record {} is in a settlement block awaiting confirmation", record_id),
+        (status, None) => format!("Record {} is {:?} and not in a settlement block", record_id, status),
+    };
+
+    let mut data = serde_json::to_value(&settlement).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    data["block_link"] = serde_json::json!(block_link);
+
+    Ok(Json(ApiResponse {
+        success: true,
+        data: Some(data),
+        message,
+    }))
+}
+
+/// Net position of one operator across all finalized blocks, broken down by counterparty
+async fn get_operator_position(
+    State(state): State<Arc<AppState>>,
+    Path(operator): Path<String>,
+) -> Json<ApiResponse<OperatorPosition>> {
+    let position = state.blockchain.operator_position(&operator).await;
+    Json(ApiResponse {
+        success: true,
+        message: format!("{} net position: {:.2} EUR", operator, position.net_cents as f64 / 100.0),
+        data: Some(position),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct OperatorHistoryQuery {
+    from_block: Option<u64>, // Inclusive
+    to_block: Option<u64>,   // Inclusive
+}
+
+/// Cumulative net position of an operator after each finalized block, for charting
+async fn get_operator_history(
+    State(state): State<Arc<AppState>>,
+    Path(operator): Path<String>,
+    Query(range): Query<OperatorHistoryQuery>,
+) -> Result<Json<ApiResponse<Vec<OperatorBalancePoint>>>, StatusCode> {
+    match state.blockchain.operator_balance_history(&operator, range.from_block, range.to_block).await {
+        Ok(history) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("{} balance history over {} blocks", operator, history.len()),
+            data: Some(history),
+        })),
+        Err(e) => {
+            error!("❌ Failed to build balance history of {}: {}", operator, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 /// Get all settlement blocks
 async fn get_settlement_blocks(
     State(state): State<Arc<AppState>>
 ) -> Result<Json<ApiResponse<Vec<serde_json::Value>>>, StatusCode> {
-    println!("🔍 DEBUG: get_settlement_blocks endpoint called");
+    debug!("🔍 get_settlement_blocks endpoint called");
 
     let blocks = match state.blockchain.get_all_blocks().await {
         Ok(blocks) => {
-            println!("🔍 DEBUG: Successfully retrieved {} settlement blocks", blocks.len());
+            debug!("🔍 Successfully retrieved {} settlement blocks", blocks.len());
             blocks
         },
         Err(e) => {
-            println!("🔍 DEBUG: Error retrieving settlement blocks: {:?}", e);
+            error!("❌ Error retrieving settlement blocks: {:?}", e);
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         },
     };
@@ -885,7 +2505,7 @@ async fn get_settlement_blocks(
         })
     }).collect();
 
-    println!("🔍 DEBUG: Returning {} settlement block details", block_details.len());
+    debug!("🔍 Returning {} settlement block details", block_details.len());
 
     Ok(Json(ApiResponse {
         success: true,
@@ -946,33 +2566,61 @@ struct ContractDeployRequest {
     contract_type: String,
     operators: Vec<String>,
     description: Option<String>,
+    /// Initial bilateral amounts of a netting contract, one for every pair of operators
+    #[serde(default)]
+    bilateral_amounts: Vec<ContractBilateralAmount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ContractBilateralAmount {
+    /// Operator ids, as listed in `operators`
+    from: String,
+    to: String,
+    amount_cents: u64,
+}
+
+/// Bilateral amounts of a netting deploy request keyed by the names the netting contract
+/// uses. Netting settles between the whole consortium, so every member must be listed.
+fn netting_bilateral_amounts(request: &ContractDeployRequest) -> Result<Vec<(String, String, u64)>, String> {
+    use sp_blockchain::zkp::smart_contracts::settlement_contract::NETTING_OPERATORS;
+
+    if let Some((missing, _)) = NETTING_OPERATORS.iter().find(|(id, _)| !request.operators.iter().any(|op| op == id)) {
+        return Err(format!("a netting contract needs every consortium operator, {} is not listed", missing));
+    }
+    let name_of = |id: &str| {
+        NETTING_OPERATORS.iter()
+            .find(|(operator, _)| *operator == id)
+            .map(|(_, name)| name.to_string())
+            .ok_or_else(|| format!("unknown netting operator {}", id))
+    };
+    request.bilateral_amounts.iter()
+        .map(|amount| Ok((name_of(&amount.from)?, name_of(&amount.to)?, amount.amount_cents)))
+        .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ContractExecuteRequest {
     contract_id: String,
     method: String,
+    #[serde(default)]
     parameters: serde_json::Value,
 }
 
-/// Deploy a new smart contract (for demo purposes)
+/// Deploy a compiled settlement contract of the requested type
 async fn deploy_smart_contract(
     State(state): State<Arc<AppState>>,
     Json(request): Json<ContractDeployRequest>,
 ) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
     println!("🚀 API: Deploying smart contract: {}", request.contract_id);
 
+    use sp_blockchain::zkp::smart_contracts::settlement_contract::{ExecutableSettlementContract, ContractType, NETTING_OPERATORS};
+    use sp_blockchain::hash::Blake2bHash;
+
     // Validate operators - must be consortium members
-    let valid_operators = vec![
-        "tmobile-de".to_string(),
-        "vodafone-uk".to_string(),
-        "orange-fr".to_string(),
-        "telefonica-es".to_string(),
-        "sfr-fr".to_string(),
-    ];
+    let valid_operators: Vec<&str> = NETTING_OPERATORS.iter().map(|(id, _)| *id).collect();
 
     for operator in &request.operators {
-        if !valid_operators.contains(operator) {
+        if !valid_operators.contains(&operator.as_str()) {
             return Ok(Json(ApiResponse {
                 success: false,
                 data: None,
@@ -981,23 +2629,33 @@ async fn deploy_smart_contract(
         }
     }
 
-    // Deploy contract directly through blockchain (simplified approach)
-    // Create simple contract for demo purposes
-    use sp_blockchain::zkp::smart_contracts::settlement_contract::{ExecutableSettlementContract, ContractType};
-    use sp_blockchain::zkp::smart_contracts::vm::Instruction;
-    use sp_blockchain::hash::Blake2bHash;
-    use std::collections::HashMap;
-
-    let contract = ExecutableSettlementContract {
-        contract_address: Blake2bHash::hash(&request.contract_id),
-        bytecode: vec![
-            Instruction::Log("Contract deployed".to_string()),
-            Instruction::Push(1),
-            Instruction::Halt,
-        ],
-        state: HashMap::new(),
-        contract_type: ContractType::BceValidator,
+    let contract_type = match request.contract_type.parse::<ContractType>() {
+        Ok(contract_type) => contract_type,
+        Err(e) => {
+            return Ok(Json(ApiResponse {
+                success: false,
+                data: None,
+                message: format!("Invalid contract type: {}", e),
+            }));
+        }
+    };
+    let contract = match contract_type {
+        ContractType::NettingCalculator => netting_bilateral_amounts(&request),
+        _ => Ok(Vec::new()),
+    }.and_then(|bilateral_amounts| {
+        ExecutableSettlementContract::for_type(&contract_type, Blake2bHash::hash(&request.contract_id), &bilateral_amounts)
+    });
+    let contract = match contract {
+        Ok(contract) => contract,
+        Err(e) => {
+            return Ok(Json(ApiResponse {
+                success: false,
+                data: None,
+                message: format!("Invalid contract: {}", e),
+            }));
+        }
     };
+    let instruction_count = contract.bytecode.len();
 
     match state.blockchain.deploy_settlement_contract(contract).await {
         Ok(contract_hash) => {
@@ -1007,6 +2665,7 @@ async fn deploy_smart_contract(
                 "contract_id": request.contract_id,
                 "contract_type": request.contract_type,
                 "deployment_hash": hex::encode(contract_hash.as_bytes()),
+                "instruction_count": instruction_count,
                 "operators": request.operators,
                 "description": request.description.unwrap_or("Smart contract for telecom settlement".to_string()),
                 "deployment_time": chrono::Utc::now().to_rfc3339(),
@@ -1036,6 +2695,8 @@ async fn list_smart_contracts(
 ) -> Result<Json<ApiResponse<serde_json::Value>>, StatusCode> {
     println!("📋 API: Listing all smart contracts");
 
+    let contracts = state.blockchain.list_contracts().await;
+
     // Get actual contract list from blockchain
     let zkp_stats = match state.blockchain.get_zkp_health_check().await {
         Ok(stats) => stats,
@@ -1048,23 +2709,64 @@ async fn list_smart_contracts(
         }
     };
 
-    let deployed_contracts = zkp_stats.get("deployed_contracts")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0);
+    let contract_summaries: Vec<serde_json::Value> = contracts.iter().map(|contract| {
+        serde_json::json!({
+            "contract_address": contract.contract_address,
+            "contract_type": contract.contract_type,
+            "instruction_count": contract.instruction_count,
+        })
+    }).collect();
 
     let response_data = serde_json::json!({
-        "contracts": [],
-        "total_count": deployed_contracts,
+        "contracts": contract_summaries,
+        "total_count": contracts.len(),
         "zkp_stats": zkp_stats
     });
 
     Ok(Json(ApiResponse {
         success: true,
         data: Some(response_data),
-        message: format!("Found {} deployed contracts", deployed_contracts),
+        message: format!("Found {} deployed contracts", contracts.len()),
     }))
 }
 
+/// Get the recorded execution history of a deployed contract, oldest first
+async fn get_contract_executions(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ContractExecutionRecord>>>, StatusCode> {
+    let contract_address = parse_block_hash(&address)?;
+
+    match state.blockchain.get_contract_executions(&contract_address).await {
+        Ok(executions) => Ok(Json(ApiResponse {
+            success: true,
+            message: format!("{} executions recorded for contract {}", executions.len(), contract_address.to_hex()),
+            data: Some(executions),
+        })),
+        Err(e) => {
+            error!("❌ Failed to load executions of contract {}: {}", contract_address.to_hex(), e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get a deployed contract's type, instruction count and current storage
+async fn get_smart_contract(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<ApiResponse<ContractInfo>>, StatusCode> {
+    let contract_address = parse_block_hash(&address)?;
+
+    match state.blockchain.get_contract_info(&contract_address).await {
+        Some(contract) => Ok(Json(ApiResponse {
+            success: true,
+            data: Some(contract),
+            message: format!("Contract {} retrieved successfully", contract_address.to_hex()),
+        })),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
 /// Execute a smart contract method
 async fn execute_smart_contract(
     State(state): State<Arc<AppState>>,
@@ -1074,42 +2776,72 @@ async fn execute_smart_contract(
 
     match request.method.as_str() {
         "validate_bce_rates" => {
-            // For demo purposes, simulate rate validation directly
-            let response_data = serde_json::json!({
-                "execution_id": format!("exec_{}_{}", request.contract_id, chrono::Utc::now().timestamp()),
-                "method": request.method,
-                "result": "valid",
-                "gas_used": 15000,
-                "events": [
-                    {
-                        "event_type": "RateValidation",
-                        "data": {
-                            "contract_id": request.contract_id,
-                            "validation_result": "valid"
-                        },
-                        "timestamp": chrono::Utc::now().timestamp()
-                    }
-                ],
-                "execution_time": chrono::Utc::now().to_rfc3339()
-            });
+            let rates: RateValidationRequest = match serde_json::from_value(request.parameters.clone()) {
+                Ok(rates) => rates,
+                Err(e) => {
+                    return Ok(Json(ApiResponse {
+                        success: false,
+                        data: None,
+                        message: format!("Invalid rate validation parameters: {}", e),
+                    }));
+                }
+            };
 
-            Ok(Json(ApiResponse {
-                success: true,
-                data: Some(response_data),
-                message: "Rate validation executed successfully".to_string(),
-            }))
+            // Proving is CPU-bound, keep it off the async workers
+            let contract_api = state.contract_api.clone();
+            let contract_id = request.contract_id.clone();
+            let validation = tokio::task::spawn_blocking(move || contract_api.validate_bce_rates(contract_id, rates))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            match validation {
+                Ok(execution) => {
+                    let response_data = serde_json::json!({
+                        "execution_id": execution.execution_id,
+                        "method": request.method,
+                        "result": execution.result,
+                        "gas_used": execution.gas_used,
+                        "events": execution.events,
+                        "execution_time": chrono::Utc::now().to_rfc3339()
+                    });
+
+                    Ok(Json(ApiResponse {
+                        success: true,
+                        data: Some(response_data),
+                        message: "Rate validation executed successfully".to_string(),
+                    }))
+                }
+                Err(e) => Ok(Json(ApiResponse {
+                    success: false,
+                    data: None,
+                    message: format!("Rate validation failed: {}", e),
+                })),
+            }
         }
         _ => {
             // Generic contract execution using blockchain directly
             let contract_hash = sp_blockchain::hash::Blake2bHash::hash(&request.contract_id);
 
-            match state.blockchain.execute_smart_contract(contract_hash).await {
-                Ok(result) => {
+            let inputs = match parse_contract_inputs(&request.parameters) {
+                Ok(inputs) => inputs,
+                Err(e) => {
+                    return Ok(Json(ApiResponse {
+                        success: false,
+                        data: None,
+                        message: format!("Invalid contract parameters: {}", e),
+                    }));
+                }
+            };
+
+            match state.blockchain.execute_smart_contract_with_inputs(contract_hash, inputs).await {
+                Ok(execution) => {
                     let response_data = serde_json::json!({
                         "execution_id": format!("exec_{}_{}", request.contract_id, chrono::Utc::now().timestamp()),
                         "method": request.method,
-                        "result": result.to_string(),
-                        "gas_used": 12000,
+                        "result": execution.result.to_string(),
+                        "gas_used": execution.gas_used,
+                        "logs": execution.logs,
+                        "stack": execution.stack,
                         "events": [],
                         "execution_time": chrono::Utc::now().to_rfc3339()
                     });
@@ -1120,6 +2852,10 @@ async fn execute_smart_contract(
                         message: "Contract executed successfully".to_string(),
                     }))
                 }
+                Err(BlockchainError::ContractNotFound(contract)) => {
+                    error!("❌ Contract not found: {}", contract);
+                    Err(StatusCode::NOT_FOUND)
+                }
                 Err(e) => {
                     Ok(Json(ApiResponse {
                         success: false,
@@ -1132,6 +2868,37 @@ async fn execute_smart_contract(
     }
 }
 
+/// Parse contract execution parameters into VM storage inputs.
+/// Keys are either 64-char hex storage hashes or names hashed with Blake2b;
+/// values must be unsigned integers.
+fn parse_contract_inputs(
+    parameters: &serde_json::Value,
+) -> Result<std::collections::HashMap<sp_blockchain::hash::Blake2bHash, u64>, String> {
+    use sp_blockchain::hash::Blake2bHash;
+
+    let mut inputs = std::collections::HashMap::new();
+
+    let params = match parameters {
+        serde_json::Value::Null => return Ok(inputs),
+        serde_json::Value::Object(params) => params,
+        _ => return Err("parameters must be a JSON object".to_string()),
+    };
+
+    for (key, value) in params {
+        let value = value.as_u64()
+            .ok_or_else(|| format!("parameter '{}' must be an unsigned integer", key))?;
+
+        let storage_key = match hex::decode(key).ok().and_then(|b| <[u8; 32]>::try_from(b).ok()) {
+            Some(bytes) => Blake2bHash::from_bytes(bytes),
+            None => Blake2bHash::hash(key.as_bytes()),
+        };
+
+        inputs.insert(storage_key, value);
+    }
+
+    Ok(inputs)
+}
+
 /// Get contract statistics and performance metrics
 async fn get_contract_stats(
     State(state): State<Arc<AppState>>,
@@ -1161,4 +2928,149 @@ async fn get_contract_stats(
             }))
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Bn254;
+    use ark_groth16::{Groth16, ProvingKey};
+    use ark_serialize::CanonicalSerialize;
+    use ark_snark::SNARK;
+    use sp_blockchain::zkp::SettlementCircuit;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    async fn test_state(
+        data_dir: &std::path::Path,
+        peer_count: Arc<AtomicUsize>,
+        zkp_system: SettlementProofSystem,
+    ) -> Arc<AppState> {
+        let (mut blockchain, _network_rx) = SimpleBlockchain::new(
+            &data_dir.to_string_lossy(),
+            "tmobile-de".to_string(),
+            0,
+            100.0,
+        ).await.unwrap();
+        blockchain.set_zkp_enabled(false);
+        blockchain.set_peer_count_handle(peer_count);
+        blockchain.set_peers_configured(true);
+        let blockchain = Arc::new(blockchain);
+
+        Arc::new(AppState {
+            contract_api: Arc::new(ContractAPI::with_blockchain(blockchain.clone())),
+            blockchain,
+            node_id: "tmobile-de".to_string(),
+            settlement_threshold_eur: 100.0,
+            authentication: Arc::new(SpAuthentication::new_consortium()),
+            zkp_system: Arc::new(zkp_system),
+            zkp_system_ready: true,
+            request_metrics: Arc::new(RequestMetrics::new()),
+        })
+    }
+
+    /// A verification-only proof system, and the proving key matching its verifying key
+    fn settlement_proof_system(circuit: SettlementCircuit) -> (ProvingKey<Bn254>, SettlementProofSystem) {
+        let rng = &mut ark_std::rand::thread_rng();
+        let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        (pk, SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap())
+    }
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint_reports_network_and_zkp_status() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let peer_count = Arc::new(AtomicUsize::new(0));
+        let (_, zkp_system) = settlement_proof_system(SettlementCircuit::new_dummy());
+        let app = Router::new()
+            .route("/health", get(health_handler))
+            .with_state(test_state(temp_dir.path(), peer_count.clone(), zkp_system).await);
+        let health = || Request::builder().uri("/health").body(Body::empty()).unwrap();
+
+        // Bootstrap peers were configured but none are connected
+        let response = app.clone().oneshot(health()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = json_body(response).await;
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["peer_count"], 0);
+        assert_eq!(body["min_validators"], 3);
+        assert_eq!(body["can_reach_consensus"], false);
+        assert_eq!(body["zkp_system_operational"], true);
+
+        // Two peers plus this node meet the default of three validators
+        peer_count.store(2, Ordering::Relaxed);
+        let response = app.oneshot(health()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["status"], "healthy");
+        assert_eq!(body["peer_count"], 2);
+        assert_eq!(body["can_reach_consensus"], true);
+    }
+
+    #[tokio::test]
+    async fn test_batch_verify_endpoint_reports_each_proof() {
+        use sp_blockchain::zkp::settlement_proofs::MAX_BATCH_VERIFY_PROOFS;
+        use sp_blockchain::zkp::SettlementWitness;
+
+        let witness = SettlementWitness {
+            total_amount: 10000,
+            operator_count: 2,
+            settlement_hash: [1u8; 32],
+            private_amounts: vec![6000, 4000],
+            private_rates: vec![100, 150],
+        };
+        let (pk, zkp_system) = settlement_proof_system(SettlementCircuit::new(witness.clone()));
+        let proof = Groth16::<Bn254>::prove(&pk, SettlementCircuit::new(witness), &mut ark_std::rand::thread_rng()).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        let public_inputs = |total: u64| {
+            // The settlement hash enters as its first 8 bytes
+            [ark_bn254::Fr::from(total), ark_bn254::Fr::from(2u64), ark_bn254::Fr::from(u64::from_le_bytes([1u8; 8]))]
+                .iter()
+                .map(|input| {
+                    let mut bytes = Vec::new();
+                    input.serialize_compressed(&mut bytes).unwrap();
+                    hex::encode(bytes)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = Router::new()
+            .route("/api/v1/zkp/batch_verify", post(batch_verify_zkp_proofs))
+            .with_state(test_state(temp_dir.path(), Arc::new(AtomicUsize::new(0)), zkp_system).await);
+        let batch_verify = |items: serde_json::Value| Request::builder()
+            .method("POST")
+            .uri("/api/v1/zkp/batch_verify")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(items.to_string()))
+            .unwrap();
+
+        let valid = serde_json::json!({ "proof": hex::encode(&proof_bytes), "public_inputs": public_inputs(10000) });
+        let items = serde_json::json!([
+            valid,
+            { "proof": hex::encode(&proof_bytes), "public_inputs": public_inputs(9999) },
+            { "proof": "not hex", "public_inputs": public_inputs(10000) },
+            valid,
+        ]);
+        let response = app.clone().oneshot(batch_verify(items)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["success"], true);
+        assert_eq!(body["data"]["results"], serde_json::json!([true, false, false, true]));
+        assert_eq!(body["data"]["valid_count"], 2);
+        assert_eq!(body["data"]["invalid_count"], 2);
+
+        // Empty and oversized batches are refused without verifying anything
+        for items in [serde_json::json!([]), serde_json::json!(vec![valid; MAX_BATCH_VERIFY_PROOFS + 1])] {
+            let body = json_body(app.clone().oneshot(batch_verify(items)).await.unwrap()).await;
+            assert_eq!(body["success"], false);
+            assert!(body["data"].is_null());
+        }
+    }
+}
@@ -0,0 +1,357 @@
+// Merkle tree over per-operator settlement balances for selective disclosure
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+use crate::hash::{Blake2bHash, Blake2bHasher};
+
+/// One step of an inclusion proof: the sibling hash and which side it sits on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MerkleProofStep {
+    pub sibling: Blake2bHash,
+    pub sibling_on_left: bool,
+}
+
+/// Inclusion proof of a single operator balance under a block's balance root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BalanceProof {
+    pub operator: String,
+    pub balance_cents: i64,
+    pub steps: Vec<MerkleProofStep>,
+}
+
+/// Inclusion proof of an operator's cumulative balance under a global state root
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StateProof {
+    pub balance_proof: BalanceProof,
+    pub previous_block_hash: Blake2bHash,
+}
+
+/// Leaf hash for an operator balance
+pub fn balance_leaf(operator: &str, balance_cents: i64) -> Blake2bHash {
+    let mut hasher = Blake2bHasher::new();
+    hasher.update(b"balance:");
+    hasher.update(operator.as_bytes());
+    hasher.update(b":");
+    hasher.update(&balance_cents.to_le_bytes());
+    hasher.finalize()
+}
+
+fn hash_pair(left: &Blake2bHash, right: &Blake2bHash) -> Blake2bHash {
+    let mut hasher = Blake2bHasher::new();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hasher.finalize()
+}
+
+/// Balances ordered by operator so every node derives the same tree
+fn sorted_leaves(balances: &HashMap<String, i64>) -> Vec<(String, Blake2bHash)> {
+    let mut operators: Vec<(&String, &i64)> = balances.iter().collect();
+    operators.sort_by(|a, b| a.0.cmp(b.0));
+    operators.into_iter()
+        .map(|(operator, balance)| (operator.clone(), balance_leaf(operator, *balance)))
+        .collect()
+}
+
+/// Compute the next tree level; an odd trailing node is carried up unchanged
+fn next_level(level: &[Blake2bHash]) -> Vec<Blake2bHash> {
+    level.chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Merkle root over per-operator balances (zero hash when there are none)
+pub fn balance_root(balances: &HashMap<String, i64>) -> Blake2bHash {
+    let mut level: Vec<Blake2bHash> = sorted_leaves(balances).into_iter().map(|(_, leaf)| leaf).collect();
+    if level.is_empty() {
+        return Blake2bHash::zero();
+    }
+
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Build an inclusion proof for one operator's balance
+pub fn generate_balance_proof(balances: &HashMap<String, i64>, operator: &str) -> Option<BalanceProof> {
+    let leaves = sorted_leaves(balances);
+    let mut index = leaves.iter().position(|(name, _)| name == operator)?;
+    let mut level: Vec<Blake2bHash> = leaves.into_iter().map(|(_, leaf)| leaf).collect();
+    let mut steps = Vec::new();
+
+    while level.len() > 1 {
+        let sibling_index = index ^ 1;
+        if sibling_index < level.len() {
+            steps.push(MerkleProofStep {
+                sibling: level[sibling_index],
+                sibling_on_left: sibling_index < index,
+            });
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+
+    Some(BalanceProof {
+        operator: operator.to_string(),
+        balance_cents: balances[operator],
+        steps,
+    })
+}
+
+/// Balance root implied by a balance proof
+fn balance_proof_root(proof: &BalanceProof) -> Blake2bHash {
+    proof.steps.iter().fold(
+        balance_leaf(&proof.operator, proof.balance_cents),
+        |node, step| {
+            if step.sibling_on_left {
+                hash_pair(&step.sibling, &node)
+            } else {
+                hash_pair(&node, &step.sibling)
+            }
+        },
+    )
+}
+
+/// Verify a balance proof against a balance root
+pub fn verify_balance_proof(root: &Blake2bHash, proof: &BalanceProof) -> bool {
+    balance_proof_root(proof).ct_eq(root)
+}
+
+/// Global state root anchoring the cumulative operator balances and the chain the latest
+/// block extends. It names that block's parent because the block commits to the root.
+pub fn state_root(balance_root: &Blake2bHash, previous_block_hash: &Blake2bHash) -> Blake2bHash {
+    let mut hasher = Blake2bHasher::new();
+    hasher.update(b"state:");
+    hasher.update(balance_root.as_bytes());
+    hasher.update(previous_block_hash.as_bytes());
+    hasher.finalize()
+}
+
+/// Verify a state proof against a global state root
+pub fn verify_state_proof(root: &Blake2bHash, proof: &StateProof) -> bool {
+    state_root(&balance_proof_root(&proof.balance_proof), &proof.previous_block_hash).ct_eq(root)
+}
+
+/// Inclusion proof of a record id under a block's record tree
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordProof {
+    pub record_id: String,
+    pub steps: Vec<MerkleProofStep>,
+}
+
+/// Leaf hash for a settled record id
+pub fn record_leaf(record_id: &str) -> Blake2bHash {
+    let mut hasher = Blake2bHasher::new();
+    hasher.update(b"record:");
+    hasher.update(record_id.as_bytes());
+    hasher.finalize()
+}
+
+/// Merkle tree over a block's record ids (in block order), keeping every level
+/// so proofs can be read off without rebuilding
+#[derive(Debug, Clone)]
+pub struct RecordTree {
+    positions: HashMap<String, usize>,
+    levels: Vec<Vec<Blake2bHash>>,
+}
+
+impl RecordTree {
+    pub fn build(record_ids: &[String]) -> Self {
+        let positions = record_ids.iter().enumerate()
+            .map(|(index, record_id)| (record_id.clone(), index))
+            .collect();
+
+        let mut levels = vec![record_ids.iter().map(|id| record_leaf(id)).collect::<Vec<_>>()];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = next_level(levels.last().unwrap());
+            levels.push(next);
+        }
+
+        Self { positions, levels }
+    }
+
+    /// Root of the tree (zero hash for a block without records)
+    pub fn root(&self) -> Blake2bHash {
+        self.levels.last()
+            .and_then(|level| level.first().copied())
+            .unwrap_or_else(Blake2bHash::zero)
+    }
+
+    /// Inclusion proof for one record id
+    pub fn proof(&self, record_id: &str) -> Option<RecordProof> {
+        let mut index = *self.positions.get(record_id)?;
+        let mut steps = Vec::new();
+
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling_index = index ^ 1;
+            if sibling_index < level.len() {
+                steps.push(MerkleProofStep {
+                    sibling: level[sibling_index],
+                    sibling_on_left: sibling_index < index,
+                });
+            }
+            index /= 2;
+        }
+
+        Some(RecordProof { record_id: record_id.to_string(), steps })
+    }
+}
+
+/// Verify a record proof against a record tree root
+pub fn verify_record_proof(root: &Blake2bHash, proof: &RecordProof) -> bool {
+    let computed = proof.steps.iter().fold(record_leaf(&proof.record_id), |node, step| {
+        if step.sibling_on_left {
+            hash_pair(&step.sibling, &node)
+        } else {
+            hash_pair(&node, &step.sibling)
+        }
+    });
+    computed.ct_eq(root)
+}
+
+/// Default number of per-block record trees kept in memory
+pub const DEFAULT_RECORD_TREE_CACHE_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordTreeCacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub builds: u64,
+}
+
+/// Least-recently-used cache of record trees keyed by block hash
+pub struct RecordTreeCache {
+    capacity: usize,
+    trees: HashMap<Blake2bHash, Arc<RecordTree>>,
+    // Front = least recently used
+    order: VecDeque<Blake2bHash>,
+    hits: u64,
+    builds: u64,
+}
+
+impl RecordTreeCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            trees: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            builds: 0,
+        }
+    }
+
+    /// Get the tree for a block, building and caching it on first use
+    pub fn get_or_build(&mut self, block_hash: &Blake2bHash, record_ids: &[String]) -> Arc<RecordTree> {
+        if let Some(tree) = self.trees.get(block_hash).cloned() {
+            self.order.retain(|hash| hash != block_hash);
+            self.order.push_back(*block_hash);
+            self.hits += 1;
+            return tree;
+        }
+
+        let tree = Arc::new(RecordTree::build(record_ids));
+        self.builds += 1;
+
+        if self.capacity > 0 {
+            self.trees.insert(*block_hash, tree.clone());
+            self.order.push_back(*block_hash);
+            while self.trees.len() > self.capacity {
+                if let Some(evicted) = self.order.pop_front() {
+                    self.trees.remove(&evicted);
+                }
+            }
+        }
+
+        tree
+    }
+
+    /// Change capacity, evicting least recently used trees if it shrinks
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.trees.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.trees.remove(&evicted);
+            }
+        }
+    }
+
+    /// Drop every cached tree, e.g. once the blocks they were built from are pruned
+    pub fn clear(&mut self) {
+        self.trees.clear();
+        self.order.clear();
+    }
+
+    pub fn stats(&self) -> RecordTreeCacheStats {
+        RecordTreeCacheStats {
+            capacity: self.capacity,
+            entries: self.trees.len(),
+            hits: self.hits,
+            builds: self.builds,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_proofs_for_every_operator() {
+        let balances = HashMap::from([
+            ("T-Mobile-DE".to_string(), -12_000),
+            ("Vodafone-UK".to_string(), 7_000),
+            ("Orange-FR".to_string(), 3_000),
+            ("Telefónica-ES".to_string(), 2_500),
+            ("SFR-FR".to_string(), -500),
+        ]);
+        let root = balance_root(&balances);
+
+        for operator in balances.keys() {
+            let proof = generate_balance_proof(&balances, operator).unwrap();
+            assert!(verify_balance_proof(&root, &proof));
+        }
+
+        assert!(generate_balance_proof(&balances, "Unknown-Operator").is_none());
+    }
+
+    #[test]
+    fn test_record_proofs_for_every_record() {
+        let record_ids: Vec<String> = (0..7).map(|i| format!("BCE-{:03}", i)).collect();
+        let tree = RecordTree::build(&record_ids);
+        let root = tree.root();
+
+        for record_id in &record_ids {
+            let proof = tree.proof(record_id).unwrap();
+            assert!(verify_record_proof(&root, &proof));
+        }
+
+        let forged = RecordProof { record_id: "BCE-999".to_string(), ..tree.proof("BCE-000").unwrap() };
+        assert!(!verify_record_proof(&root, &forged));
+        assert!(tree.proof("BCE-999").is_none());
+    }
+
+    #[test]
+    fn test_state_proof_binds_previous_block() {
+        let balances = HashMap::from([
+            ("T-Mobile-DE".to_string(), -500),
+            ("Vodafone-UK".to_string(), 500),
+        ]);
+        let tip = Blake2bHash::hash(b"block_1");
+        let root = state_root(&balance_root(&balances), &tip);
+
+        let proof = StateProof {
+            balance_proof: generate_balance_proof(&balances, "Vodafone-UK").unwrap(),
+            previous_block_hash: tip,
+        };
+        assert!(verify_state_proof(&root, &proof));
+
+        let stale = StateProof { previous_block_hash: Blake2bHash::hash(b"block_0"), ..proof };
+        assert!(!verify_state_proof(&root, &stale));
+    }
+}
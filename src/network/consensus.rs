@@ -28,6 +28,63 @@ pub struct SimpleConsensus {
     active_rounds: HashMap<Blake2bHash, ConsensusRound>,
     // Configuration
     config: ConsensusConfig,
+    // Proposals turned away because max_concurrent_rounds was reached
+    rejected_rounds: u64,
+    // Preferred block per height when several validators propose at the same height
+    height_candidates: HashMap<u64, BlockCandidate>,
+}
+
+/// Heights below the newest registered one for which candidates are remembered
+const CANDIDATE_HEIGHT_WINDOW: u64 = 16;
+
+/// Deterministic rule choosing between competing blocks proposed at the same height.
+///
+/// Every honest node applies the same rule to the same pair of candidates, so all
+/// of them vote for (and only finalize) the same block regardless of arrival order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TieBreakRule {
+    /// The block with the lexicographically lowest hash wins
+    #[default]
+    LowestHash,
+    /// The block with the earliest proposal timestamp wins; equal timestamps fall
+    /// back to the lowest hash
+    EarliestTimestampThenHash,
+}
+
+/// A block competing for a height
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockCandidate {
+    pub block_hash: Blake2bHash,
+    pub timestamp_millis: i64,
+}
+
+impl std::str::FromStr for TieBreakRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lowest-hash" => Ok(TieBreakRule::LowestHash),
+            "earliest-timestamp" => Ok(TieBreakRule::EarliestTimestampThenHash),
+            other => Err(format!("unknown tie-break rule '{}' (expected lowest-hash or earliest-timestamp)", other)),
+        }
+    }
+}
+
+impl TieBreakRule {
+    /// Whether `challenger` beats `incumbent` under this rule
+    pub fn prefers(&self, challenger: &BlockCandidate, incumbent: &BlockCandidate) -> bool {
+        let by_hash = challenger.block_hash.as_bytes() < incumbent.block_hash.as_bytes();
+        match self {
+            TieBreakRule::LowestHash => by_hash,
+            TieBreakRule::EarliestTimestampThenHash => {
+                match challenger.timestamp_millis.cmp(&incumbent.timestamp_millis) {
+                    std::cmp::Ordering::Less => true,
+                    std::cmp::Ordering::Greater => false,
+                    std::cmp::Ordering::Equal => by_hash,
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,6 +101,9 @@ pub struct ConsensusConfig {
     pub approval_threshold: f64, // 0.67 = 67% approval needed
     pub timeout_duration: Duration,
     pub max_concurrent_rounds: usize,
+    pub tie_break: TieBreakRule,
+    // Per-validator vote weight (e.g. stake or settlement volume); unlisted validators weigh 1
+    pub validator_weights: HashMap<String, u64>,
 }
 
 impl Default for ConsensusConfig {
@@ -53,10 +113,19 @@ impl Default for ConsensusConfig {
             approval_threshold: 0.67, // 67% approval (4/5 or 3/4)
             timeout_duration: Duration::from_secs(30),
             max_concurrent_rounds: 10,
+            tie_break: TieBreakRule::default(),
+            validator_weights: HashMap::new(),
         }
     }
 }
 
+impl ConsensusConfig {
+    /// Vote weight of a validator, defaulting to 1 when none is configured
+    pub fn weight_of(&self, validator_id: &str) -> u64 {
+        self.validator_weights.get(validator_id).copied().unwrap_or(1)
+    }
+}
+
 impl SimpleConsensus {
     pub fn new(config: ConsensusConfig) -> Self {
         let mut validators = HashMap::new();
@@ -88,16 +157,61 @@ impl SimpleConsensus {
             validators,
             active_rounds: HashMap::new(),
             config,
+            rejected_rounds: 0,
+            height_candidates: HashMap::new(),
         }
     }
 
+    /// Register a block proposed at `height` and return whether it is the
+    /// preferred candidate for that height under the configured tie-break rule
+    pub fn register_candidate(&mut self, height: u64, candidate: BlockCandidate) -> bool {
+        let rule = self.config.tie_break;
+        let preferred = match self.height_candidates.get(&height) {
+            Some(incumbent) if incumbent.block_hash == candidate.block_hash => true,
+            Some(incumbent) => {
+                let wins = rule.prefers(&candidate, incumbent);
+                println!("⚔️  Competing blocks at height {}: {} {} {}",
+                         height,
+                         hex::encode(candidate.block_hash.as_bytes()),
+                         if wins { "beats" } else { "loses to" },
+                         hex::encode(incumbent.block_hash.as_bytes()));
+                wins
+            }
+            None => true,
+        };
+
+        if preferred {
+            self.height_candidates.insert(height, candidate);
+        }
+
+        // Forget candidates for heights long since settled
+        self.height_candidates.retain(|h, _| h + CANDIDATE_HEIGHT_WINDOW >= height);
+
+        preferred
+    }
+
+    /// The currently preferred candidate at `height`, if any block was proposed there
+    pub fn preferred_candidate(&self, height: u64) -> Option<BlockCandidate> {
+        self.height_candidates.get(&height).copied()
+    }
+
+    /// Whether a block is the preferred candidate at its height (true when it has no competitor)
+    pub fn is_preferred_candidate(&self, height: u64, block_hash: &Blake2bHash) -> bool {
+        self.height_candidates.get(&height)
+            .is_none_or(|candidate| candidate.block_hash == *block_hash)
+    }
+
     /// Start a new consensus round for a block
     pub fn start_consensus(&mut self, block_hash: Blake2bHash) -> Result<(), ConsensusError> {
         if self.active_rounds.contains_key(&block_hash) {
             return Err(ConsensusError::RoundAlreadyExists);
         }
 
-        if self.active_rounds.len() >= self.config.max_concurrent_rounds {
+        // Only rounds still collecting votes count against the limit
+        if self.open_round_count() >= self.config.max_concurrent_rounds {
+            self.rejected_rounds += 1;
+            println!("🚦 Consensus round rejected: {} rounds already open (max {})",
+                     self.open_round_count(), self.config.max_concurrent_rounds);
             return Err(ConsensusError::TooManyActiveRounds);
         }
 
@@ -115,6 +229,22 @@ impl SimpleConsensus {
         Ok(())
     }
 
+    /// Reinstate an unfinalized round with the votes collected before a restart.
+    /// The round's timeout restarts from now.
+    pub fn restore_round(&mut self, block_hash: Blake2bHash, votes: Vec<Vote>) {
+        let round = ConsensusRound {
+            block_hash,
+            votes: votes.into_iter().map(|vote| (vote.validator_id.clone(), vote)).collect(),
+            started_at: SystemTime::now(),
+            finalized: false,
+            result: None,
+        };
+
+        println!("♻️  Restored consensus round for block {} with {} votes",
+                 hex::encode(block_hash.as_bytes()), round.votes.len());
+        self.active_rounds.insert(block_hash, round);
+    }
+
     /// Process a vote from a validator
     pub fn process_vote(&mut self, vote: Vote) -> Result<ConsensusResult, ConsensusError> {
         // Validate the validator
@@ -178,10 +308,30 @@ impl SimpleConsensus {
         let rejections = round.votes.values().filter(|v| !v.approve).count();
         let total_votes = approvals + rejections;
 
-        let approval_rate = approvals as f64 / total_votes as f64;
+        // Weighted rounds are decided against the weight of every active validator, so a heavy
+        // validator cannot carry a round just because the others have not voted yet. Unweighted
+        // rounds keep deciding on the votes cast once min_validators have voted, which lets
+        // deployments smaller than the full validator set finalize.
+        let approval_weight: u64 = round.votes.values()
+            .filter(|v| v.approve)
+            .map(|v| self.config.weight_of(&v.validator_id))
+            .sum();
+        let total_weight: u64 = if self.config.validator_weights.is_empty() {
+            total_votes as u64
+        } else {
+            self.validators.values()
+                .filter(|v| v.is_active)
+                .map(|v| self.config.weight_of(&v.node_id))
+                .sum()
+        };
+        let approval_rate = if total_weight == 0 {
+            0.0
+        } else {
+            approval_weight as f64 / total_weight as f64
+        };
 
-        println!("📊 Consensus check: {}/{} approvals ({}%)",
-                 approvals, total_votes, (approval_rate * 100.0) as u32);
+        println!("📊 Consensus check: {}/{} approvals, weight {}/{} ({}%)",
+                 approvals, total_votes, approval_weight, total_weight, (approval_rate * 100.0) as u32);
 
         // Check if we have enough votes and meet threshold
         if total_votes >= active_validators || approval_rate >= self.config.approval_threshold {
@@ -203,8 +353,8 @@ impl SimpleConsensus {
         }
     }
 
-    /// Check for expired consensus rounds
-    pub fn cleanup_expired_rounds(&mut self) {
+    /// Reject rounds that ran past the timeout, returning their block hashes
+    pub fn cleanup_expired_rounds(&mut self) -> Vec<Blake2bHash> {
         let now = SystemTime::now();
         let mut expired_rounds = Vec::new();
 
@@ -216,13 +366,28 @@ impl SimpleConsensus {
             }
         }
 
-        for block_hash in expired_rounds {
+        for block_hash in &expired_rounds {
             println!("⏰ Consensus round expired for block: {}", hex::encode(block_hash.as_bytes()));
-            if let Some(mut round) = self.active_rounds.remove(&block_hash) {
+            if let Some(mut round) = self.active_rounds.remove(block_hash) {
                 round.finalized = true;
                 round.result = Some(false); // Timeout = rejection
             }
         }
+        expired_rounds
+    }
+
+    /// Close the round of a block that will not be finalized, such as the loser of a
+    /// same-height tie-break, as rejected so it no longer counts as open
+    pub fn abandon_round(&mut self, block_hash: &Blake2bHash) -> bool {
+        match self.active_rounds.get_mut(block_hash) {
+            Some(round) if !round.finalized => {
+                println!("🏳️  Abandoned consensus round for block: {}", hex::encode(block_hash.as_bytes()));
+                round.finalized = true;
+                round.result = Some(false);
+                true
+            }
+            _ => false,
+        }
     }
 
     /// Get the status of all active consensus rounds
@@ -230,12 +395,131 @@ impl SimpleConsensus {
         self.active_rounds.iter().collect()
     }
 
+    /// Number of consensus rounds that have not been finalized yet
+    pub fn open_round_count(&self) -> usize {
+        self.active_rounds.values().filter(|r| !r.finalized).count()
+    }
+
+    /// Change the maximum number of simultaneously open consensus rounds
+    pub fn set_max_concurrent_rounds(&mut self, max_concurrent_rounds: usize) {
+        self.config.max_concurrent_rounds = max_concurrent_rounds;
+    }
+
+    /// Change the rule used to choose between same-height blocks
+    pub fn set_tie_break_rule(&mut self, rule: TieBreakRule) {
+        self.config.tie_break = rule;
+    }
+
+    /// Change the per-validator vote weights; validators left out weigh 1
+    pub fn set_validator_weights(&mut self, validator_weights: HashMap<String, u64>) {
+        self.config.validator_weights = validator_weights;
+    }
+
+    /// Whether a validator id belongs to the validator set
+    pub fn is_known_validator(&self, validator_id: &str) -> bool {
+        self.validators.contains_key(validator_id)
+    }
+
+    /// Change how many votes a round needs before it can finalize
+    pub fn set_min_validators(&mut self, min_validators: usize) {
+        self.config.min_validators = min_validators;
+    }
+
+    /// Effective finalization requirements and validator set size
+    pub fn get_settings(&self) -> ConsensusSettings {
+        ConsensusSettings {
+            min_validators: self.config.min_validators,
+            approval_threshold: self.config.approval_threshold,
+            known_validators: self.validators.len(),
+            active_validators: self.validators.values().filter(|v| v.is_active).count(),
+        }
+    }
+
+    /// Per-round view of votes and age for every round still tracked, oldest first
+    pub fn round_statuses(&self) -> Vec<RoundStatus> {
+        let now = SystemTime::now();
+        let mut statuses: Vec<RoundStatus> = self.active_rounds.values()
+            .map(|round| {
+                let mut votes: Vec<VoteStatus> = round.votes.values()
+                    .map(|vote| VoteStatus {
+                        validator_id: vote.validator_id.clone(),
+                        approve: vote.approve,
+                    })
+                    .collect();
+                votes.sort_by(|a, b| a.validator_id.cmp(&b.validator_id));
+
+                RoundStatus {
+                    block_hash: round.block_hash,
+                    votes_received: round.votes.len(),
+                    votes_needed: self.config.min_validators,
+                    approvals: votes.iter().filter(|v| v.approve).count(),
+                    rejections: votes.iter().filter(|v| !v.approve).count(),
+                    votes,
+                    age_secs: now.duration_since(round.started_at).map(|d| d.as_secs()).unwrap_or(0),
+                    finalized: round.finalized,
+                    result: round.result,
+                }
+            })
+            .collect();
+        statuses.sort_by(|a, b| b.age_secs.cmp(&a.age_secs));
+        statuses
+    }
+
+    /// Get consensus round metrics
+    pub fn get_metrics(&self) -> ConsensusMetrics {
+        ConsensusMetrics {
+            open_rounds: self.open_round_count(),
+            finalized_rounds: self.active_rounds.len() - self.open_round_count(),
+            max_concurrent_rounds: self.config.max_concurrent_rounds,
+            rejected_rounds: self.rejected_rounds,
+        }
+    }
+
     /// Get validator information
     pub fn get_validators(&self) -> &HashMap<String, ValidatorInfo> {
         &self.validators
     }
 }
 
+/// How one validator voted in a round
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct VoteStatus {
+    pub validator_id: String,
+    pub approve: bool,
+}
+
+/// Snapshot of a single consensus round for operators debugging stuck blocks
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RoundStatus {
+    pub block_hash: Blake2bHash,
+    pub votes_received: usize,
+    pub votes_needed: usize,
+    pub approvals: usize,
+    pub rejections: usize,
+    pub votes: Vec<VoteStatus>,
+    pub age_secs: u64,
+    pub finalized: bool,
+    pub result: Option<bool>,
+}
+
+/// Finalization requirements a node is running with
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConsensusSettings {
+    pub min_validators: usize,
+    pub approval_threshold: f64,
+    pub known_validators: usize,
+    pub active_validators: usize,
+}
+
+/// Snapshot of consensus round usage
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConsensusMetrics {
+    pub open_rounds: usize,
+    pub finalized_rounds: usize,
+    pub max_concurrent_rounds: usize,
+    pub rejected_rounds: u64,
+}
+
 #[derive(Debug)]
 pub enum ConsensusResult {
     InProgress {
@@ -270,6 +554,9 @@ pub enum ConsensusError {
 
     #[error("Consensus round already finalized")]
     RoundAlreadyFinalized,
+
+    #[error("A competing block is preferred at height {0}")]
+    CompetingBlockPreferred(u64),
 }
 
 #[cfg(test)]
@@ -320,4 +607,159 @@ mod tests {
             _ => panic!("Expected consensus to be finalized"),
         }
     }
+
+    #[test]
+    fn test_same_height_blocks_converge_on_tie_break_winner() {
+        for rule in [TieBreakRule::LowestHash, TieBreakRule::EarliestTimestampThenHash] {
+            let config = ConsensusConfig { tie_break: rule, ..ConsensusConfig::default() };
+            let mut node_a = SimpleConsensus::new(config.clone());
+            let mut node_b = SimpleConsensus::new(config);
+
+            let block_a = BlockCandidate { block_hash: Blake2bHash::hash(b"block_from_a"), timestamp_millis: 2_000 };
+            let block_b = BlockCandidate { block_hash: Blake2bHash::hash(b"block_from_b"), timestamp_millis: 1_000 };
+
+            // Each node sees its own proposal first, then the competitor
+            assert!(node_a.register_candidate(5, block_a));
+            node_a.register_candidate(5, block_b);
+            assert!(node_b.register_candidate(5, block_b));
+            node_b.register_candidate(5, block_a);
+
+            let expected = match rule {
+                TieBreakRule::LowestHash => {
+                    if block_a.block_hash.as_bytes() < block_b.block_hash.as_bytes() { block_a } else { block_b }
+                }
+                TieBreakRule::EarliestTimestampThenHash => block_b,
+            };
+            let loser = if expected == block_a { block_b } else { block_a };
+
+            for node in [&node_a, &node_b] {
+                assert!(node.is_preferred_candidate(5, &expected.block_hash));
+                assert!(!node.is_preferred_candidate(5, &loser.block_hash));
+            }
+
+            // Other heights are unaffected
+            assert!(node_a.is_preferred_candidate(6, &loser.block_hash));
+        }
+    }
+
+    #[test]
+    fn test_max_concurrent_rounds_enforced() {
+        let config = ConsensusConfig {
+            max_concurrent_rounds: 2,
+            ..ConsensusConfig::default()
+        };
+        let mut consensus = SimpleConsensus::new(config);
+        let block_a = Blake2bHash::hash(b"block_a");
+        let block_b = Blake2bHash::hash(b"block_b");
+        let block_c = Blake2bHash::hash(b"block_c");
+
+        consensus.start_consensus(block_a).unwrap();
+        consensus.start_consensus(block_b).unwrap();
+
+        // Limit reached - the next round is rejected
+        assert!(matches!(
+            consensus.start_consensus(block_c),
+            Err(ConsensusError::TooManyActiveRounds)
+        ));
+        assert_eq!(consensus.get_metrics().open_rounds, 2);
+        assert_eq!(consensus.get_metrics().rejected_rounds, 1);
+
+        // Complete one round
+        for validator_id in ["tmobile-de", "vodafone-uk", "orange-fr"] {
+            consensus.process_vote(Vote {
+                validator_id: validator_id.to_string(),
+                block_hash: block_a,
+                approve: true,
+                signature: vec![],
+                timestamp: SystemTime::now(),
+            }).unwrap();
+        }
+
+        // A slot is free again
+        consensus.start_consensus(block_c).unwrap();
+        let metrics = consensus.get_metrics();
+        assert_eq!(metrics.open_rounds, 2);
+        assert_eq!(metrics.finalized_rounds, 1);
+
+        // Abandoning a round frees its slot too, and later votes find it closed
+        assert!(consensus.abandon_round(&block_b));
+        assert!(!consensus.abandon_round(&block_b));
+        assert_eq!(consensus.get_metrics().open_rounds, 1);
+        assert!(matches!(
+            consensus.process_vote(Vote {
+                validator_id: "tmobile-de".to_string(),
+                block_hash: block_b,
+                approve: true,
+                signature: vec![],
+                timestamp: SystemTime::now(),
+            }),
+            Err(ConsensusError::RoundAlreadyFinalized)
+        ));
+    }
+
+    #[test]
+    fn test_round_status_reports_votes() {
+        let mut consensus = SimpleConsensus::new(ConsensusConfig::default());
+        let block_hash = Blake2bHash::hash(b"stuck_block");
+        consensus.start_consensus(block_hash).unwrap();
+
+        for (validator_id, approve) in [("vodafone-uk", true), ("orange-fr", false)] {
+            consensus.process_vote(Vote {
+                validator_id: validator_id.to_string(),
+                block_hash,
+                approve,
+                signature: vec![],
+                timestamp: SystemTime::now(),
+            }).unwrap();
+        }
+
+        let statuses = consensus.round_statuses();
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert_eq!(status.block_hash, block_hash);
+        assert_eq!((status.votes_received, status.votes_needed), (2, 3));
+        assert_eq!((status.approvals, status.rejections), (1, 1));
+        assert_eq!(status.votes[0].validator_id, "orange-fr");
+        assert!(!status.votes[0].approve);
+        assert!(!status.finalized);
+    }
+
+    #[test]
+    fn test_weighted_approval_outvotes_raw_count() {
+        let votes = [("tmobile-de", true), ("vodafone-uk", false), ("orange-fr", false)];
+        let run = |config: ConsensusConfig| {
+            let mut consensus = SimpleConsensus::new(config);
+            let block_hash = Blake2bHash::hash(b"weighted_block");
+            consensus.start_consensus(block_hash).unwrap();
+
+            let mut result = None;
+            for (validator_id, approve) in votes {
+                result = Some(consensus.process_vote(Vote {
+                    validator_id: validator_id.to_string(),
+                    block_hash,
+                    approve,
+                    signature: vec![],
+                    timestamp: SystemTime::now(),
+                }).unwrap());
+            }
+            result.unwrap()
+        };
+
+        // 1 of 3 validators approve: below the 67% threshold by count
+        assert!(!matches!(run(ConsensusConfig::default()), ConsensusResult::Finalized { approved: true }));
+
+        // tmobile-de carries 10 of the 14 total validator weight (~71%)
+        let weighted = ConsensusConfig {
+            validator_weights: HashMap::from([("tmobile-de".to_string(), 10)]),
+            ..ConsensusConfig::default()
+        };
+        assert!(matches!(run(weighted), ConsensusResult::Finalized { approved: true }));
+
+        // 5 of the 7 voted weight, but only 5 of the 9 total: not enough
+        let outweighed = ConsensusConfig {
+            validator_weights: HashMap::from([("tmobile-de".to_string(), 5)]),
+            ..ConsensusConfig::default()
+        };
+        assert!(matches!(run(outweighed), ConsensusResult::InProgress { .. }));
+    }
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 pub mod p2p;
 pub mod consensus;
+pub mod compression;
 
 use crate::hash::Blake2bHash;
 use serde::{Deserialize, Serialize};
@@ -11,6 +12,11 @@ pub enum NetworkMessage {
         block_hash: Blake2bHash,
         block_data: Vec<u8>,
     },
+    /// NewBlock with zstd-compressed block_data, only sent once every peer supports it
+    CompressedBlock {
+        block_hash: Blake2bHash,
+        block_data: Vec<u8>,
+    },
     RequestBlock {
         block_hash: Blake2bHash,
     },
@@ -40,12 +46,61 @@ pub enum NetworkMessage {
     Pong,
 }
 
+/// Wire protocol version advertised to peers; bump the major version for any
+/// change to `NetworkMessage` that older nodes can't deserialize
+pub const PROTOCOL_VERSION: &str = "1.1.0";
+
+const PROTOCOL_PREFIX: &str = "/sp-blockchain/";
+
+/// Identify protocol string for a wire protocol version
+pub fn protocol_id(version: &str) -> String {
+    format!("{}{}", PROTOCOL_PREFIX, version)
+}
+
+/// Version advertised in a peer's identify protocol string, if it is an SP blockchain node
+pub fn parse_protocol_version(protocol: &str) -> Option<&str> {
+    protocol.strip_prefix(PROTOCOL_PREFIX)
+}
+
+/// Peers can exchange messages only if their major protocol versions match
+pub fn is_compatible_version(version: &str) -> bool {
+    let major = |v: &str| v.split('.').next().and_then(|m| m.parse::<u64>().ok());
+    match (major(version), major(PROTOCOL_VERSION)) {
+        (Some(remote), Some(local)) => remote == local,
+        _ => false,
+    }
+}
+
+/// How a node finds consortium peers beyond the ones it was bootstrapped with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiscoveryMode {
+    /// Multicast DNS on the local network, for single-host and docker test setups
+    #[default]
+    Mdns,
+    /// Kademlia DHT lookups seeded from the bootstrap peers
+    Kademlia,
+}
+
+impl std::str::FromStr for DiscoveryMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mdns" => Ok(DiscoveryMode::Mdns),
+            "kademlia" => Ok(DiscoveryMode::Kademlia),
+            other => Err(format!("unknown discovery mode '{}' (expected mdns or kademlia)", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
     pub node_id: String,
     pub peer_id: libp2p::PeerId,
     pub addresses: Vec<libp2p::Multiaddr>,
     pub last_seen: std::time::SystemTime,
+    /// Protocol version agreed in the handshake, once the peer has identified itself
+    pub protocol_version: Option<String>,
 }
 
 pub type NetworkResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
\ No newline at end of file
@@ -4,23 +4,90 @@ use libp2p::{
     kad::{self, store::MemoryStore},
     mdns,
     noise,
-    swarm::{NetworkBehaviour, SwarmEvent},
-    tcp, yamux, Multiaddr, PeerId, Swarm, Transport,
+    multiaddr::Protocol,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour, SwarmEvent},
+    tcp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm, Transport,
 };
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::{Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use futures::StreamExt;
 use tokio::sync::mpsc;
 use async_trait::async_trait;
 
-use super::{NetworkMessage, NetworkResult, PeerInfo};
+use super::compression::{compress_new_block, decompress_new_block, supports_block_compression};
+use super::{
+    is_compatible_version, parse_protocol_version, protocol_id,
+    DiscoveryMode, NetworkMessage, NetworkResult, PeerInfo, PROTOCOL_VERSION,
+};
+use crate::hash::Blake2bHash;
+
+/// Kademlia protocol name, so the consortium DHT stays separate from public ones
+const KADEMLIA_PROTOCOL: StreamProtocol = StreamProtocol::new("/sp-blockchain/kad/1.0.0");
+
+/// How often Kademlia re-runs its bootstrap lookup to pick up newly joined nodes
+const KADEMLIA_BOOTSTRAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How many recent block/vote messages are remembered for deduplication
+pub const SEEN_MESSAGE_CACHE_SIZE: usize = 4096;
+
+/// How many gossip messages are held per peer while its version handshake is outstanding
+const MAX_PRE_HANDSHAKE_MESSAGES: usize = 64;
+
+/// Least-recently-seen set of message hashes, so a block or vote relayed by
+/// several peers is only handed to the blockchain once
+pub struct SeenMessageCache {
+    capacity: usize,
+    seen: HashSet<Blake2bHash>,
+    // Front = oldest
+    order: VecDeque<Blake2bHash>,
+}
+
+impl SeenMessageCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Record a message hash, returning false if it was already seen
+    pub fn insert(&mut self, message_hash: Blake2bHash) -> bool {
+        if self.seen.contains(&message_hash) {
+            self.order.retain(|hash| *hash != message_hash);
+            self.order.push_back(message_hash);
+            return false;
+        }
+
+        self.seen.insert(message_hash);
+        self.order.push_back(message_hash);
+        while self.seen.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.seen.remove(&evicted);
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
 
 // Network behavior combining all protocols
 #[derive(NetworkBehaviour)]
 pub struct SpBlockchainBehaviour {
     pub gossipsub: gossipsub::Behaviour,
-    pub mdns: mdns::tokio::Behaviour,
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
     pub kademlia: kad::Behaviour<MemoryStore>,
     pub identify: identify::Behaviour,
 }
@@ -30,12 +97,37 @@ pub struct P2PNetwork {
     message_sender: mpsc::UnboundedSender<NetworkMessage>,
     message_receiver: mpsc::UnboundedReceiver<NetworkMessage>,
     peers: std::collections::HashMap<PeerId, PeerInfo>,
+    // Mirrors peers.len() for readers outside the swarm task (e.g. the health endpoint)
+    peer_count: Arc<AtomicUsize>,
     node_id: String,
     message_callback: Option<mpsc::UnboundedSender<NetworkMessage>>,
+    seen_messages: SeenMessageCache,
+    discovery: DiscoveryMode,
+    kademlia_bootstrapped: bool,
+    // Peers that completed the version handshake on their current connection
+    peer_versions: HashMap<PeerId, String>,
+    // Gossip that arrived before its peer's handshake, replayed once the peer is identified
+    pre_handshake_gossip: HashMap<PeerId, Vec<gossipsub::Message>>,
+    // Peers disconnected for an incompatible protocol version
+    rejected_peers: HashSet<PeerId>,
 }
 
 impl P2PNetwork {
-    pub async fn new(node_id: String, listen_port: u16) -> NetworkResult<Self> {
+    /// Listen on `listen_addr`; an unspecified IP (`0.0.0.0`) binds all interfaces
+    pub async fn new(node_id: String, listen_addr: SocketAddr) -> NetworkResult<Self> {
+        Self::with_discovery(node_id, listen_addr, DiscoveryMode::default()).await
+    }
+
+    pub async fn with_discovery(node_id: String, listen_addr: SocketAddr, discovery: DiscoveryMode) -> NetworkResult<Self> {
+        Self::build(node_id, listen_addr, discovery, PROTOCOL_VERSION).await
+    }
+
+    async fn build(
+        node_id: String,
+        listen_addr: SocketAddr,
+        discovery: DiscoveryMode,
+        protocol_version: &str,
+    ) -> NetworkResult<Self> {
         // Generate a random PeerId
         let local_key = libp2p::identity::Keypair::generate_ed25519();
         let local_peer_id = PeerId::from(local_key.public());
@@ -69,23 +161,31 @@ impl P2PNetwork {
         gossipsub.subscribe(&gossipsub_topic)?;
 
         // Set up mDNS for local peer discovery
-        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)?;
+        let mdns = match discovery {
+            DiscoveryMode::Mdns => Some(mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)?),
+            DiscoveryMode::Kademlia => None,
+        };
 
         // Set up Kademlia for DHT
-        let kademlia = kad::Behaviour::new(
+        let mut kademlia_config = kad::Config::new(KADEMLIA_PROTOCOL);
+        kademlia_config.set_periodic_bootstrap_interval(Some(KADEMLIA_BOOTSTRAP_INTERVAL));
+        let mut kademlia = kad::Behaviour::with_config(
             local_peer_id,
             MemoryStore::new(local_peer_id),
+            kademlia_config,
         );
+        // Every consortium node answers DHT queries, even before it learns an external address
+        kademlia.set_mode(Some(kad::Mode::Server));
 
-        // Set up identify protocol
+        // Set up identify protocol; it runs as soon as a connection opens and doubles as the version handshake
         let identify = identify::Behaviour::new(identify::Config::new(
-            "/sp-blockchain/1.0.0".into(),
+            protocol_id(protocol_version),
             local_key.public(),
         ));
 
         let behaviour = SpBlockchainBehaviour {
             gossipsub,
-            mdns,
+            mdns: mdns.into(),
             kademlia,
             identify,
         };
@@ -93,9 +193,7 @@ impl P2PNetwork {
         let swarm_config = libp2p::swarm::Config::with_tokio_executor();
         let mut swarm = Swarm::new(transport, behaviour, local_peer_id, swarm_config);
 
-        // Listen on all interfaces
-        let listen_addr: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", listen_port).parse()?;
-        swarm.listen_on(listen_addr)?;
+        swarm.listen_on(tcp_multiaddr(listen_addr))?;
 
         let (message_sender, message_receiver) = mpsc::unbounded_channel();
 
@@ -104,8 +202,15 @@ impl P2PNetwork {
             message_sender,
             message_receiver,
             peers: std::collections::HashMap::new(),
+            peer_count: Arc::new(AtomicUsize::new(0)),
             node_id,
             message_callback: None,
+            seen_messages: SeenMessageCache::new(SEEN_MESSAGE_CACHE_SIZE),
+            discovery,
+            kademlia_bootstrapped: false,
+            peer_versions: HashMap::new(),
+            pre_handshake_gossip: HashMap::new(),
+            rejected_peers: HashSet::new(),
         })
     }
 
@@ -139,20 +244,44 @@ impl P2PNetwork {
                 println!("🎧 Listening on: {}", address);
             }
 
+            SwarmEvent::ConnectionEstablished { peer_id, .. } if self.rejected_peers.contains(&peer_id) => {
+                let _ = self.swarm.disconnect_peer_id(peer_id);
+            }
+
+            SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                // A reconnecting peer has to complete the handshake again
+                self.peer_versions.remove(&peer_id);
+                self.pre_handshake_gossip.remove(&peer_id);
+                // mDNS reports departures itself through Expired; DHT peers are dropped here
+                if self.discovery == DiscoveryMode::Kademlia {
+                    println!("🚫 Peer disconnected: {}", peer_id);
+                    self.forget_peer(&peer_id);
+                }
+            }
+
             SwarmEvent::Behaviour(event) => match event {
                 SpBlockchainBehaviourEvent::Mdns(mdns::Event::Discovered(list)) => {
                     for (peer_id, multiaddr) in list {
                         println!("🔍 Discovered peer: {} at {}", peer_id, multiaddr);
-                        self.swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
                         self.swarm.behaviour_mut().kademlia.add_address(&peer_id, multiaddr.clone());
+                        self.record_peer(peer_id, vec![multiaddr]);
+                    }
+                }
 
-                        // Add to peer list
-                        self.peers.insert(peer_id, PeerInfo {
-                            node_id: peer_id.to_string(),
-                            peer_id,
-                            addresses: vec![multiaddr],
-                            last_seen: std::time::SystemTime::now(),
-                        });
+                SpBlockchainBehaviourEvent::Kademlia(kad::Event::RoutingUpdated {
+                    peer,
+                    is_new_peer,
+                    addresses,
+                    ..
+                }) => {
+                    if is_new_peer {
+                        println!("🧭 Discovered peer via Kademlia: {}", peer);
+                    }
+                    // Peers learned from DHT lookups aren't connected yet
+                    if self.record_peer(peer, addresses.into_vec()) && !self.swarm.is_connected(&peer) {
+                        if let Err(e) = self.swarm.dial(peer) {
+                            println!("❌ Failed to dial discovered peer {}: {}", peer, e);
+                        }
                     }
                 }
 
@@ -162,14 +291,15 @@ impl P2PNetwork {
                         self.swarm.behaviour_mut().gossipsub.remove_explicit_peer(&peer_id);
                         self.peers.remove(&peer_id);
                     }
+                    self.peer_count.store(self.peers.len(), Ordering::Relaxed);
                 }
 
                 SpBlockchainBehaviourEvent::Gossipsub(gossipsub::Event::Message {
-                    propagation_source: _,
+                    propagation_source,
                     message_id: _,
                     message,
                 }) => {
-                    self.handle_gossip_message(message).await?;
+                    self.handle_gossip_message(propagation_source, message).await?;
                 }
 
                 SpBlockchainBehaviourEvent::Identify(identify::Event::Received {
@@ -178,9 +308,32 @@ impl P2PNetwork {
                     ..
                 }) => {
                     println!("🆔 Identified peer: {} - Agent: {}", peer_id, info.agent_version);
+
+                    let version = match parse_protocol_version(&info.protocol_version) {
+                        Some(version) if is_compatible_version(version) => version.to_string(),
+                        _ => {
+                            println!("⛔ Rejecting peer {}: protocol {} is incompatible with {}",
+                                     peer_id, info.protocol_version, protocol_id(PROTOCOL_VERSION));
+                            self.reject_peer(peer_id);
+                            return Ok(());
+                        }
+                    };
+                    self.complete_handshake(peer_id, version).await?;
+
                     for addr in info.listen_addrs {
                         self.swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
                     }
+
+                    // The first identified peer (normally a bootstrap peer) seeds the DHT lookup
+                    if self.discovery == DiscoveryMode::Kademlia && !self.kademlia_bootstrapped {
+                        match self.swarm.behaviour_mut().kademlia.bootstrap() {
+                            Ok(_) => {
+                                println!("🧭 Kademlia bootstrap started via {}", peer_id);
+                                self.kademlia_bootstrapped = true;
+                            }
+                            Err(e) => println!("❌ Kademlia bootstrap failed: {:?}", e),
+                        }
+                    }
                 }
 
                 _ => {}
@@ -191,9 +344,99 @@ impl P2PNetwork {
         Ok(())
     }
 
-    async fn handle_gossip_message(&mut self, message: gossipsub::Message) -> NetworkResult<()> {
+    /// Track a discovered peer and include it in gossip, unless it was rejected
+    fn record_peer(&mut self, peer_id: PeerId, addresses: Vec<Multiaddr>) -> bool {
+        if self.rejected_peers.contains(&peer_id) {
+            return false;
+        }
+
+        self.swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
+        self.peers.insert(peer_id, PeerInfo {
+            node_id: peer_id.to_string(),
+            peer_id,
+            addresses,
+            last_seen: std::time::SystemTime::now(),
+            protocol_version: self.peer_versions.get(&peer_id).cloned(),
+        });
+        self.peer_count.store(self.peers.len(), Ordering::Relaxed);
+        true
+    }
+
+    /// Gossip reaches every connected peer, so compress only if all of them negotiated support
+    fn peers_support_compression(&self) -> bool {
+        self.swarm.connected_peers().all(|peer_id| {
+            self.peer_versions.get(peer_id).is_some_and(|version| supports_block_compression(version))
+        })
+    }
+
+    /// Record a peer's negotiated protocol version and deliver the gossip it sent before
+    async fn complete_handshake(&mut self, peer_id: PeerId, version: String) -> NetworkResult<()> {
+        if let Some(peer) = self.peers.get_mut(&peer_id) {
+            peer.protocol_version = Some(version.clone());
+        }
+        self.peer_versions.insert(peer_id, version);
+
+        for message in self.pre_handshake_gossip.remove(&peer_id).unwrap_or_default() {
+            self.handle_gossip_message(peer_id, message).await?;
+        }
+        Ok(())
+    }
+
+    /// Disconnect a peer speaking an incompatible protocol and stop discovering it
+    fn reject_peer(&mut self, peer_id: PeerId) {
+        self.rejected_peers.insert(peer_id);
+        self.peer_versions.remove(&peer_id);
+        self.pre_handshake_gossip.remove(&peer_id);
+        self.forget_peer(&peer_id);
+        let _ = self.swarm.disconnect_peer_id(peer_id);
+    }
+
+    /// Drop a peer from gossip, the DHT routing table and the peer list
+    fn forget_peer(&mut self, peer_id: &PeerId) {
+        self.swarm.behaviour_mut().gossipsub.remove_explicit_peer(peer_id);
+        self.swarm.behaviour_mut().kademlia.remove_peer(peer_id);
+        self.peers.remove(peer_id);
+        self.peer_count.store(self.peers.len(), Ordering::Relaxed);
+    }
+
+    async fn handle_gossip_message(&mut self, source: PeerId, message: gossipsub::Message) -> NetworkResult<()> {
+        if self.rejected_peers.contains(&source) {
+            println!("⛔ Dropping message from rejected peer {}", source);
+            return Ok(());
+        }
+
+        // Nothing is processed from a peer until it has passed the version handshake; gossip
+        // can overtake identify on a fresh connection, so it is held until then
+        if !self.peer_versions.contains_key(&source) {
+            let held = self.pre_handshake_gossip.entry(source).or_default();
+            if held.len() < MAX_PRE_HANDSHAKE_MESSAGES {
+                println!("⏳ Holding message from {} until protocol handshake", source);
+                held.push(message);
+            } else {
+                println!("⏳ Dropping message from {}: too many before protocol handshake", source);
+            }
+            return Ok(());
+        }
+
         // Deserialize the network message
         if let Ok(network_msg) = serde_json::from_slice::<NetworkMessage>(&message.data) {
+            // The blockchain only ever sees uncompressed blocks
+            let network_msg = match decompress_new_block(network_msg) {
+                Ok(network_msg) => network_msg,
+                Err(e) => {
+                    println!("❌ Dropping block that failed to decompress: {}", e);
+                    return Ok(());
+                }
+            };
+
+            // Blocks and votes re-run consensus processing, so drop any we've already handled
+            if matches!(network_msg, NetworkMessage::NewBlock { .. } | NetworkMessage::Vote { .. })
+                && !self.seen_messages.insert(Blake2bHash::hash(&message.data))
+            {
+                println!("♻️  Dropping duplicate network message");
+                return Ok(());
+            }
+
             println!("📨 Received network message: {:?}", network_msg);
 
             // Forward to blockchain layer
@@ -218,6 +461,15 @@ impl P2PNetwork {
                         }
                     }
                 }
+                NetworkMessage::RequestBlock { block_hash } | NetworkMessage::BlockResponse { block_hash, .. } => {
+                    println!("🔎 Block sync message received for: {}", hex::encode(block_hash.as_bytes()));
+                    // Forward to blockchain message handler
+                    if let Some(ref callback) = self.message_callback {
+                        if let Err(e) = callback.send(network_msg) {
+                            println!("❌ Failed to forward block sync message to blockchain: {}", e);
+                        }
+                    }
+                }
                 NetworkMessage::Ping => {
                     // Respond with pong
                     self.broadcast_message(NetworkMessage::Pong).await?;
@@ -229,6 +481,11 @@ impl P2PNetwork {
     }
 
     async fn handle_outgoing_message(&mut self, message: NetworkMessage) -> NetworkResult<()> {
+        let message = if self.peers_support_compression() {
+            compress_new_block(message)?
+        } else {
+            message
+        };
         let serialized = serde_json::to_vec(&message)?;
 
         // Publish to gossipsub topic
@@ -256,6 +513,15 @@ impl P2PNetwork {
         self.peers.values().collect()
     }
 
+    /// Shared count of connected peers that stays current while the network runs
+    pub fn peer_count_handle(&self) -> Arc<AtomicUsize> {
+        self.peer_count.clone()
+    }
+
+    pub fn discovery_mode(&self) -> DiscoveryMode {
+        self.discovery
+    }
+
     pub fn get_node_id(&self) -> &str {
         &self.node_id
     }
@@ -266,6 +532,23 @@ impl P2PNetwork {
     }
 }
 
+/// Turn a bootstrap peer given as a multiaddr or `host:port` into a dialable address
+pub async fn resolve_peer_address(peer: &str) -> NetworkResult<Multiaddr> {
+    if peer.starts_with('/') {
+        return Ok(peer.parse()?);
+    }
+
+    let socket_addr = tokio::net::lookup_host(peer).await?
+        .next()
+        .ok_or_else(|| format!("No address found for peer {}", peer))?;
+    Ok(tcp_multiaddr(socket_addr))
+}
+
+/// TCP multiaddr of a socket address, e.g. `/ip4/10.0.0.5/tcp/30303`
+pub fn tcp_multiaddr(addr: SocketAddr) -> Multiaddr {
+    Multiaddr::from(addr.ip()).with(Protocol::Tcp(addr.port()))
+}
+
 #[async_trait]
 pub trait BlockchainNetwork {
     async fn broadcast_block(&mut self, block_hash: crate::hash::Blake2bHash, block_data: Vec<u8>) -> NetworkResult<()>;
@@ -300,4 +583,218 @@ impl BlockchainNetwork for P2PNetwork {
         let message = NetworkMessage::RequestChainState;
         self.broadcast_message(message).await
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gossip_message(network_msg: &NetworkMessage) -> gossipsub::Message {
+        gossipsub::Message {
+            source: None,
+            data: serde_json::to_vec(network_msg).unwrap(),
+            sequence_number: None,
+            topic: IdentTopic::new("sp-blockchain-settlement").hash(),
+        }
+    }
+
+    #[test]
+    fn test_seen_message_cache_evicts_oldest() {
+        let mut cache = SeenMessageCache::new(2);
+        let (a, b, c) = (Blake2bHash::hash(b"a"), Blake2bHash::hash(b"b"), Blake2bHash::hash(b"c"));
+
+        assert!(cache.insert(a));
+        assert!(!cache.insert(a));
+        assert!(cache.insert(b));
+        assert!(cache.insert(c));
+        assert_eq!(cache.len(), 2);
+
+        // a was evicted, so it counts as new again
+        assert!(cache.insert(a));
+        assert!(!cache.insert(c));
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn any_addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([0, 0, 0, 0], port))
+    }
+
+    #[test]
+    fn test_protocol_version_compatibility() {
+        assert_eq!(parse_protocol_version(&protocol_id("1.3.0")), Some("1.3.0"));
+        assert_eq!(parse_protocol_version("/ipfs/id/1.0.0"), None);
+        assert!(is_compatible_version("1.3.0"));
+        assert!(!is_compatible_version("2.0.0"));
+        assert!(!is_compatible_version("garbage"));
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_protocol_version_rejected() {
+        let port = free_port();
+        let addr: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap();
+
+        let mut node = P2PNetwork::with_discovery("node-a".to_string(), any_addr(port), DiscoveryMode::Kademlia).await.unwrap();
+        let (callback_tx, mut callback_rx) = mpsc::unbounded_channel();
+        node.set_message_callback(callback_tx);
+
+        let mut newer = P2PNetwork::build("node-b".to_string(), any_addr(free_port()), DiscoveryMode::Kademlia, "2.0.0").await.unwrap();
+        let mut compatible = P2PNetwork::build("node-c".to_string(), any_addr(free_port()), DiscoveryMode::Kademlia, "1.4.0").await.unwrap();
+        newer.add_peer(addr.clone()).unwrap();
+        compatible.add_peer(addr).unwrap();
+        let newer_id = *newer.swarm.local_peer_id();
+        let compatible_id = *compatible.swarm.local_peer_id();
+
+        let _ = tokio::time::timeout(Duration::from_secs(3), async {
+            tokio::join!(node.start(), newer.start(), compatible.start())
+        }).await;
+
+        assert!(node.rejected_peers.contains(&newer_id));
+        assert!(!node.swarm.is_connected(&newer_id));
+        assert!(!node.peers.contains_key(&newer_id));
+        assert_eq!(node.peer_versions.get(&compatible_id).map(String::as_str), Some("1.4.0"));
+        assert_eq!(node.peers[&compatible_id].protocol_version.as_deref(), Some("1.4.0"));
+
+        // Blocks relayed by the rejected peer never reach the blockchain
+        let block = NetworkMessage::NewBlock {
+            block_hash: Blake2bHash::hash(b"block-1"),
+            block_data: b"{}".to_vec(),
+        };
+        node.handle_gossip_message(newer_id, gossip_message(&block)).await.unwrap();
+        assert!(callback_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_gossip_before_handshake_is_delivered_once_identified() {
+        let mut network = P2PNetwork::new("test-node".to_string(), any_addr(0)).await.unwrap();
+        let (callback_tx, mut callback_rx) = mpsc::unbounded_channel();
+        network.set_message_callback(callback_tx);
+        let source = PeerId::random();
+
+        let block = NetworkMessage::NewBlock {
+            block_hash: Blake2bHash::hash(b"block-1"),
+            block_data: b"{}".to_vec(),
+        };
+        network.handle_gossip_message(source, gossip_message(&block)).await.unwrap();
+        assert!(callback_rx.try_recv().is_err());
+
+        network.complete_handshake(source, PROTOCOL_VERSION.to_string()).await.unwrap();
+        assert!(matches!(callback_rx.try_recv(), Ok(NetworkMessage::NewBlock { .. })));
+        assert!(callback_rx.try_recv().is_err());
+        assert!(network.pre_handshake_gossip.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_peer_address() {
+        let addr = resolve_peer_address("127.0.0.1:30303").await.unwrap();
+        assert_eq!(addr.to_string(), "/ip4/127.0.0.1/tcp/30303");
+        let addr = resolve_peer_address("/ip4/10.0.0.2/tcp/30303").await.unwrap();
+        assert_eq!(addr.to_string(), "/ip4/10.0.0.2/tcp/30303");
+        assert!(resolve_peer_address("not-an-address").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_kademlia_discovers_peers_beyond_bootstrap() {
+        let bootstrap_port = free_port();
+        let bootstrap_addr: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", bootstrap_port).parse().unwrap();
+
+        let bootstrap = P2PNetwork::with_discovery("node-a".to_string(), any_addr(bootstrap_port), DiscoveryMode::Kademlia).await.unwrap();
+        let mut second = P2PNetwork::with_discovery("node-b".to_string(), any_addr(free_port()), DiscoveryMode::Kademlia).await.unwrap();
+        second.add_peer(bootstrap_addr.clone()).unwrap();
+        let mut third = P2PNetwork::with_discovery("node-c".to_string(), any_addr(free_port()), DiscoveryMode::Kademlia).await.unwrap();
+        let third_peers = third.peer_count_handle();
+
+        for mut network in [bootstrap, second] {
+            tokio::spawn(async move { network.start().await });
+        }
+
+        // Give node-b time to join through node-a before node-c arrives
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        third.add_peer(bootstrap_addr).unwrap();
+        tokio::spawn(async move { third.start().await });
+
+        // node-c only knows node-a and must find node-b through the DHT
+        tokio::time::timeout(Duration::from_secs(20), async {
+            while third_peers.load(Ordering::Relaxed) < 2 {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }).await.expect("node-c should discover both peers");
+    }
+
+    #[tokio::test]
+    async fn test_kademlia_peer_removed_on_disconnect() {
+        let port = free_port();
+        let mut node = P2PNetwork::with_discovery("node-a".to_string(), any_addr(port), DiscoveryMode::Kademlia).await.unwrap();
+        let mut other = P2PNetwork::with_discovery("node-b".to_string(), any_addr(free_port()), DiscoveryMode::Kademlia).await.unwrap();
+        let other_id = *other.swarm.local_peer_id();
+        other.add_peer(format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap()).unwrap();
+
+        let _ = tokio::time::timeout(Duration::from_secs(3), async {
+            tokio::join!(node.start(), other.start())
+        }).await;
+        assert!(node.peers.contains_key(&other_id));
+        assert_eq!(node.swarm.behaviour_mut().kademlia.kbuckets().count(), 1);
+
+        drop(other);
+        let _ = tokio::time::timeout(Duration::from_secs(2), node.start()).await;
+
+        assert!(!node.swarm.is_connected(&other_id));
+        assert!(!node.peers.contains_key(&other_id));
+        assert_eq!(node.peer_count_handle().load(Ordering::Relaxed), 0);
+        assert_eq!(node.swarm.behaviour_mut().kademlia.kbuckets().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_listens_only_on_configured_address() {
+        let port = free_port();
+        let mut network = P2PNetwork::new("test-node".to_string(), SocketAddr::from(([127, 0, 0, 1], port))).await.unwrap();
+
+        let address = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let SwarmEvent::NewListenAddr { address, .. } = network.swarm.select_next_some().await {
+                    return address;
+                }
+            }
+        }).await.expect("node should start listening");
+        assert_eq!(address, format!("/ip4/127.0.0.1/tcp/{}", port).parse::<Multiaddr>().unwrap());
+        assert_eq!(network.swarm.listeners().count(), 1);
+
+        assert_eq!(tcp_multiaddr("[::1]:30303".parse().unwrap()).to_string(), "/ip6/::1/tcp/30303");
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_block_forwarded_once() {
+        let mut network = P2PNetwork::new("test-node".to_string(), any_addr(0)).await.unwrap();
+        let (callback_tx, mut callback_rx) = mpsc::unbounded_channel();
+        network.set_message_callback(callback_tx);
+        let source = PeerId::random();
+        network.peer_versions.insert(source, PROTOCOL_VERSION.to_string());
+
+        let block = NetworkMessage::NewBlock {
+            block_hash: Blake2bHash::hash(b"block-1"),
+            block_data: b"{}".to_vec(),
+        };
+        network.handle_gossip_message(source, gossip_message(&block)).await.unwrap();
+        network.handle_gossip_message(source, gossip_message(&block)).await.unwrap();
+
+        assert!(matches!(callback_rx.try_recv(), Ok(NetworkMessage::NewBlock { .. })));
+        assert!(callback_rx.try_recv().is_err());
+
+        // A different vote for the same block is still delivered
+        let vote = |approve| NetworkMessage::Vote {
+            block_hash: Blake2bHash::hash(b"block-1"),
+            validator_id: "vodafone-uk".to_string(),
+            signature: vec![],
+            approve,
+        };
+        network.handle_gossip_message(source, gossip_message(&vote(true))).await.unwrap();
+        network.handle_gossip_message(source, gossip_message(&vote(true))).await.unwrap();
+        network.handle_gossip_message(source, gossip_message(&vote(false))).await.unwrap();
+
+        assert!(matches!(callback_rx.try_recv(), Ok(NetworkMessage::Vote { approve: true, .. })));
+        assert!(matches!(callback_rx.try_recv(), Ok(NetworkMessage::Vote { approve: false, .. })));
+        assert!(callback_rx.try_recv().is_err());
+    }
+}
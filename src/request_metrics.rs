@@ -0,0 +1,194 @@
+// Per-endpoint API request counts and latency histograms
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Upper bounds (inclusive, in milliseconds) of the latency histogram buckets; slower
+/// requests fall into a final overflow bucket
+pub const LATENCY_BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Key for requests that matched no route, so unknown paths cannot grow the table
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Cost class of an endpoint, so expensive submissions are not averaged in with cheap reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointClass {
+    /// Record submission, which generates and verifies ZK proofs
+    Submission,
+    /// Read-only request
+    Read,
+    /// Any other state-changing request
+    Write,
+}
+
+impl EndpointClass {
+    pub fn classify(method: &Method, route: &str) -> Self {
+        if route.starts_with("/api/v1/bce/submit") {
+            EndpointClass::Submission
+        } else if method == Method::GET || method == Method::HEAD {
+            EndpointClass::Read
+        } else {
+            EndpointClass::Write
+        }
+    }
+}
+
+/// Count and latency histogram of one route
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointMetrics {
+    pub class: EndpointClass,
+    pub request_count: u64,
+    pub error_count: u64, // Responses with a 4xx or 5xx status
+    pub total_latency_ms: f64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: f64,
+    /// Requests per bucket of `LATENCY_BUCKETS_MS`, plus the overflow bucket
+    pub latency_histogram: Vec<u64>,
+}
+
+impl EndpointMetrics {
+    fn new(class: EndpointClass) -> Self {
+        Self {
+            class,
+            request_count: 0,
+            error_count: 0,
+            total_latency_ms: 0.0,
+            avg_latency_ms: 0.0,
+            max_latency_ms: 0.0,
+            latency_histogram: vec![0; LATENCY_BUCKETS_MS.len() + 1],
+        }
+    }
+
+    fn record(&mut self, latency: Duration, is_error: bool) {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let bucket = LATENCY_BUCKETS_MS.iter()
+            .position(|bound| latency_ms <= *bound as f64)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        self.request_count += 1;
+        if is_error {
+            self.error_count += 1;
+        }
+        self.total_latency_ms += latency_ms;
+        self.avg_latency_ms = self.total_latency_ms / self.request_count as f64;
+        self.max_latency_ms = self.max_latency_ms.max(latency_ms);
+        self.latency_histogram[bucket] += 1;
+    }
+}
+
+/// Point-in-time copy of all endpoint metrics, keyed by "METHOD /route"
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestMetricsSnapshot {
+    pub latency_buckets_ms: Vec<u64>,
+    pub endpoints: BTreeMap<String, EndpointMetrics>,
+}
+
+/// Shared per-endpoint request metrics, filled in by `request_metrics_middleware`
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    endpoints: Mutex<HashMap<String, EndpointMetrics>>,
+}
+
+impl RequestMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one handled request against its route template (e.g. `/api/v1/blockchain/blocks/:number`)
+    pub fn record(&self, method: &Method, route: &str, latency: Duration, is_error: bool) {
+        let key = format!("{} {}", method, route);
+        let mut endpoints = self.endpoints.lock().unwrap();
+        endpoints.entry(key)
+            .or_insert_with(|| EndpointMetrics::new(EndpointClass::classify(method, route)))
+            .record(latency, is_error);
+    }
+
+    pub fn snapshot(&self) -> RequestMetricsSnapshot {
+        let endpoints = self.endpoints.lock().unwrap();
+        RequestMetricsSnapshot {
+            latency_buckets_ms: LATENCY_BUCKETS_MS.to_vec(),
+            endpoints: endpoints.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        }
+    }
+}
+
+/// Request metrics middleware - times every request and records it under its matched route
+pub async fn request_metrics_middleware(
+    State(metrics): State<Arc<RequestMetrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    // Route templates rather than raw paths, so block hashes and numbers share one entry
+    let route = request.extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let status = response.status();
+
+    metrics.record(&method, &route, start.elapsed(), status.is_client_error() || status.is_server_error());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_middleware_records_handled_request() {
+        let metrics = Arc::new(RequestMetrics::new());
+        let app = Router::new()
+            .route("/api/v1/blockchain/blocks/:number", get(|| async { "block" }))
+            .layer(middleware::from_fn_with_state(metrics.clone(), request_metrics_middleware));
+
+        for number in [1, 2] {
+            let request = Request::builder()
+                .uri(format!("/api/v1/blockchain/blocks/{}", number))
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+
+        let snapshot = metrics.snapshot();
+        let endpoint = &snapshot.endpoints["GET /api/v1/blockchain/blocks/:number"];
+        assert_eq!(snapshot.endpoints.len(), 1);
+        assert_eq!(endpoint.class, EndpointClass::Read);
+        assert_eq!(endpoint.request_count, 2);
+        assert_eq!(endpoint.error_count, 0);
+        assert_eq!(endpoint.latency_histogram.iter().sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn test_submission_tracked_apart_from_reads() {
+        let metrics = RequestMetrics::new();
+        metrics.record(&Method::POST, "/api/v1/bce/submit", Duration::from_millis(1_200), false);
+        metrics.record(&Method::GET, "/api/v1/bce/stats", Duration::from_micros(300), false);
+        metrics.record(&Method::POST, "/api/v1/bce/submit", Duration::from_secs(30), true);
+
+        let snapshot = metrics.snapshot();
+        let submit = &snapshot.endpoints["POST /api/v1/bce/submit"];
+        assert_eq!(submit.class, EndpointClass::Submission);
+        assert_eq!(submit.request_count, 2);
+        assert_eq!(submit.error_count, 1);
+        // 1.2s lands in the 2.5s bucket, 30s overflows
+        assert_eq!(submit.latency_histogram[9], 1);
+        assert_eq!(submit.latency_histogram[LATENCY_BUCKETS_MS.len()], 1);
+
+        let stats = &snapshot.endpoints["GET /api/v1/bce/stats"];
+        assert_eq!(stats.class, EndpointClass::Read);
+        assert_eq!(stats.latency_histogram[0], 1);
+    }
+}
@@ -0,0 +1,305 @@
+// Authentication audit log for security reviews
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use log::error;
+
+/// Most entries returned by one audit log query
+pub const MAX_AUDIT_QUERY_ENTRIES: usize = 1000;
+
+/// Size at which the audit log is moved to `<path>.1` and restarted, which bounds what a
+/// query has to read
+pub const MAX_AUDIT_LOG_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Denied,
+}
+
+/// One authentication or authorization decision
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    /// `None` when the caller could not be identified (e.g. an unknown API key)
+    pub provider_id: Option<String>,
+    pub endpoint: String,
+    pub outcome: AuditOutcome,
+    pub reason: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn new(provider_id: Option<&str>, endpoint: &str, outcome: AuditOutcome, reason: Option<String>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp() as u64,
+            provider_id: provider_id.map(str::to_string),
+            endpoint: endpoint.to_string(),
+            outcome,
+            reason,
+        }
+    }
+}
+
+/// Filter for `AuditLog::query`; unset fields match everything. Older pages are fetched
+/// by passing the oldest returned timestamp as `to`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub provider_id: Option<String>,
+    /// Newest matching entries to return, capped at `MAX_AUDIT_QUERY_ENTRIES`
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+            && self.provider_id.as_ref().is_none_or(|id| entry.provider_id.as_ref() == Some(id))
+    }
+}
+
+enum WriterCommand {
+    Record(AuditEntry),
+    /// Acknowledged once everything recorded before it has reached the file
+    Flush(mpsc::Sender<()>),
+}
+
+/// Append-only audit log of newline-delimited JSON entries. Entries are written by a
+/// background thread so recording never blocks a request on disk I/O; write failures are
+/// logged and never fail the request being audited.
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    sender: Option<mpsc::Sender<WriterCommand>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl AuditLog {
+    /// Audit log that records nothing
+    pub fn disabled() -> Self {
+        Self { path: None, sender: None, writer: None }
+    }
+
+    /// Append to the audit log at `path`, creating it if needed
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Self::open_with_max_bytes(path.as_ref(), MAX_AUDIT_LOG_BYTES)
+    }
+
+    fn open_with_max_bytes(path: &Path, max_bytes: u64) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let writer = AuditWriter {
+            path: path.to_path_buf(),
+            written: file.metadata()?.len(),
+            file: BufWriter::new(file),
+            max_bytes,
+        };
+
+        let (sender, commands) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("audit-log".to_string())
+            .spawn(move || writer.run(commands))?;
+        Ok(Self { path: Some(path.to_path_buf()), sender: Some(sender), writer: Some(writer) })
+    }
+
+    pub fn record(&self, entry: AuditEntry) {
+        let Some(sender) = &self.sender else { return };
+        if let Err(mpsc::SendError(WriterCommand::Record(entry))) = sender.send(WriterCommand::Record(entry)) {
+            error!("🚨 Audit log writer stopped - dropping entry for {}", entry.endpoint);
+        }
+    }
+
+    /// Newest entries matching `query` in the current log file, oldest first. Reads the
+    /// file from disk, so async callers should run it on a blocking thread.
+    pub fn query(&self, query: &AuditQuery) -> std::io::Result<Vec<AuditEntry>> {
+        let (Some(path), Some(sender)) = (&self.path, &self.sender) else { return Ok(Vec::new()) };
+        let limit = query.limit.unwrap_or(MAX_AUDIT_QUERY_ENTRIES).min(MAX_AUDIT_QUERY_ENTRIES);
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Entries still buffered by the writer have to be visible to the query
+        let (ack, flushed) = mpsc::channel();
+        if sender.send(WriterCommand::Flush(ack)).is_ok() {
+            let _ = flushed.recv();
+        }
+
+        let mut entries = VecDeque::with_capacity(limit);
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            // A torn last line after a crash is skipped rather than failing the query
+            match serde_json::from_str::<AuditEntry>(&line) {
+                Ok(entry) if query.matches(&entry) => {
+                    if entries.len() == limit {
+                        entries.pop_front();
+                    }
+                    entries.push_back(entry);
+                }
+                Ok(_) => {}
+                Err(e) => error!("🚨 Skipping unreadable audit log line: {}", e),
+            }
+        }
+        Ok(entries.into())
+    }
+}
+
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        // Closing the channel lets the writer drain what is queued and exit
+        self.sender.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+struct AuditWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+    written: u64,
+    max_bytes: u64,
+}
+
+impl AuditWriter {
+    fn run(mut self, commands: mpsc::Receiver<WriterCommand>) {
+        while let Ok(command) = commands.recv() {
+            // Everything queued behind the first command shares one flush
+            for command in std::iter::once(command).chain(commands.try_iter()) {
+                match command {
+                    WriterCommand::Record(entry) => self.write(&entry),
+                    WriterCommand::Flush(ack) => {
+                        self.flush();
+                        let _ = ack.send(());
+                    }
+                }
+            }
+            self.flush();
+        }
+    }
+
+    fn write(&mut self, entry: &AuditEntry) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("🚨 Failed to serialize audit log entry for {}: {}", entry.endpoint, e);
+                return;
+            }
+        };
+        line.push(b'\n');
+        if let Err(e) = self.file.write_all(&line) {
+            error!("🚨 Failed to write audit log entry for {}: {}", entry.endpoint, e);
+            return;
+        }
+
+        self.written += line.len() as u64;
+        if self.written >= self.max_bytes {
+            if let Err(e) = self.rotate() {
+                error!("🚨 Failed to rotate audit log {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    /// Keep the full log as `<path>.1`, replacing the previous one, and start a new file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(".1");
+        std::fs::rename(&self.path, rotated)?;
+        self.file = BufWriter::new(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        self.written = 0;
+        Ok(())
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.file.flush() {
+            error!("🚨 Failed to flush audit log {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_audit_entries_persist_and_filter_by_time() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("audit_log.jsonl");
+
+        {
+            let log = AuditLog::open(&path).unwrap();
+            for (timestamp, provider, outcome) in [
+                (100, Some("tmobile-de"), AuditOutcome::Success),
+                (200, None, AuditOutcome::Denied),
+                (300, Some("vodafone-uk"), AuditOutcome::Denied),
+            ] {
+                let mut entry = AuditEntry::new(provider, "/api/v1/bce/submit", outcome, None);
+                entry.timestamp = timestamp;
+                log.record(entry);
+            }
+        }
+
+        let log = AuditLog::open(&path).unwrap();
+        let range = AuditQuery { from: Some(150), to: Some(300), ..Default::default() };
+        let entries = log.query(&range).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].provider_id, None);
+        assert_eq!(entries[1].outcome, AuditOutcome::Denied);
+
+        let by_provider = AuditQuery { provider_id: Some("tmobile-de".to_string()), ..Default::default() };
+        assert_eq!(log.query(&by_provider).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_query_returns_newest_entries_up_to_limit() {
+        let temp_dir = tempdir().unwrap();
+        let log = AuditLog::open(temp_dir.path().join("audit_log.jsonl")).unwrap();
+        for timestamp in 1..=MAX_AUDIT_QUERY_ENTRIES as u64 + 10 {
+            let mut entry = AuditEntry::new(Some("tmobile-de"), "/api/v1/bce/stats", AuditOutcome::Success, None);
+            entry.timestamp = timestamp;
+            log.record(entry);
+        }
+
+        // Recorded entries are visible without waiting for the writer
+        let entries = log.query(&AuditQuery::default()).unwrap();
+        assert_eq!(entries.len(), MAX_AUDIT_QUERY_ENTRIES);
+        assert_eq!(entries.first().unwrap().timestamp, 11);
+        assert_eq!(entries.last().unwrap().timestamp, MAX_AUDIT_QUERY_ENTRIES as u64 + 10);
+
+        let page = log.query(&AuditQuery { to: Some(100), limit: Some(5), ..Default::default() }).unwrap();
+        assert_eq!(page.iter().map(|e| e.timestamp).collect::<Vec<_>>(), vec![96, 97, 98, 99, 100]);
+
+        let oversized = AuditQuery { limit: Some(MAX_AUDIT_QUERY_ENTRIES * 10), ..Default::default() };
+        assert_eq!(log.query(&oversized).unwrap().len(), MAX_AUDIT_QUERY_ENTRIES);
+    }
+
+    #[test]
+    fn test_audit_log_rotates_at_size_limit() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("audit_log.jsonl");
+        let log = AuditLog::open_with_max_bytes(&path, 1024).unwrap();
+        for timestamp in 0..15 {
+            let mut entry = AuditEntry::new(Some("tmobile-de"), "/api/v1/bce/stats", AuditOutcome::Success, None);
+            entry.timestamp = timestamp;
+            log.record(entry);
+        }
+
+        let entries = log.query(&AuditQuery::default()).unwrap();
+        assert!(!entries.is_empty() && entries.len() < 15);
+        assert_eq!(entries.last().unwrap().timestamp, 14);
+        assert!(std::fs::metadata(&path).unwrap().len() < 1024);
+        assert!(temp_dir.path().join("audit_log.jsonl.1").exists());
+    }
+
+    #[test]
+    fn test_disabled_audit_log_records_nothing() {
+        let log = AuditLog::disabled();
+        log.record(AuditEntry::new(Some("tmobile-de"), "/api/v1/bce/stats", AuditOutcome::Success, None));
+        assert!(log.query(&AuditQuery::default()).unwrap().is_empty());
+    }
+}
@@ -5,6 +5,13 @@ use thiserror::Error;
 use ed25519_dalek::{VerifyingKey, Signature, Verifier};
 use log::{info, warn, error};
 
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::security::audit::{AuditEntry, AuditLog, AuditOutcome};
+use crate::security::rate_limiting::{RateLimitConfig, RateLimiter};
+use crate::security::credentials::{CredentialError, CredentialManager, KeyRotation, ProviderKey, SpCredentials};
+
 #[derive(Error, Debug)]
 pub enum AuthenticationError {
     #[error("Invalid API key")]
@@ -23,6 +30,10 @@ pub enum AuthenticationError {
     MalformedAuth,
 }
 
+/// Audit log endpoint label of record submission authorization, which covers both
+/// single and batch submissions
+pub const BCE_SUBMISSION_AUDIT_ENDPOINT: &str = "bce_submission";
+
 /// Represents an authenticated SP provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthenticatedSp {
@@ -31,12 +42,36 @@ pub struct AuthenticatedSp {
     pub api_key: String,
     pub public_key_bytes: [u8; 32],
     pub permissions: Vec<SpPermission>,
+    pub roles: Vec<Role>,
 }
 
 impl AuthenticatedSp {
     pub fn get_public_key(&self) -> Result<VerifyingKey, ed25519_dalek::SignatureError> {
         VerifyingKey::from_bytes(&self.public_key_bytes)
     }
+
+    pub fn has_role(&self, role: &Role) -> bool {
+        self.roles.contains(role)
+    }
+}
+
+/// Role of an SP on this node, on top of its per-action permissions
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Node administration: pruning, forced blocks, key restore, metrics
+    Admin,
+    /// Regular consortium member submitting and settling records
+    Operator,
+    /// May only read chain data
+    ReadOnly,
+}
+
+impl Role {
+    /// Roles of a provider whose credentials name none
+    pub fn default_roles() -> Vec<Role> {
+        vec![Role::Operator]
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -58,6 +93,16 @@ pub struct SpAuthentication {
 
     /// Request signature validation window (seconds)
     signature_window: u64,
+
+    /// Persisted credentials holding rotated signing keys; providers without managed
+    /// keys verify against their built-in key
+    credentials: RwLock<CredentialManager>,
+
+    /// Record of authentication and submission authorization decisions
+    audit_log: AuditLog,
+
+    /// Tracks authentication failures to lock out credential guessing
+    rate_limiter: Mutex<RateLimiter>,
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +112,7 @@ struct SpProvider {
     api_key: String,
     public_key: VerifyingKey,
     permissions: Vec<SpPermission>,
+    roles: Vec<Role>,
     active: bool,
 }
 
@@ -77,6 +123,9 @@ impl SpAuthentication {
             providers: HashMap::new(),
             api_keys: HashMap::new(),
             signature_window: 300, // 5 minutes
+            credentials: RwLock::new(CredentialManager::new()),
+            audit_log: AuditLog::disabled(),
+            rate_limiter: Mutex::new(RateLimiter::new(RateLimitConfig::default())),
         };
 
         // Initialize consortium providers
@@ -108,6 +157,7 @@ impl SpAuthentication {
                     SpPermission::ViewStats,
                     SpPermission::ExecuteSettlements,
                 ],
+                roles: Role::default_roles(),
                 active: true,
             };
 
@@ -161,6 +211,7 @@ impl SpAuthentication {
             api_key: api_key.to_string(),
             public_key_bytes: provider.public_key.to_bytes(),
             permissions: provider.permissions.clone(),
+            roles: provider.roles.clone(),
         })
     }
 
@@ -187,8 +238,11 @@ impl SpAuthentication {
             return Err(AuthenticationError::ExpiredTimestamp);
         }
 
-        // Verify signature
-        if let Err(_) = provider.public_key.verify(message, signature) {
+        // Verify signature against the keys valid now, including a rotated-out key in its grace period
+        let managed = self.credentials.read().ok()
+            .and_then(|credentials| credentials.verify_signature(provider_id, message, signature, now));
+        let verified = managed.unwrap_or_else(|| provider.public_key.verify(message, signature).is_ok());
+        if !verified {
             error!("❌ Signature verification failed for {}", provider.provider_name);
             return Err(AuthenticationError::InvalidSignature);
         }
@@ -201,9 +255,81 @@ impl SpAuthentication {
             api_key: provider.api_key.clone(),
             public_key_bytes: provider.public_key.to_bytes(),
             permissions: provider.permissions.clone(),
+            roles: provider.roles.clone(),
         })
     }
 
+    /// Take each known provider's roles from its credentials; providers without
+    /// credentials keep the default operator role
+    pub fn assign_roles(&mut self, credentials: &[SpCredentials]) {
+        for credential in credentials {
+            match self.providers.get_mut(&credential.provider_id) {
+                Some(provider) => {
+                    provider.roles = credential.roles.clone();
+                    info!("🎭 Roles of {}: {:?}", provider.provider_name, provider.roles);
+                }
+                None => warn!("⚠️  Ignoring roles for unknown SP provider: {}", credential.provider_id),
+            }
+        }
+    }
+
+    pub fn set_audit_log(&mut self, audit_log: AuditLog) {
+        self.audit_log = audit_log;
+    }
+
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit_log
+    }
+
+    pub fn set_rate_limiter(&mut self, rate_limiter: RateLimiter) {
+        self.rate_limiter = Mutex::new(rate_limiter);
+    }
+
+    /// Time left on the authentication lockout of a source identity (the client IP)
+    pub fn lockout_remaining(&self, identity: &str) -> Option<Duration> {
+        self.rate_limiter.lock().ok()?.lockout_remaining(identity, Instant::now())
+    }
+
+    /// Count a failed authentication; returns the lockout duration once the identity is locked out
+    pub fn record_auth_failure(&self, identity: &str) -> Option<Duration> {
+        self.rate_limiter.lock().ok()?.record_auth_failure(identity, Instant::now())
+    }
+
+    pub fn record_auth_success(&self, identity: &str) {
+        if let Ok(mut rate_limiter) = self.rate_limiter.lock() {
+            rate_limiter.record_auth_success(identity);
+        }
+    }
+
+    /// Use persisted credentials for signing keys, and take provider roles from them
+    pub fn set_credential_manager(&mut self, manager: CredentialManager) {
+        self.assign_roles(&manager.all_credentials());
+        self.credentials = RwLock::new(manager);
+    }
+
+    /// Replace a provider's signing key; its previous key stays valid for the grace period
+    pub fn rotate_credential(&self, provider_id: &str, new_public_key: &VerifyingKey, now: u64) -> Result<KeyRotation, CredentialError> {
+        let provider = self.providers.get(provider_id)
+            .ok_or_else(|| CredentialError::UnknownProvider(provider_id.to_string()))?;
+        let mut credentials = self.credentials.write()
+            .map_err(|_| CredentialError::InvalidKey("credential store lock poisoned".to_string()))?;
+
+        // A provider's first rotation starts from its built-in key
+        if credentials.get_credentials(provider_id).is_none_or(|cred| cred.signing_keys.is_empty()) {
+            let mut credential = credentials.get_credentials(provider_id).cloned().unwrap_or_else(|| SpCredentials {
+                provider_id: provider.provider_id.clone(),
+                api_key: provider.api_key.clone(),
+                public_key_pem: String::new(),
+                roles: provider.roles.clone(),
+                signing_keys: Vec::new(),
+            });
+            credential.signing_keys.push(ProviderKey::new(&provider.public_key, 0));
+            credentials.load_credentials(vec![credential]);
+        }
+
+        credentials.rotate_credential(provider_id, new_public_key, now)
+    }
+
     /// Check if SP has specific permission
     pub fn check_permission(&self, sp: &AuthenticatedSp, permission: &SpPermission) -> bool {
         sp.permissions.contains(permission)
@@ -211,11 +337,26 @@ impl SpAuthentication {
 
     /// Authorize SP to submit records for specific network (realistic telecom roaming)
     pub fn authorize_bce_submission(&self, sp: &AuthenticatedSp, visited_network: &str) -> Result<(), AuthenticationError> {
+        let result = self.check_bce_submission(sp, visited_network);
+        let (outcome, reason) = match &result {
+            Ok(()) => (AuditOutcome::Success, Some(format!("visited network {}", visited_network))),
+            Err(e) => (AuditOutcome::Denied, Some(e.to_string())),
+        };
+        self.audit_log.record(AuditEntry::new(Some(&sp.provider_id), BCE_SUBMISSION_AUDIT_ENDPOINT, outcome, reason));
+        result
+    }
+
+    fn check_bce_submission(&self, sp: &AuthenticatedSp, visited_network: &str) -> Result<(), AuthenticationError> {
         if !self.check_permission(sp, &SpPermission::SubmitBceRecords) {
             return Err(AuthenticationError::AuthorizationDenied(
                 format!("SP {} lacks SubmitBceRecords permission", sp.provider_id)
             ));
         }
+        if !sp.has_role(&Role::Operator) && !sp.has_role(&Role::Admin) {
+            return Err(AuthenticationError::AuthorizationDenied(
+                format!("SP {} has a read-only role", sp.provider_id)
+            ));
+        }
 
         // SP can only submit records where they are the visited network (provider of service)
         let expected_visited_network = match sp.provider_id.as_str() {
@@ -328,4 +469,81 @@ mod tests {
         let result = auth.authorize_bce_submission(&sp, "Vodafone-UK");
         assert!(matches!(result, Err(AuthenticationError::AuthorizationDenied(_))));
     }
+
+    #[test]
+    fn test_bce_authorization_is_audited() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut auth = SpAuthentication::new_consortium();
+        auth.set_audit_log(AuditLog::open(temp_dir.path().join("audit_log.jsonl")).unwrap());
+        let sp = auth.authenticate_by_api_key("tmobile_api_key_2024_secure").unwrap();
+
+        auth.authorize_bce_submission(&sp, "T-Mobile-DE").unwrap();
+        auth.authorize_bce_submission(&sp, "Vodafone-UK").unwrap_err();
+
+        let entries = auth.audit_log().query(&Default::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.provider_id.as_deref() == Some("tmobile-de")));
+        assert_eq!(entries[0].outcome, AuditOutcome::Success);
+        assert_eq!(entries[1].outcome, AuditOutcome::Denied);
+        assert!(entries[1].reason.as_ref().unwrap().contains("visited network"));
+    }
+
+    #[test]
+    fn test_rotated_key_verifies_alongside_old_key() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let auth = SpAuthentication::new_consortium();
+        let old_key = SigningKey::from_bytes(&[7u8; 32]);
+        let new_key = SigningKey::from_bytes(&[8u8; 32]);
+        let now = chrono::Utc::now().timestamp() as u64;
+        let message = b"settlement request";
+
+        auth.rotate_credential("vodafone-uk", &old_key.verifying_key(), now - 10).unwrap();
+        let rotation = auth.rotate_credential("vodafone-uk", &new_key.verifying_key(), now).unwrap();
+        assert!(rotation.previous_key_valid_until.unwrap() > now);
+
+        // Both keys verify while the old one is in its grace period
+        assert!(auth.authenticate_by_signature("vodafone-uk", message, &old_key.sign(message), now).is_ok());
+        assert!(auth.authenticate_by_signature("vodafone-uk", message, &new_key.sign(message), now).is_ok());
+
+        let other_key = SigningKey::from_bytes(&[9u8; 32]);
+        assert!(matches!(
+            auth.authenticate_by_signature("vodafone-uk", message, &other_key.sign(message), now),
+            Err(AuthenticationError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_roles_assigned_from_credentials() {
+        let mut auth = SpAuthentication::new_consortium();
+        auth.assign_roles(&[
+            SpCredentials {
+                provider_id: "tmobile-de".to_string(),
+                api_key: "tmobile_api_key_2024_secure".to_string(),
+                public_key_pem: String::new(),
+                roles: vec![Role::Admin, Role::Operator],
+                signing_keys: Vec::new(),
+            },
+            SpCredentials {
+                provider_id: "orange-fr".to_string(),
+                api_key: "orange_api_key_2024_secure".to_string(),
+                public_key_pem: String::new(),
+                roles: vec![Role::ReadOnly],
+                signing_keys: Vec::new(),
+            },
+        ]);
+
+        let admin = auth.authenticate_by_api_key("tmobile_api_key_2024_secure").unwrap();
+        assert!(admin.has_role(&Role::Admin));
+
+        // Providers without credentials keep the operator role
+        let operator = auth.authenticate_by_api_key("vodafone_api_key_2024_secure").unwrap();
+        assert_eq!(operator.roles, vec![Role::Operator]);
+
+        let read_only = auth.authenticate_by_api_key("orange_api_key_2024_secure").unwrap();
+        assert!(matches!(
+            auth.authorize_bce_submission(&read_only, "Orange-FR"),
+            Err(AuthenticationError::AuthorizationDenied(_))
+        ));
+    }
 }
\ No newline at end of file
@@ -1,27 +1,124 @@
 // SP Credential management for consortium
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use thiserror::Error;
+use log::info;
+
+use crate::security::auth::Role;
+
+/// How long a rotated-out signing key keeps verifying, so requests signed just before
+/// the rotation are still accepted
+pub const DEFAULT_KEY_GRACE_PERIOD_SECS: u64 = 3600;
+
+#[derive(Error, Debug)]
+pub enum CredentialError {
+    #[error("Unknown SP provider: {0}")]
+    UnknownProvider(String),
+    #[error("Invalid public key: {0}")]
+    InvalidKey(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpCredentials {
     pub provider_id: String,
     pub api_key: String,
     pub public_key_pem: String,
+    #[serde(default = "Role::default_roles")]
+    pub roles: Vec<Role>,
+    /// Ed25519 signing keys in rotation order, newest last
+    #[serde(default)]
+    pub signing_keys: Vec<ProviderKey>,
+}
+
+/// One signing key of a provider and the period it verifies signatures in
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderKey {
+    /// Hex-encoded 32-byte Ed25519 public key
+    pub public_key: String,
+    pub effective_from: u64,
+    /// Set once the key has been rotated out
+    pub valid_until: Option<u64>,
+}
+
+impl ProviderKey {
+    pub fn new(public_key: &VerifyingKey, effective_from: u64) -> Self {
+        Self {
+            public_key: hex::encode(public_key.as_bytes()),
+            effective_from,
+            valid_until: None,
+        }
+    }
+
+    pub fn is_valid_at(&self, now: u64) -> bool {
+        self.effective_from <= now && self.valid_until.is_none_or(|until| now <= until)
+    }
+
+    pub fn verifying_key(&self) -> Result<VerifyingKey, CredentialError> {
+        let bytes: [u8; 32] = hex::decode(&self.public_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| CredentialError::InvalidKey(self.public_key.clone()))?;
+        VerifyingKey::from_bytes(&bytes).map_err(|e| CredentialError::InvalidKey(e.to_string()))
+    }
+}
+
+/// Result of a signing key rotation
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRotation {
+    pub provider_id: String,
+    pub effective_from: u64,
+    /// When the replaced key stops verifying; `None` if the provider had no key before
+    pub previous_key_valid_until: Option<u64>,
 }
 
 pub struct CredentialManager {
     credentials: HashMap<String, SpCredentials>,
+    // Credentials are written here after every change when set
+    path: Option<PathBuf>,
+    grace_period_secs: u64,
 }
 
 impl CredentialManager {
     pub fn new() -> Self {
         Self {
             credentials: HashMap::new(),
+            path: None,
+            grace_period_secs: DEFAULT_KEY_GRACE_PERIOD_SECS,
         }
     }
 
+    /// Load persisted credentials from `path` (if it exists) and keep them saved there
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, CredentialError> {
+        let path = path.as_ref().to_path_buf();
+        let mut manager = Self::new();
+        if path.exists() {
+            let credentials: Vec<SpCredentials> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            info!("🔑 Loaded {} SP credentials from {}", credentials.len(), path.display());
+            manager.load_credentials(credentials);
+        }
+        manager.path = Some(path);
+        Ok(manager)
+    }
+
+    pub fn set_grace_period(&mut self, grace_period_secs: u64) {
+        self.grace_period_secs = grace_period_secs;
+    }
+
+    /// Add or update credentials. Signing keys already held for a provider are kept,
+    /// so reloading a static credentials file does not undo a rotation.
     pub fn load_credentials(&mut self, credentials: Vec<SpCredentials>) {
-        for cred in credentials {
+        for mut cred in credentials {
+            if let Some(existing) = self.credentials.get(&cred.provider_id) {
+                if !existing.signing_keys.is_empty() {
+                    cred.signing_keys = existing.signing_keys.clone();
+                }
+            }
             self.credentials.insert(cred.provider_id.clone(), cred);
         }
     }
@@ -29,4 +126,128 @@ impl CredentialManager {
     pub fn get_credentials(&self, provider_id: &str) -> Option<&SpCredentials> {
         self.credentials.get(provider_id)
     }
-}
\ No newline at end of file
+
+    pub fn all_credentials(&self) -> Vec<SpCredentials> {
+        self.credentials.values().cloned().collect()
+    }
+
+    /// Make `new_public_key` the provider's signing key from `now`. The current key stays
+    /// valid for the grace period so requests signed before the rotation still verify.
+    pub fn rotate_credential(
+        &mut self,
+        provider_id: &str,
+        new_public_key: &VerifyingKey,
+        now: u64,
+    ) -> Result<KeyRotation, CredentialError> {
+        let grace_period_secs = self.grace_period_secs;
+        let credential = self.credentials.get_mut(provider_id)
+            .ok_or_else(|| CredentialError::UnknownProvider(provider_id.to_string()))?;
+
+        // Keys whose grace period has already passed are no longer needed
+        credential.signing_keys.retain(|key| key.valid_until.is_none_or(|until| now <= until));
+
+        let previous_key_valid_until = credential.signing_keys.iter_mut()
+            .filter(|key| key.valid_until.is_none())
+            .map(|key| {
+                key.valid_until = Some(now + grace_period_secs);
+                now + grace_period_secs
+            })
+            .last();
+        credential.signing_keys.push(ProviderKey::new(new_public_key, now));
+
+        self.save()?;
+        info!("🔄 Rotated signing key of SP {}", provider_id);
+        Ok(KeyRotation {
+            provider_id: provider_id.to_string(),
+            effective_from: now,
+            previous_key_valid_until,
+        })
+    }
+
+    /// Keys of a provider that verify signatures at `now`; empty if none are managed
+    pub fn valid_keys(&self, provider_id: &str, now: u64) -> Vec<VerifyingKey> {
+        self.credentials.get(provider_id)
+            .map(|cred| cred.signing_keys.iter()
+                .filter(|key| key.is_valid_at(now))
+                .filter_map(|key| key.verifying_key().ok())
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// Verify a provider's signature against its keys valid at `now`.
+    /// `None` when no signing keys are managed for the provider.
+    pub fn verify_signature(&self, provider_id: &str, message: &[u8], signature: &Signature, now: u64) -> Option<bool> {
+        let managed = self.credentials.get(provider_id)
+            .is_some_and(|cred| !cred.signing_keys.is_empty());
+        if !managed {
+            return None;
+        }
+        Some(self.valid_keys(provider_id, now).iter().any(|key| key.verify(message, signature).is_ok()))
+    }
+
+    /// Write all credentials to the persistence file, if one is configured
+    pub fn save(&self) -> Result<(), CredentialError> {
+        let Some(path) = &self.path else { return Ok(()) };
+
+        let mut credentials = self.all_credentials();
+        credentials.sort_by(|a, b| a.provider_id.cmp(&b.provider_id));
+
+        // Write then rename so a crash never leaves a truncated file behind
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(&credentials)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use tempfile::tempdir;
+
+    fn credentials(provider_id: &str) -> SpCredentials {
+        SpCredentials {
+            provider_id: provider_id.to_string(),
+            api_key: format!("{}_key", provider_id),
+            public_key_pem: String::new(),
+            roles: Role::default_roles(),
+            signing_keys: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_old_key_verifies_only_during_grace_period() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("sp_credentials.json");
+        let old_key = SigningKey::from_bytes(&[1u8; 32]);
+        let new_key = SigningKey::from_bytes(&[2u8; 32]);
+        let message = b"POST /api/v1/bce/submit";
+
+        let mut manager = CredentialManager::open(&path).unwrap();
+        manager.set_grace_period(300);
+        manager.load_credentials(vec![credentials("tmobile-de")]);
+        manager.rotate_credential("tmobile-de", &old_key.verifying_key(), 0).unwrap();
+
+        let rotation = manager.rotate_credential("tmobile-de", &new_key.verifying_key(), 1_000).unwrap();
+        assert_eq!(rotation.previous_key_valid_until, Some(1_300));
+
+        let old_signature = old_key.sign(message);
+        let new_signature = new_key.sign(message);
+        assert_eq!(manager.verify_signature("tmobile-de", message, &old_signature, 1_200), Some(true));
+        assert_eq!(manager.verify_signature("tmobile-de", message, &new_signature, 1_200), Some(true));
+        assert_eq!(manager.verify_signature("tmobile-de", message, &old_signature, 1_301), Some(false));
+        assert_eq!(manager.verify_signature("tmobile-de", message, &new_signature, 1_301), Some(true));
+        assert_eq!(manager.verify_signature("vodafone-uk", message, &new_signature, 1_301), None);
+
+        // The rotation survives a restart, even when the static credentials are loaded again
+        let mut reopened = CredentialManager::open(&path).unwrap();
+        reopened.load_credentials(vec![credentials("tmobile-de")]);
+        assert_eq!(reopened.verify_signature("tmobile-de", message, &old_signature, 1_200), Some(true));
+        assert_eq!(reopened.verify_signature("tmobile-de", message, &old_signature, 1_301), Some(false));
+        assert!(matches!(
+            reopened.rotate_credential("unknown-sp", &new_key.verifying_key(), 2_000),
+            Err(CredentialError::UnknownProvider(_))
+        ));
+    }
+}
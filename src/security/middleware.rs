@@ -1,66 +1,123 @@
 // Axum middleware for SP authentication and security
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode, header},
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, header, header::InvalidHeaderValue},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use ed25519_dalek::Signature;
 use log::{info, warn, error};
 
 use crate::security::{SpAuthentication, AuthenticatedSp, AuthenticationError};
+use crate::security::auth::Role;
+use crate::security::audit::{AuditEntry, AuditOutcome};
 
 /// Extension type to store authenticated SP in request
 #[derive(Clone)]
 pub struct AuthenticatedSpExtension(pub AuthenticatedSp);
 
-/// Authentication middleware - validates API key or signature
+/// Authentication middleware - validates API key or signature.
+/// Repeated failures from one source lock it out with 429 until the cooldown passes.
 pub async fn auth_middleware(
     State(auth): State<Arc<SpAuthentication>>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
     let headers = request.headers();
+    let endpoint = request.uri().path().to_string();
+    let identity = auth_source_identity(&request);
+    let audit = |provider_id: Option<&str>, outcome: AuditOutcome, reason: Option<String>| {
+        auth.audit_log().record(AuditEntry::new(provider_id, &endpoint, outcome, reason));
+    };
+
+    if let Some(remaining) = auth.lockout_remaining(&identity) {
+        warn!("🔒 Authentication attempt from locked out {}", identity);
+        audit(None, AuditOutcome::Denied, Some(format!("{} is locked out", identity)));
+        return Ok(locked_out_response(remaining));
+    }
+    let failed = |provider_id: Option<&str>, reason: String| {
+        audit(provider_id, AuditOutcome::Denied, Some(reason));
+        if let Some(lockout) = auth.record_auth_failure(&identity) {
+            warn!("🔒 Locking out {} for {}s after repeated authentication failures", identity, lockout.as_secs());
+        }
+        Err(StatusCode::UNAUTHORIZED)
+    };
 
     // Try API key authentication first
     if let Some(api_key) = extract_api_key(headers) {
         match auth.authenticate_by_api_key(&api_key) {
             Ok(authenticated_sp) => {
                 info!("🔐 API key authentication successful for: {}", authenticated_sp.provider_name);
+                audit(Some(&authenticated_sp.provider_id), AuditOutcome::Success, None);
+                auth.record_auth_success(&identity);
                 request.extensions_mut().insert(AuthenticatedSpExtension(authenticated_sp));
                 return Ok(next.run(request).await);
             }
             Err(AuthenticationError::InvalidApiKey) => {
                 warn!("⚠️  Invalid API key provided");
-                return Err(StatusCode::UNAUTHORIZED);
+                return failed(None, AuthenticationError::InvalidApiKey.to_string());
             }
             Err(e) => {
                 error!("❌ Authentication error: {}", e);
-                return Err(StatusCode::UNAUTHORIZED);
+                return failed(None, e.to_string());
             }
         }
     }
 
-    // Try signature authentication if no API key
-    if let Some((provider_id, signature_data)) = extract_signature_auth(headers) {
-        // In a full implementation, we'd extract the message and signature
-        // For now, we'll just validate the provider exists
-        match auth.authenticate_by_api_key(&format!("{}_api_key_2024_secure", provider_id.replace("-", ""))) {
+    // Try signature authentication if no API key, against the provider's currently valid
+    // keys so rotated keys take effect and a replaced key only works for its grace period
+    if let Some((provider_id, signature)) = extract_signature_auth(headers) {
+        let timestamp = headers.get("X-SP-Timestamp")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        let signature = hex::decode(&signature).ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok());
+        let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+            warn!("⚠️  Malformed signature authentication headers for {}", provider_id);
+            return failed(Some(&provider_id), AuthenticationError::InvalidSignature.to_string());
+        };
+
+        let message = signature_message(request.method(), &endpoint, timestamp);
+        match auth.authenticate_by_signature(&provider_id, &message, &signature, timestamp) {
             Ok(authenticated_sp) => {
                 info!("🔐 Signature authentication successful for: {}", authenticated_sp.provider_name);
+                audit(Some(&authenticated_sp.provider_id), AuditOutcome::Success, None);
+                auth.record_auth_success(&identity);
                 request.extensions_mut().insert(AuthenticatedSpExtension(authenticated_sp));
                 return Ok(next.run(request).await);
             }
             Err(e) => {
                 error!("❌ Signature authentication error: {}", e);
-                return Err(StatusCode::UNAUTHORIZED);
+                return failed(Some(&provider_id), e.to_string());
             }
         }
     }
 
     // No valid authentication found
     warn!("⚠️  No valid authentication provided");
-    Err(StatusCode::UNAUTHORIZED)
+    failed(None, AuthenticationError::MissingAuthHeader.to_string())
+}
+
+/// Identity authentication failures are counted against: the peer IP of the connection.
+/// Client-supplied headers such as X-SP-Provider are not used, so nobody can lock a
+/// provider out by failing in its name or dodge a lockout by changing the header.
+fn auth_source_identity(request: &Request) -> String {
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+fn locked_out_response(remaining: Duration) -> Response {
+    // Round up so a client retrying at Retry-After is never still locked out
+    let retry_after = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    let mut response = StatusCode::TOO_MANY_REQUESTS.into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    response
 }
 
 /// Signature middleware - validates Ed25519 signatures on requests
@@ -121,6 +178,37 @@ pub async fn security_headers_middleware(
     Ok(response)
 }
 
+/// CORS policy for browser clients served from another origin, e.g. the dashboard on a CDN.
+///
+/// With no origins configured no `Access-Control-Allow-Origin` header is ever sent, so
+/// browsers only allow same-origin calls. `*` allows every origin: requests still need an
+/// API key, but any web page a user opens can then drive the API with keys the browser holds
+/// and read the responses, so list the dashboard's exact origin instead wherever possible.
+/// The layer must wrap the authentication middleware, since preflight `OPTIONS` requests
+/// carry no credentials and are answered by the layer itself.
+pub fn cors_layer(allowed_origins: &[String]) -> Result<CorsLayer, InvalidHeaderValue> {
+    let allow_origin = if allowed_origins.iter().any(|origin| origin == "*") {
+        warn!("⚠️  CORS allows any origin - every website can call this API from a browser");
+        AllowOrigin::any()
+    } else {
+        let origins = allowed_origins.iter()
+            .map(|origin| HeaderValue::from_str(origin))
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static("x-api-key"),
+            HeaderName::from_static("x-sp-provider"),
+            HeaderName::from_static("x-sp-signature"),
+        ]))
+}
+
 /// Authorization middleware - checks if SP can perform the requested action
 pub async fn authorization_middleware(
     request: Request,
@@ -140,10 +228,22 @@ pub async fn authorization_middleware(
 
     // Check permissions based on path
     let authorized = match path {
+        path if is_admin_path(path) => {
+            // Node administration is reserved for admins
+            authenticated_sp.has_role(&Role::Admin)
+        }
+        path if path.contains("/api/v1/bce/submit_batch") => {
+            // Batch submission - each record's visited operator is checked in the handler
+            true
+        }
         path if path.contains("/api/v1/bce/submit") => {
             // Only allow SPs to submit their own records - this will be checked in the handler
             true
         }
+        path if path.contains("/api/v1/bce/validate") => {
+            // Pre-flight validation - visited operator is checked in the handler like a submission
+            true
+        }
         path if path.contains("/api/v1/bce/stats") => {
             // All authenticated SPs can view BCE stats
             true
@@ -156,6 +256,46 @@ pub async fn authorization_middleware(
             // All authenticated SPs can view blockchain stats
             true
         }
+        path if path.contains("/api/v1/snapshot") => {
+            // All authenticated SPs can view the dashboard snapshot
+            true
+        }
+        path if path.contains("/api/v1/blockchain/stream") => {
+            // All authenticated SPs can follow newly finalized blocks
+            true
+        }
+        path if path.contains("/api/v1/ws") => {
+            // All authenticated SPs can follow live consensus events
+            true
+        }
+        path if path.contains("/api/v1/blockchain/block/") && path.ends_with("/provenance") => {
+            // All authenticated SPs can audit block provenance
+            true
+        }
+        path if path.contains("/api/v1/blockchain/block/") && path.contains("/balance_proof/") => {
+            // All authenticated SPs can request balance inclusion proofs
+            true
+        }
+        path if path.contains("/api/v1/blockchain/block/") && path.ends_with("/record_proofs") => {
+            // All authenticated SPs can request record inclusion proofs
+            true
+        }
+        path if path.contains("/api/v1/blockchain/verify_balance_proof") => {
+            // All authenticated SPs can verify balance inclusion proofs
+            true
+        }
+        path if path.contains("/api/v1/blockchain/state_root") || path.contains("/api/v1/blockchain/state_proof/") => {
+            // All authenticated SPs can read the global state root and its inclusion proofs
+            true
+        }
+        path if path.contains("/api/v1/consensus/status") => {
+            // All authenticated SPs can inspect in-flight consensus rounds
+            true
+        }
+        path if path.contains("/api/v1/consensus/config") => {
+            // All authenticated SPs can read the consensus finalization requirements
+            true
+        }
         path if path.contains("/api/v1/zkp/stats") => {
             // All authenticated SPs can view ZKP stats
             true
@@ -168,6 +308,10 @@ pub async fn authorization_middleware(
             // All authenticated SPs can verify ZKP proofs
             true
         }
+        path if path.contains("/api/v1/zkp/batch_verify") => {
+            // All authenticated SPs can verify ZKP proofs in bulk
+            true
+        }
         path if path.contains("/api/v1/zkp/system_status") => {
             // All authenticated SPs can view ZKP system status
             true
@@ -196,6 +340,18 @@ pub async fn authorization_middleware(
             // All authenticated SPs can run integration tests (for testing)
             true
         }
+        path if path.contains("/api/v1/zkp/verification_failures") => {
+            // All authenticated SPs can investigate proof verification failures
+            true
+        }
+        path if path.contains("/api/v1/zkp/reverify/") => {
+            // All authenticated SPs can audit a stored record's proof
+            true
+        }
+        path if path.contains("/api/v1/zkp/keys/backups") => {
+            // All authenticated SPs can list ZKP key backups
+            true
+        }
         path if path.contains("/api/v1/read/bce_records") => {
             // All authenticated SPs can read BCE records
             true
@@ -204,6 +360,15 @@ pub async fn authorization_middleware(
             // All authenticated SPs can read settlement blocks
             true
         }
+        path if path.contains("/api/v1/read/operator/")
+            && (path.ends_with("/position") || path.ends_with("/history")) => {
+            // All authenticated SPs can read settlement positions, like the blocks they come from
+            true
+        }
+        path if path.contains("/api/v1/read/settlement_report.csv") => {
+            // All authenticated SPs can export the settlement report
+            true
+        }
         path if path.contains("/health") => {
             // Health endpoint is public
             true
@@ -213,21 +378,25 @@ pub async fn authorization_middleware(
             true
         }
         path if path.contains("/api/v1/contracts/deploy") => {
-            // All authenticated SPs can deploy smart contracts
-            true
+            // Deploying changes chain state, so read-only SPs cannot
+            authenticated_sp.has_role(&Role::Operator) || authenticated_sp.has_role(&Role::Admin)
         }
         path if path.contains("/api/v1/contracts/list") => {
             // All authenticated SPs can list smart contracts
             true
         }
         path if path.contains("/api/v1/contracts/execute") => {
-            // All authenticated SPs can execute smart contracts
-            true
+            // Executions are recorded on chain, so read-only SPs cannot run them
+            authenticated_sp.has_role(&Role::Operator) || authenticated_sp.has_role(&Role::Admin)
         }
         path if path.contains("/api/v1/contracts/stats") => {
             // All authenticated SPs can view smart contract stats
             true
         }
+        path if is_contract_lookup_path(path) => {
+            // All authenticated SPs can inspect a deployed contract and its executions
+            true
+        }
         _ => {
             // Unknown endpoint - deny by default
             false
@@ -243,6 +412,33 @@ pub async fn authorization_middleware(
     Ok(next.run(request).await)
 }
 
+/// Node administration routes: key management, forced blocks, pruning, credentials,
+/// audit and capacity information
+fn is_admin_path(path: &str) -> bool {
+    const ADMIN_ROUTES: [&str; 8] = [
+        "/api/v1/zkp/keys/restore",
+        "/api/v1/zkp/keys/reload",
+        "/api/v1/admin/resources",
+        "/api/v1/admin/metrics",
+        "/api/v1/admin/create_block",
+        "/api/v1/admin/prune",
+        "/api/v1/admin/reindex",
+        "/api/v1/admin/audit_log",
+    ];
+    ADMIN_ROUTES.iter().any(|route| path.contains(route))
+        || (path.contains("/api/v1/admin/credentials/") && path.ends_with("/rotate"))
+}
+
+/// `/api/v1/contracts/{address}` and `/api/v1/contracts/{address}/executions`, and nothing
+/// else under the contracts prefix
+fn is_contract_lookup_path(path: &str) -> bool {
+    let Some((_, rest)) = path.split_once("/api/v1/contracts/") else {
+        return false;
+    };
+    let (address, suffix) = rest.split_once('/').unwrap_or((rest, ""));
+    !address.is_empty() && address.chars().all(|c| c.is_ascii_hexdigit()) && matches!(suffix, "" | "executions")
+}
+
 /// Extract API key from headers
 fn extract_api_key(headers: &HeaderMap) -> Option<String> {
     // Try Authorization: Bearer {api_key}
@@ -264,6 +460,12 @@ fn extract_api_key(headers: &HeaderMap) -> Option<String> {
     None
 }
 
+/// What an SP signs with Ed25519 for X-SP-Signature: the request method, path and the
+/// Unix timestamp sent in X-SP-Timestamp, one per line
+pub fn signature_message(method: &Method, path: &str, timestamp: u64) -> Vec<u8> {
+    format!("{}\n{}\n{}", method, path, timestamp).into_bytes()
+}
+
 /// Extract signature authentication data from headers
 fn extract_signature_auth(headers: &HeaderMap) -> Option<(String, String)> {
     let provider_id = headers.get("X-SP-Provider")?.to_str().ok()?.to_string();
@@ -339,4 +541,260 @@ mod tests {
         let auth_data = extract_signature_auth(&headers);
         assert_eq!(auth_data, Some(("tmobile-de".to_string(), "signature_data".to_string())));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_cors_preflight_bypasses_authentication() {
+        use axum::{body::Body, routing::get, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route("/api/v1/bce/stats", get(|| async { "stats" }))
+            // Stands in for auth_middleware: a preflight carries no API key
+            .layer(axum::middleware::from_fn(|_request: Request, _next: Next| async {
+                Err::<Response, StatusCode>(StatusCode::UNAUTHORIZED)
+            }))
+            .layer(cors_layer(&["https://dashboard.example".to_string()]).unwrap());
+
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method(Method::OPTIONS)
+                .uri("/api/v1/bce/stats")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-api-key")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(preflight("https://dashboard.example")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://dashboard.example");
+        assert!(headers[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap().contains("GET"));
+        assert!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap().contains("x-api-key"));
+
+        // Other origins get no allow header, so the browser blocks the call
+        let response = app.oneshot(preflight("https://evil.example")).await.unwrap();
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        // Same-origin only by default
+        assert!(cors_layer(&[]).is_ok());
+        assert!(cors_layer(&["bad\norigin".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_contract_lookup_paths_are_listed_explicitly() {
+        let address = "ab".repeat(32);
+        assert!(is_contract_lookup_path(&format!("/api/v1/contracts/{}", address)));
+        assert!(is_contract_lookup_path(&format!("/api/v1/contracts/{}/executions", address)));
+
+        assert!(!is_contract_lookup_path("/api/v1/contracts/"));
+        assert!(!is_contract_lookup_path(&format!("/api/v1/contracts/{}/upgrade", address)));
+        assert!(!is_contract_lookup_path(&format!("/api/v1/contracts/{}/executions/0", address)));
+        assert!(!is_contract_lookup_path("/api/v1/contracts/admin/upgrade"));
+    }
+
+    /// Status of a POST to `route` by an SP holding `roles`, behind the authorization middleware
+    async fn post_with_roles(route: &'static str, roles: Vec<Role>) -> StatusCode {
+        use axum::{body::Body, routing::post, Router};
+        use tower::ServiceExt;
+
+        let sp = SpAuthentication::new_consortium()
+            .authenticate_by_api_key("tmobile_api_key_2024_secure")
+            .unwrap();
+        let sp = AuthenticatedSp { roles, ..sp };
+        let app = Router::new()
+            .route(route, post(|| async { "ok" }))
+            .layer(axum::middleware::from_fn(authorization_middleware))
+            // Stands in for auth_middleware
+            .layer(axum::middleware::from_fn(move |mut request: Request, next: Next| {
+                request.extensions_mut().insert(AuthenticatedSpExtension(sp.clone()));
+                next.run(request)
+            }));
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(route)
+            .body(Body::empty())
+            .unwrap();
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_admin_role() {
+        for route in ["/api/v1/admin/prune", "/api/v1/zkp/keys/restore", "/api/v1/admin/credentials/tmobile-de/rotate"] {
+            assert_eq!(post_with_roles(route, vec![Role::Operator]).await, StatusCode::FORBIDDEN);
+            assert_eq!(post_with_roles(route, vec![Role::Admin]).await, StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_only_sps_cannot_deploy_or_execute_contracts() {
+        for route in ["/api/v1/contracts/deploy", "/api/v1/contracts/execute"] {
+            assert_eq!(post_with_roles(route, vec![Role::ReadOnly]).await, StatusCode::FORBIDDEN);
+            assert_eq!(post_with_roles(route, vec![Role::Operator]).await, StatusCode::OK);
+            assert_eq!(post_with_roles(route, vec![Role::Admin]).await, StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_records_audit_entries() {
+        use crate::security::audit::{AuditLog, AuditQuery};
+        use axum::{body::Body, routing::get, Router};
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut auth = SpAuthentication::new_consortium();
+        auth.set_audit_log(AuditLog::open(temp_dir.path().join("audit_log.jsonl")).unwrap());
+        let auth = Arc::new(auth);
+
+        let app = Router::new()
+            .route("/api/v1/bce/stats", get(|| async { "stats" }))
+            .layer(axum::middleware::from_fn_with_state(auth.clone(), auth_middleware));
+        let stats = |api_key: &str| Request::builder()
+            .uri("/api/v1/bce/stats")
+            .header("X-API-Key", api_key)
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.clone().oneshot(stats("tmobile_api_key_2024_secure")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(stats("wrong_key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let entries = auth.audit_log().query(&AuditQuery::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.endpoint == "/api/v1/bce/stats"));
+        assert_eq!(entries[0].provider_id.as_deref(), Some("tmobile-de"));
+        assert_eq!(entries[0].outcome, AuditOutcome::Success);
+        assert_eq!(entries[1].provider_id, None);
+        assert_eq!(entries[1].outcome, AuditOutcome::Denied);
+        assert!(entries[1].reason.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_repeated_auth_failures_lock_out_until_success_is_possible_again() {
+        use crate::security::rate_limiting::{RateLimitConfig, RateLimiter};
+        use axum::{body::Body, routing::get, Router};
+        use tower::ServiceExt;
+
+        let mut auth = SpAuthentication::new_consortium();
+        auth.set_rate_limiter(RateLimiter::new(RateLimitConfig { max_auth_failures: 3, ..Default::default() }));
+        let app = Router::new()
+            .route("/api/v1/bce/stats", get(|| async { "stats" }))
+            .layer(axum::middleware::from_fn_with_state(Arc::new(auth), auth_middleware));
+        let stats = |ip: [u8; 4], api_key: &str| {
+            let mut request = Request::builder()
+                .uri("/api/v1/bce/stats")
+                .header("X-API-Key", api_key)
+                .body(Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+            request
+        };
+
+        for _ in 0..3 {
+            let response = app.clone().oneshot(stats([10, 0, 0, 1], "guess")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        // Even the right key is refused while locked out
+        let response = app.clone().oneshot(stats([10, 0, 0, 1], "tmobile_api_key_2024_secure")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!(retry_after > 0 && retry_after <= 900);
+
+        // Other sources are not affected
+        let response = app.oneshot(stats([10, 0, 0, 2], "tmobile_api_key_2024_secure")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_signed_requests_use_rotated_keys() {
+        use crate::security::credentials::CredentialManager;
+        use axum::{body::Body, routing::get, Router};
+        use ed25519_dalek::{Signer, SigningKey};
+        use tower::ServiceExt;
+
+        let mut credentials = CredentialManager::new();
+        credentials.set_grace_period(1);
+        let mut auth = SpAuthentication::new_consortium();
+        auth.set_credential_manager(credentials);
+        let now = || chrono::Utc::now().timestamp() as u64;
+        let old_key = SigningKey::from_bytes(&[1u8; 32]);
+        let new_key = SigningKey::from_bytes(&[2u8; 32]);
+        auth.rotate_credential("tmobile-de", &old_key.verifying_key(), now()).unwrap();
+        auth.rotate_credential("tmobile-de", &new_key.verifying_key(), now()).unwrap();
+
+        let app = Router::new()
+            .route("/api/v1/bce/stats", get(|| async { "stats" }))
+            .layer(axum::middleware::from_fn_with_state(Arc::new(auth), auth_middleware));
+        let signed_with = |key: &SigningKey| {
+            let timestamp = now();
+            let signature = key.sign(&signature_message(&Method::GET, "/api/v1/bce/stats", timestamp));
+            Request::builder()
+                .uri("/api/v1/bce/stats")
+                .header("X-SP-Provider", "tmobile-de")
+                .header("X-SP-Signature", hex::encode(signature.to_bytes()))
+                .header("X-SP-Timestamp", timestamp.to_string())
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // The replaced key still verifies during its grace period
+        let response = app.clone().oneshot(signed_with(&old_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(signed_with(&new_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        tokio::time::sleep(Duration::from_millis(2_100)).await;
+        let response = app.clone().oneshot(signed_with(&old_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.oneshot(signed_with(&new_key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_failures_in_a_providers_name_do_not_lock_out_the_provider() {
+        use crate::security::rate_limiting::{RateLimitConfig, RateLimiter};
+        use axum::{body::Body, routing::get, Router};
+        use tower::ServiceExt;
+
+        let mut auth = SpAuthentication::new_consortium();
+        auth.set_rate_limiter(RateLimiter::new(RateLimitConfig { max_auth_failures: 3, ..Default::default() }));
+        let app = Router::new()
+            .route("/api/v1/bce/stats", get(|| async { "stats" }))
+            .layer(axum::middleware::from_fn_with_state(Arc::new(auth), auth_middleware));
+        let signed_as = |ip: [u8; 4], provider_id: &str| {
+            let mut request = Request::builder()
+                .uri("/api/v1/bce/stats")
+                .header("X-SP-Provider", provider_id)
+                .header("X-SP-Signature", "forged")
+                .body(Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+            request
+        };
+
+        // An attacker failing as tmobile-de only locks out its own address
+        for _ in 0..3 {
+            let response = app.clone().oneshot(signed_as([10, 0, 0, 9], "tmobile-de")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let response = app.clone().oneshot(signed_as([10, 0, 0, 9], "tmobile-de")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Changing the claimed provider does not get around the lockout either
+        let response = app.clone().oneshot(signed_as([10, 0, 0, 9], "vodafone-uk")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // The real provider connecting from its own address still gets in
+        let mut request = Request::builder()
+            .uri("/api/v1/bce/stats")
+            .header("X-API-Key", "tmobile_api_key_2024_secure")
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 40000))));
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -3,8 +3,10 @@ pub mod auth;
 pub mod middleware;
 pub mod credentials;
 pub mod rate_limiting;
+pub mod audit;
 
-pub use auth::{SpAuthentication, AuthenticationError, AuthenticatedSp};
+pub use auth::{SpAuthentication, AuthenticationError, AuthenticatedSp, Role};
 pub use middleware::{auth_middleware, signature_middleware, security_headers_middleware};
-pub use credentials::{SpCredentials, CredentialManager};
-pub use rate_limiting::{RateLimiter, RateLimitConfig};
\ No newline at end of file
+pub use credentials::{SpCredentials, CredentialManager, CredentialError, KeyRotation};
+pub use rate_limiting::{RateLimiter, RateLimitConfig};
+pub use audit::{AuditLog, AuditEntry, AuditOutcome, AuditQuery};
\ No newline at end of file
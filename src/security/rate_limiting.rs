@@ -7,6 +7,12 @@ use serde::{Deserialize, Serialize};
 pub struct RateLimitConfig {
     pub requests_per_minute: u32,
     pub burst_limit: u32,
+    /// Consecutive authentication failures within `auth_failure_window_secs` that lock an identity out
+    pub max_auth_failures: u32,
+    pub auth_failure_window_secs: u64,
+    pub lockout_secs: u64,
+    /// Most source identities whose failures are tracked at once
+    pub max_tracked_identities: usize,
 }
 
 impl Default for RateLimitConfig {
@@ -14,13 +20,33 @@ impl Default for RateLimitConfig {
         Self {
             requests_per_minute: 100,
             burst_limit: 20,
+            max_auth_failures: 5,
+            auth_failure_window_secs: 300,
+            lockout_secs: 900,
+            max_tracked_identities: 10_000,
         }
     }
 }
 
+/// Recent authentication failures of one source identity
+#[derive(Debug, Default)]
+struct AuthFailures {
+    failures: Vec<Instant>,
+    locked_until: Option<Instant>,
+}
+
+impl AuthFailures {
+    /// When this entry stops mattering: its lockout ends or its last failure leaves the window
+    fn expires_at(&self, window: Duration) -> Option<Instant> {
+        let failures_expire = self.failures.last().map(|&last| last + window);
+        self.locked_until.max(failures_expire)
+    }
+}
+
 pub struct RateLimiter {
     config: RateLimitConfig,
     client_requests: HashMap<String, Vec<Instant>>,
+    auth_failures: HashMap<String, AuthFailures>,
 }
 
 impl RateLimiter {
@@ -28,6 +54,62 @@ impl RateLimiter {
         Self {
             config,
             client_requests: HashMap::new(),
+            auth_failures: HashMap::new(),
+        }
+    }
+
+    /// Time left on the lockout of `identity`, or `None` if it may try to authenticate
+    pub fn lockout_remaining(&mut self, identity: &str, now: Instant) -> Option<Duration> {
+        let entry = self.auth_failures.get(identity)?;
+        match entry.locked_until {
+            Some(until) if until > now => Some(until - now),
+            Some(_) => {
+                // Cooldown is over - start counting from scratch
+                self.auth_failures.remove(identity);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Count a failed authentication; returns the lockout duration once `identity` hits the limit
+    pub fn record_auth_failure(&mut self, identity: &str, now: Instant) -> Option<Duration> {
+        let window = Duration::from_secs(self.config.auth_failure_window_secs);
+        if !self.auth_failures.contains_key(identity) && self.auth_failures.len() >= self.config.max_tracked_identities {
+            self.evict_auth_failures(now, window);
+        }
+
+        let entry = self.auth_failures.entry(identity.to_string()).or_default();
+
+        entry.failures.retain(|&time| now.duration_since(time) < window);
+        entry.failures.push(now);
+
+        if entry.failures.len() as u32 >= self.config.max_auth_failures {
+            let lockout = Duration::from_secs(self.config.lockout_secs);
+            entry.failures.clear();
+            entry.locked_until = Some(now + lockout);
+            return Some(lockout);
+        }
+        None
+    }
+
+    /// A successful authentication clears the failure count of `identity`
+    pub fn record_auth_success(&mut self, identity: &str) {
+        self.auth_failures.remove(identity);
+    }
+
+    /// Make room for a new identity: drop entries that no longer matter, and if the table
+    /// is still full, the one closest to expiring
+    fn evict_auth_failures(&mut self, now: Instant, window: Duration) {
+        self.auth_failures.retain(|_, entry| entry.expires_at(window).is_some_and(|at| at > now));
+
+        if self.auth_failures.len() >= self.config.max_tracked_identities {
+            let soonest = self.auth_failures.iter()
+                .min_by_key(|(_, entry)| entry.expires_at(window))
+                .map(|(identity, _)| identity.clone());
+            if let Some(identity) = soonest {
+                self.auth_failures.remove(&identity);
+            }
         }
     }
 
@@ -48,4 +130,86 @@ impl RateLimiter {
         requests.push(now);
         true
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            max_auth_failures: 3,
+            auth_failure_window_secs: 60,
+            lockout_secs: 120,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_lockout_after_repeated_auth_failures() {
+        let mut limiter = limiter();
+        let start = Instant::now();
+
+        assert_eq!(limiter.record_auth_failure("ip:10.0.0.1", start), None);
+        assert_eq!(limiter.record_auth_failure("ip:10.0.0.1", start), None);
+        assert_eq!(limiter.lockout_remaining("ip:10.0.0.1", start), None);
+        assert_eq!(limiter.record_auth_failure("ip:10.0.0.1", start), Some(Duration::from_secs(120)));
+
+        let later = start + Duration::from_secs(20);
+        assert_eq!(limiter.lockout_remaining("ip:10.0.0.1", later), Some(Duration::from_secs(100)));
+        // Other identities are unaffected
+        assert_eq!(limiter.lockout_remaining("ip:10.0.0.2", later), None);
+
+        // Failures spread wider than the window never lock out
+        for i in 0..5 {
+            let at = start + Duration::from_secs(61 * i);
+            assert_eq!(limiter.record_auth_failure("ip:10.0.0.3", at), None);
+        }
+    }
+
+    #[test]
+    fn test_lockout_recovers_after_cooldown_and_success_resets() {
+        let mut limiter = limiter();
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.record_auth_failure("provider:tmobile-de", start);
+        }
+
+        let after_cooldown = start + Duration::from_secs(121);
+        assert_eq!(limiter.lockout_remaining("provider:tmobile-de", after_cooldown), None);
+        // A fresh count starts after the cooldown
+        assert_eq!(limiter.record_auth_failure("provider:tmobile-de", after_cooldown), None);
+
+        // Success clears the count, so two more failures are not enough to lock out
+        limiter.record_auth_failure("provider:tmobile-de", after_cooldown);
+        limiter.record_auth_success("provider:tmobile-de");
+        assert_eq!(limiter.record_auth_failure("provider:tmobile-de", after_cooldown), None);
+        assert_eq!(limiter.record_auth_failure("provider:tmobile-de", after_cooldown), None);
+    }
+
+    #[test]
+    fn test_tracked_identities_are_bounded() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            max_auth_failures: 3,
+            auth_failure_window_secs: 60,
+            lockout_secs: 120,
+            max_tracked_identities: 3,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.record_auth_failure("ip:10.0.0.1", start);
+        }
+
+        // Failures from many addresses never grow the table past the cap
+        for i in 2..50 {
+            limiter.record_auth_failure(&format!("ip:10.0.0.{}", i), start + Duration::from_secs(i));
+        }
+        assert_eq!(limiter.auth_failures.len(), 3);
+
+        // Entries whose window and lockout have passed are dropped first
+        let later = start + Duration::from_secs(200);
+        limiter.record_auth_failure("ip:10.0.1.1", later);
+        assert_eq!(limiter.auth_failures.len(), 1);
+    }
+}
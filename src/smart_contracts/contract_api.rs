@@ -6,8 +6,17 @@ use futures::TryFutureExt;
 use crate::simple_blockchain::SimpleBlockchain;
 use crate::zkp::smart_contracts::settlement_contract::{ExecutableSettlementContract, ContractType};
 use crate::zkp::smart_contracts::vm::{SmartContractVM, Instruction};
+use crate::zkp::circuits::RateBounds;
 use crate::hash::Blake2bHash;
 
+/// Consortium-agreed band for call and data rates, proven in zero knowledge by `validate_bce_rates`
+pub const CONSORTIUM_RATE_BOUNDS: RateBounds = RateBounds {
+    min_call_rate_cents: 1,
+    max_call_rate_cents: 49,
+    min_data_rate_cents: 1,
+    max_data_rate_cents: 19,
+};
+
 /// API layer for smart contract interactions using ZKP-enabled VM
 pub struct ContractAPI {
     blockchain: Arc<SimpleBlockchain>,
@@ -178,10 +187,10 @@ impl ContractAPI {
         // Create bytecode for rate validation
         let validation_bytecode = vec![
             Instruction::Push(request.call_rate_cents),
-            Instruction::Push(50), // Max rate limit
+            Instruction::Push(CONSORTIUM_RATE_BOUNDS.max_call_rate_cents + 1), // Max rate limit
             Instruction::Lt, // Check if call rate < 50 cents
             Instruction::Push(request.data_rate_cents),
-            Instruction::Push(20), // Max data rate limit
+            Instruction::Push(CONSORTIUM_RATE_BOUNDS.max_data_rate_cents + 1), // Max data rate limit
             Instruction::Lt, // Check if data rate < 20 cents
             Instruction::Add, // Combine validation results
             Instruction::Push(request.sms_rate_cents),
@@ -201,7 +210,44 @@ impl ContractAPI {
 
         match vm.execute() {
             Ok(result) => {
-                let validation_passed = result == 1;
+                let mut validation_passed = result == 1;
+
+                // Call and data rates stay private: only the band and a proof of membership are published
+                let mut data = HashMap::new();
+                data.insert("call_rate_band".to_string(), format!(
+                    "{}-{}", CONSORTIUM_RATE_BOUNDS.min_call_rate_cents, CONSORTIUM_RATE_BOUNDS.max_call_rate_cents
+                ));
+                data.insert("data_rate_band".to_string(), format!(
+                    "{}-{}", CONSORTIUM_RATE_BOUNDS.min_data_rate_cents, CONSORTIUM_RATE_BOUNDS.max_data_rate_cents
+                ));
+                data.insert("sms_rate".to_string(), request.sms_rate_cents.to_string());
+
+                if let Some(proof_system) = self.blockchain.settlement_proof_system.as_ref() {
+                    let proof_verified = match proof_system.prove_rate_validation(
+                        request.call_rate_cents,
+                        request.data_rate_cents,
+                        &CONSORTIUM_RATE_BOUNDS,
+                    ) {
+                        Ok(proof) => {
+                            let verified = proof_system.verify_rate_validation(&proof, &CONSORTIUM_RATE_BOUNDS).unwrap_or(false);
+                            data.insert("rate_proof".to_string(), hex::encode(&proof.proof_bytes));
+                            verified
+                        }
+                        Err(e) => {
+                            println!("❌ Rate validation proof not generated: {}", e);
+                            false
+                        }
+                    };
+                    data.insert("proof_verified".to_string(), proof_verified.to_string());
+                    validation_passed &= proof_verified;
+                } else {
+                    // Without a proof the rates cannot be shown to be in band, so they are not accepted
+                    println!("❌ Rate validation needs the settlement proof system, which is not loaded");
+                    data.insert("proof_verified".to_string(), "false".to_string());
+                    validation_passed = false;
+                }
+
+                data.insert("validation_result".to_string(), validation_passed.to_string());
                 let result_message = if validation_passed { "valid" } else { "invalid" };
 
                 Ok(ExecutionResponse {
@@ -212,14 +258,7 @@ impl ContractAPI {
                     events: vec![
                         ContractEventResponse {
                             event_type: "RateValidation".to_string(),
-                            data: {
-                                let mut data = HashMap::new();
-                                data.insert("call_rate".to_string(), request.call_rate_cents.to_string());
-                                data.insert("data_rate".to_string(), request.data_rate_cents.to_string());
-                                data.insert("sms_rate".to_string(), request.sms_rate_cents.to_string());
-                                data.insert("validation_result".to_string(), validation_passed.to_string());
-                                data
-                            },
+                            data,
                             timestamp: chrono::Utc::now().timestamp() as u64,
                         }
                     ],
@@ -408,9 +447,31 @@ mod tests {
         assert!(!response.deployment_hash.is_empty());
     }
 
+    /// API over a blockchain with a settlement proof system loaded
+    async fn api_with_proof_system(data_dir: &std::path::Path) -> ContractAPI {
+        use ark_groth16::Groth16;
+        use ark_serialize::CanonicalSerialize;
+        use ark_snark::SNARK;
+        use crate::zkp::{SettlementCircuit, SettlementProofSystem};
+
+        let (_, vk) = Groth16::<ark_bn254::Bn254>::circuit_specific_setup(
+            SettlementCircuit::new_dummy(),
+            &mut ark_std::rand::thread_rng(),
+        ).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+
+        let (mut blockchain, _) = SimpleBlockchain::new(&data_dir.to_string_lossy(), "api-node".to_string(), 0, 100.0)
+            .await
+            .unwrap();
+        blockchain.set_settlement_proof_system(Arc::new(SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap()));
+        ContractAPI::with_blockchain(Arc::new(blockchain))
+    }
+
     #[tokio::test]
     async fn test_zkp_bce_rate_validation() {
-        let api = ContractAPI::new().await.unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let api = api_with_proof_system(temp_dir.path()).await;
         let operators = vec!["tmobile-de".to_string(), "vodafone-uk".to_string()];
 
         // Deploy contract first
@@ -426,9 +487,30 @@ mod tests {
         let response = api.validate_bce_rates("test_zkp_contract".to_string(), request).unwrap();
         assert!(response.success);
         assert_eq!(response.result, "valid");
+        assert_eq!(response.events[0].data["proof_verified"], "true");
         assert!(response.gas_used > 0);
     }
 
+    #[tokio::test]
+    async fn test_rate_validation_fails_closed_without_proof_system() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (blockchain, _) = SimpleBlockchain::new(&temp_dir.path().to_string_lossy(), "api-node".to_string(), 0, 100.0)
+            .await
+            .unwrap();
+        let api = ContractAPI::with_blockchain(Arc::new(blockchain));
+
+        // In-band rates are still not accepted when they cannot be proven
+        let request = RateValidationRequest {
+            call_rate_cents: 30,
+            data_rate_cents: 15,
+            sms_rate_cents: 10,
+        };
+
+        let response = api.validate_bce_rates("test_zkp_contract".to_string(), request).unwrap();
+        assert_eq!(response.result, "invalid");
+        assert_eq!(response.events[0].data["proof_verified"], "false");
+    }
+
     #[tokio::test]
     async fn test_zkp_bce_rate_validation_invalid() {
         let api = ContractAPI::new().await.unwrap();
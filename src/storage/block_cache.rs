@@ -0,0 +1,174 @@
+// LRU cache for deserialized settlement blocks
+use std::collections::{HashMap, VecDeque};
+use serde::Serialize;
+
+use crate::hash::Blake2bHash;
+use crate::simple_blockchain::SettlementBlock;
+
+/// Default number of blocks kept in memory
+pub const DEFAULT_BLOCK_CACHE_CAPACITY: usize = 256;
+
+/// Running totals of the finalized chain
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChainSummary {
+    pub block_count: usize,
+    pub total_records: u32,
+    pub total_amount_cents: u64,
+    pub latest_block: Option<SettlementBlock>,
+}
+
+/// Chain summary plus a hash-to-height index, extended as blocks are
+/// finalized so callers do not rescan the whole chain
+#[derive(Debug, Default)]
+pub struct ChainIndex {
+    summary: ChainSummary,
+    heights: HashMap<Blake2bHash, u64>,
+}
+
+impl ChainIndex {
+    /// Build from the full chain, ordered by block number
+    pub fn from_blocks(blocks: &[SettlementBlock]) -> Self {
+        Self::from_checkpoint(ChainSummary::default(), blocks)
+    }
+
+    /// Build from the summary of a pruned chain prefix and the blocks stored after it
+    pub fn from_checkpoint(base: ChainSummary, blocks: &[SettlementBlock]) -> Self {
+        let mut index = Self { summary: base, heights: HashMap::new() };
+        for block in blocks {
+            index.summary.total_records += block.record_count;
+            index.summary.total_amount_cents += block.settlement_summary.total_amount_cents;
+            index.heights.insert(block.block_hash, block.block_number);
+        }
+        index.summary.block_count += blocks.len();
+        if let Some(latest) = blocks.last() {
+            index.summary.latest_block = Some(latest.clone());
+        }
+        index
+    }
+
+    /// Extend the index with the next block on the chain. Returns false if the
+    /// block does not extend the current tip, in which case the index is stale.
+    pub fn append(&mut self, block: &SettlementBlock) -> bool {
+        if block.block_number != self.summary.block_count as u64 {
+            return false;
+        }
+
+        self.summary.block_count += 1;
+        self.summary.total_records += block.record_count;
+        self.summary.total_amount_cents += block.settlement_summary.total_amount_cents;
+        self.summary.latest_block = Some(block.clone());
+        self.heights.insert(block.block_hash, block.block_number);
+        true
+    }
+
+    pub fn summary(&self) -> &ChainSummary {
+        &self.summary
+    }
+
+    pub fn height_of(&self, block_hash: &Blake2bHash) -> Option<u64> {
+        self.heights.get(block_hash).copied()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockCacheStats {
+    pub capacity: usize,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Least-recently-used cache of settlement blocks keyed by block number
+pub struct BlockCache {
+    capacity: usize,
+    entries: HashMap<u64, SettlementBlock>,
+    // Front = least recently used
+    order: VecDeque<u64>,
+    hits: u64,
+    misses: u64,
+}
+
+impl BlockCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up a block, recording a hit or miss
+    pub fn get(&mut self, block_number: u64) -> Option<SettlementBlock> {
+        match self.entries.get(&block_number) {
+            Some(block) => {
+                let block = block.clone();
+                self.touch(block_number);
+                self.hits += 1;
+                Some(block)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache a block, evicting the least recently used entry when full
+    pub fn insert(&mut self, block: SettlementBlock) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let block_number = block.block_number;
+        if self.entries.insert(block_number, block).is_some() {
+            self.touch(block_number);
+            return;
+        }
+
+        self.order.push_back(block_number);
+        while self.entries.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    /// Drop a block and every block above it, since a rewritten block
+    /// invalidates the chain built on top of it
+    pub fn invalidate_from(&mut self, block_number: u64) {
+        self.entries.retain(|number, _| *number < block_number);
+        self.order.retain(|number| *number < block_number);
+    }
+
+    /// Drop all cached blocks
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// Change capacity, evicting entries if it shrinks
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.entries.remove(&evicted);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> BlockCacheStats {
+        BlockCacheStats {
+            capacity: self.capacity,
+            entries: self.entries.len(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    fn touch(&mut self, block_number: u64) {
+        self.order.retain(|number| *number != block_number);
+        self.order.push_back(block_number);
+    }
+}
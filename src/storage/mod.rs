@@ -1,4 +1,5 @@
 pub mod rocks_store;
+pub mod block_cache;
 
 // Re-export the RocksDB store as the primary storage backend
 pub use rocks_store::RocksSettlementStore;
\ No newline at end of file
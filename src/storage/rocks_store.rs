@@ -1,11 +1,146 @@
-use crate::simple_blockchain::{BceRecord, SettlementBlock};
+use crate::hash::Blake2bHash;
+use crate::simple_blockchain::{BceRecord, SettlementBlock, SettlementStatus, BlockProvenanceEntry, VerificationFailure, ContractExecutionRecord, IdempotencyEntry, LedgerState};
+use crate::network::consensus::Vote;
+use crate::zkp::settlement_proofs::migrate_unversioned_proof;
+use std::collections::HashMap;
 use std::path::Path;
-use rocksdb::{DB, Options, ColumnFamilyDescriptor};
-use std::sync::Arc;
+use rocksdb::{DB, Options, ColumnFamilyDescriptor, WriteBatch};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::block_cache::{BlockCache, BlockCacheStats, ChainIndex, ChainSummary, DEFAULT_BLOCK_CACHE_CAPACITY};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// Each kind of data lives in its own column family, so scans never cross types: blocks keyed
+// `block_{number:08}`, records keyed by record id, secondary record indexes in record_index and
+// chain-wide values (aggregates, prune checkpoint, index version, ids of pruned records, digests
+// of legacy blocks) in chain_metadata
+const COLUMN_FAMILIES: [&str; 9] = [
+    "bce_records", "settlement_blocks", "block_provenance", "verification_failures", "chain_metadata", "consensus_rounds",
+    "record_index", "contract_executions", "idempotency_keys",
+];
+
+const AGGREGATES_KEY: &[u8] = b"aggregates";
+const PRUNE_CHECKPOINT_KEY: &[u8] = b"prune_checkpoint";
+
+/// Tombstone left for a record deleted by pruning, holding the block it settled in
+fn pruned_record_key(record_id: &str) -> String {
+    format!("pruned_record/{}", record_id)
+}
+
+/// Content digest recorded for a block whose hash predates canonical hashing
+fn legacy_block_digest_key(block_number: u64) -> String {
+    format!("legacy_block_digest/{:08}", block_number)
+}
+
+// Bump when the layout of the record_index column family changes to force a rebuild on open
+const RECORD_INDEX_VERSION: u32 = 3;
+const RECORD_INDEX_VERSION_KEY: &[u8] = b"record_index_version";
+
+fn status_index_key(status: &SettlementStatus, record_id: &str) -> String {
+    format!("status/{}/{}", status.as_str(), record_id)
+}
+
+fn pair_index_prefix(home_operator: &str, visited_operator: &str) -> String {
+    format!("pair/{}:{}/", home_operator, visited_operator)
+}
+
+fn pair_index_key(record: &BceRecord) -> String {
+    format!("{}{}", pair_index_prefix(&record.home_operator, &record.visited_operator), record.record_id)
+}
+
+fn correction_index_prefix(original_id: &str) -> String {
+    format!("correction/{}/", original_id)
+}
+
+/// Index entry linking a credit to the record it corrects
+fn correction_index_key(record: &BceRecord) -> Option<String> {
+    record.corrects_record_id.as_ref()
+        .map(|original_id| format!("{}{}", correction_index_prefix(original_id), record.record_id))
+}
+
+/// Running totals over all finalized blocks, persisted alongside the chain
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChainAggregates {
+    pub block_count: usize,
+    pub total_records: u32,
+    pub total_amount_cents: u64,
+    pub last_block_time: Option<DateTime<Utc>>,
+}
+
+impl ChainAggregates {
+    fn from_blocks(base: Self, blocks: &[SettlementBlock]) -> Self {
+        let mut aggregates = base;
+        for block in blocks {
+            aggregates.apply(block);
+        }
+        aggregates
+    }
+
+    fn apply(&mut self, block: &SettlementBlock) {
+        self.block_count += 1;
+        self.total_records += block.record_count;
+        self.total_amount_cents += block.settlement_summary.total_amount_cents;
+        self.last_block_time = Some(block.timestamp);
+    }
+}
+
+/// Chain aggregates and the newest blocks read from one storage snapshot, so a block
+/// finalized in between can never show up in one and not the other
+#[derive(Debug, Clone)]
+pub struct ChainSnapshot {
+    pub aggregates: ChainAggregates,
+    /// Oldest first
+    pub recent_blocks: Vec<SettlementBlock>,
+}
+
+/// What remains of blocks pruned from the store. The last pruned block is kept whole so the
+/// first remaining block still links to it, alongside the totals of the pruned prefix.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneCheckpoint {
+    pub boundary_block: SettlementBlock,
+    pub aggregates: ChainAggregates,
+    pub ledger_state: LedgerState,
+}
+
+impl PruneCheckpoint {
+    /// Number of the first block still stored
+    pub fn pruned_before(&self) -> u64 {
+        self.boundary_block.block_number + 1
+    }
+}
+
+/// Inconsistencies found by `RocksSettlementStore::verify_integrity`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    pub blocks_checked: usize,
+    /// Missing heights as (expected block number, next stored block number)
+    pub gaps: Vec<(u64, u64)>,
+    /// Blocks whose `previous_hash` does not match the block stored before them
+    pub broken_links: Vec<u64>,
+    /// (block number, record id) pairs whose record is not stored
+    pub dangling_records: Vec<(u64, String)>,
+}
+
+impl IntegrityReport {
+    pub fn is_consistent(&self) -> bool {
+        self.gaps.is_empty() && self.broken_links.is_empty() && self.dangling_records.is_empty()
+    }
+}
 
 /// Persistent storage for SP blockchain using RocksDB
 pub struct RocksSettlementStore {
     db: Arc<DB>,
+    block_cache: Mutex<BlockCache>,
+    // Built on first use from a full scan, then extended on each stored block
+    chain_index: Mutex<Option<ChainIndex>>,
+    chain_scans: AtomicU64,
+    // Recompute block hashes on load and skip blocks that do not match
+    verify_block_hashes: AtomicBool,
+    // Failure injection point: the next finalize_atomic or import_atomic errors before its batch is written
+    #[cfg(test)]
+    fail_next_finalize: AtomicBool,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -14,6 +149,10 @@ pub enum RocksError {
     Rocks(#[from] rocksdb::Error),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("Prune rejected: {0}")]
+    PruneRejected(String),
+    #[error("Corrupt settlement block #{0}: its hash does not match its contents")]
+    CorruptBlock(u64),
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -28,51 +167,868 @@ impl RocksSettlementStore {
         opts.create_missing_column_families(true);
 
         // Define column families
-        let cf_descriptors = vec![
-            ColumnFamilyDescriptor::new("bce_records", Options::default()),
-            ColumnFamilyDescriptor::new("settlement_blocks", Options::default()),
-        ];
+        let cf_descriptors = COLUMN_FAMILIES.iter()
+            .map(|name| ColumnFamilyDescriptor::new(*name, Options::default()))
+            .collect::<Vec<_>>();
 
         // Open database with column families
         let db = DB::open_cf_descriptors(&opts, &data_dir, cf_descriptors)?;
 
         println!("🗄️  RocksDB persistent storage initialized at: {}", data_dir.display());
 
-        Ok(Self {
+        let store = Self {
             db: Arc::new(db),
-        })
+            block_cache: Mutex::new(BlockCache::new(DEFAULT_BLOCK_CACHE_CAPACITY)),
+            chain_index: Mutex::new(None),
+            chain_scans: AtomicU64::new(0),
+            verify_block_hashes: AtomicBool::new(false),
+            #[cfg(test)]
+            fail_next_finalize: AtomicBool::new(false),
+        };
+
+        // Databases written before aggregates were persisted get them computed once here
+        store.get_chain_aggregates()?;
+        store.ensure_record_index()?;
+
+        Ok(store)
+    }
+
+    /// Configure how many deserialized blocks are kept in memory (0 disables caching)
+    pub fn set_block_cache_capacity(&self, capacity: usize) {
+        if let Ok(mut cache) = self.block_cache.lock() {
+            cache.set_capacity(capacity);
+        }
+    }
+
+    /// Get block cache hit/miss statistics
+    pub fn get_block_cache_stats(&self) -> BlockCacheStats {
+        match self.block_cache.lock() {
+            Ok(cache) => cache.stats(),
+            Err(poisoned) => poisoned.into_inner().stats(),
+        }
+    }
+
+    /// Drop cached blocks from `block_number` upwards (after a reorg or prune)
+    pub fn invalidate_cached_blocks_from(&self, block_number: u64) {
+        if let Ok(mut cache) = self.block_cache.lock() {
+            cache.invalidate_from(block_number);
+        }
+        if let Ok(mut index) = self.chain_index.lock() {
+            *index = None;
+        }
+    }
+
+    /// Recompute each block's hash when it is read from disk, failing the read of any
+    /// block whose contents no longer match its hash. Cached blocks are dropped so they
+    /// are checked too.
+    pub fn set_verify_block_hashes(&self, enabled: bool) {
+        self.verify_block_hashes.store(enabled, Ordering::Relaxed);
+        if enabled {
+            self.invalidate_cached_blocks_from(0);
+        }
     }
 
-    /// Store BCE record persistently
+    /// Deserialize a stored block, rejecting it if integrity checks are on and it fails them
+    fn decode_block(&self, data: &[u8]) -> Result<SettlementBlock, RocksError> {
+        let mut block: SettlementBlock = serde_json::from_slice(data)?;
+        if self.verify_block_hashes.load(Ordering::Relaxed) {
+            self.check_block_hash(&block)?;
+        }
+        for proof in block.block_proof.iter_mut().chain(block.conservation_proof.iter_mut()) {
+            migrate_proof_bytes(&mut proof.proof_bytes);
+        }
+        Ok(block)
+    }
+
+    /// Check a block against its hash, or against its recorded digest if it is a legacy block
+    fn check_block_hash(&self, block: &SettlementBlock) -> Result<(), RocksError> {
+        if block.verify_hash() {
+            return Ok(());
+        }
+        if block.hash_version == 0 {
+            if let Some(digest) = self.get_legacy_block_digest(block.block_number)? {
+                if block.verify_legacy_digest(&digest) {
+                    return Ok(());
+                }
+            }
+        }
+        println!("🚨 Corrupt settlement block #{}: stored hash {} does not match its contents",
+                 block.block_number, hex::encode(block.block_hash.as_bytes()));
+        Err(RocksError::CorruptBlock(block.block_number))
+    }
+
+    fn get_legacy_block_digest(&self, block_number: u64) -> Result<Option<Blake2bHash>, RocksError> {
+        let cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+        match self.db.get_cf(&cf, legacy_block_digest_key(block_number).as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// One-off migration for chains written before block hashes were canonical: such blocks
+    /// cannot have their hash recomputed, so record a digest of their current contents to
+    /// verify against from now on. This trusts the blocks as they are, so only run it on
+    /// a store known to be intact. Returns the number of digests recorded.
+    pub fn record_legacy_block_digests(&self) -> Result<usize, RocksError> {
+        let blocks_cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
+            RocksError::Other("settlement_blocks column family not found".to_string())
+        })?;
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        let mut batch = WriteBatch::default();
+        let mut recorded = 0;
+        for item in self.db.iterator_cf(&blocks_cf, rocksdb::IteratorMode::Start) {
+            let (_, value) = item?;
+            let block: SettlementBlock = serde_json::from_slice(&value)?;
+            if block.hash_version != 0 || block.verify_hash() || self.get_legacy_block_digest(block.block_number)?.is_some() {
+                continue;
+            }
+            batch.put_cf(&meta_cf, legacy_block_digest_key(block.block_number).as_bytes(), serde_json::to_vec(&block.compute_hash()?)?);
+            recorded += 1;
+        }
+        self.db.write(batch)?;
+
+        println!("🏷️  Recorded content digests for {} legacy settlement blocks", recorded);
+        Ok(recorded)
+    }
+
+    /// Decode and hash-check every stored block straight from disk, bypassing the cache.
+    /// Fails on the first block that does not decode or verify. Returns the blocks checked.
+    pub fn verify_stored_blocks(&self) -> Result<usize, RocksError> {
+        let cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
+            RocksError::Other("settlement_blocks column family not found".to_string())
+        })?;
+
+        let mut checked = 0;
+        for item in self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            let block: SettlementBlock = serde_json::from_slice(&value).map_err(|e| {
+                println!("🚨 Undecodable settlement block {}: {}", String::from_utf8_lossy(&key), e);
+                e
+            })?;
+            self.check_block_hash(&block)?;
+            checked += 1;
+        }
+        Ok(checked)
+    }
+
+    /// Number the next finalized block gets: one past the highest stored block key, or
+    /// the first height after the prune checkpoint when no blocks are stored
+    pub fn next_block_number(&self) -> Result<u64, RocksError> {
+        let cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
+            RocksError::Other("settlement_blocks column family not found".to_string())
+        })?;
+
+        // Keys are zero-padded, so the last one holds the highest block number
+        if let Some(item) = self.db.iterator_cf(&cf, rocksdb::IteratorMode::End).next() {
+            let (key, _) = item?;
+            let block_number = std::str::from_utf8(&key).ok()
+                .and_then(|k| k.strip_prefix("block_"))
+                .and_then(|n| n.parse::<u64>().ok())
+                .ok_or_else(|| RocksError::Other(format!("unexpected settlement block key {}", String::from_utf8_lossy(&key))))?;
+            return Ok(block_number + 1);
+        }
+
+        Ok(self.get_prune_checkpoint()?.map_or(0, |c| c.pruned_before()))
+    }
+
+    /// Number of full scans of the settlement_blocks column family so far
+    pub fn get_chain_scan_count(&self) -> u64 {
+        self.chain_scans.load(Ordering::Relaxed)
+    }
+
+    /// Block count, totals and tip of the finalized chain without rescanning it
+    pub fn get_chain_summary(&self) -> Result<ChainSummary, RocksError> {
+        self.with_chain_index(|index| index.summary().clone())
+    }
+
+    /// Get the latest finalized block
+    pub fn get_latest_block(&self) -> Result<Option<SettlementBlock>, RocksError> {
+        self.with_chain_index(|index| index.summary().latest_block.clone())
+    }
+
+    /// Find a finalized block by its hash
+    pub fn get_block_by_hash(&self, block_hash: &Blake2bHash) -> Result<Option<SettlementBlock>, RocksError> {
+        match self.with_chain_index(|index| index.height_of(block_hash))? {
+            Some(block_number) => Ok(self.get_block_by_number(block_number)?
+                .filter(|block| block.block_hash == *block_hash)),
+            None => Ok(None),
+        }
+    }
+
+    fn with_chain_index<T>(&self, f: impl FnOnce(&ChainIndex) -> T) -> Result<T, RocksError> {
+        let mut guard = self.chain_index.lock().map_err(|_| {
+            RocksError::Other("chain index lock poisoned".to_string())
+        })?;
+
+        let index = match guard.take() {
+            Some(index) => index,
+            None => match self.get_prune_checkpoint()? {
+                Some(checkpoint) => ChainIndex::from_checkpoint(
+                    ChainSummary {
+                        block_count: checkpoint.aggregates.block_count,
+                        total_records: checkpoint.aggregates.total_records,
+                        total_amount_cents: checkpoint.aggregates.total_amount_cents,
+                        latest_block: Some(checkpoint.boundary_block),
+                    },
+                    &self.get_all_blocks()?,
+                ),
+                None => ChainIndex::from_blocks(&self.get_all_blocks()?),
+            },
+        };
+        let result = f(&index);
+        *guard = Some(index);
+        Ok(result)
+    }
+
+    /// Run store operations on the blocking thread pool so disk I/O never
+    /// stalls the async runtime
+    pub async fn run_blocking<T, F>(self: &Arc<Self>, f: F) -> Result<T, RocksError>
+    where
+        F: FnOnce(&RocksSettlementStore) -> Result<T, RocksError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let store = Arc::clone(self);
+        tokio::task::spawn_blocking(move || f(&store))
+            .await
+            .map_err(|e| RocksError::Other(format!("Storage task failed: {}", e)))?
+    }
+
+    /// Flush memtables and the write-ahead log of every column family to disk
+    pub fn flush(&self) -> Result<(), RocksError> {
+        for name in COLUMN_FAMILIES {
+            let cf = self.db.cf_handle(name).ok_or_else(|| {
+                RocksError::Other(format!("{} column family not found", name))
+            })?;
+            self.db.flush_cf(&cf)?;
+        }
+        self.db.flush_wal(true)?;
+        Ok(())
+    }
+
+    /// Store BCE record persistently, moving its index entries when its status or operators change
     pub fn store_bce_record(&self, record: &BceRecord) -> Result<(), RocksError> {
+        let mut batch = WriteBatch::default();
+        self.batch_bce_record(&mut batch, record)?;
+        self.db.write(batch)?;
+
+        println!("💾 Stored BCE record: {} in RocksDB", record.record_id);
+        Ok(())
+    }
+
+    /// Store several records and their index updates in one atomic write
+    pub fn store_bce_records(&self, records: &[BceRecord]) -> Result<(), RocksError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut batch = WriteBatch::default();
+        for record in records {
+            self.batch_bce_record(&mut batch, record)?;
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Add a record and its index updates to `batch`
+    fn batch_bce_record(&self, batch: &mut WriteBatch, record: &BceRecord) -> Result<(), RocksError> {
         let cf = self.db.cf_handle("bce_records").ok_or_else(|| {
             RocksError::Other("bce_records column family not found".to_string())
         })?;
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
 
         let key = record.record_id.as_bytes();
         let value = serde_json::to_vec(record)?;
 
-        self.db.put_cf(&cf, key, value)?;
-
-        println!("💾 Stored BCE record: {} in RocksDB", record.record_id);
+        if let Some(previous) = self.get_bce_record(&record.record_id)? {
+            if previous.settlement_status != record.settlement_status {
+                batch.delete_cf(&index_cf, status_index_key(&previous.settlement_status, &previous.record_id).as_bytes());
+            }
+            if pair_index_key(&previous) != pair_index_key(record) {
+                batch.delete_cf(&index_cf, pair_index_key(&previous).as_bytes());
+            }
+            if correction_index_key(&previous) != correction_index_key(record) {
+                if let Some(previous_key) = correction_index_key(&previous) {
+                    batch.delete_cf(&index_cf, previous_key.as_bytes());
+                }
+            }
+        }
+        batch.put_cf(&cf, key, value);
+        batch.put_cf(&index_cf, status_index_key(&record.settlement_status, &record.record_id).as_bytes(), b"");
+        batch.put_cf(&index_cf, pair_index_key(record).as_bytes(), b"");
+        if let Some(correction_key) = correction_index_key(record) {
+            batch.put_cf(&index_cf, correction_key.as_bytes(), b"");
+        }
         Ok(())
     }
 
-    /// Store settlement block persistently
+    /// Store settlement block persistently, updating the chain aggregates in the same write
     pub fn store_settlement_block(&self, block: &SettlementBlock) -> Result<(), RocksError> {
+        let mut batch = WriteBatch::default();
+        let extends_tip = self.batch_settlement_block(&mut batch, block)?;
+        self.db.write(batch)?;
+        self.settlement_block_written(block, extends_tip)?;
+
+        println!("🧱 Stored settlement block #{} in RocksDB", block.block_number);
+        Ok(())
+    }
+
+    /// Store a finalized block together with its settled records and provenance in one
+    /// write batch, so a crash never leaves records settled by a block that was not stored
+    /// (or the reverse). The stored block is what advances the chain height on restart.
+    /// The block's persisted consensus round is dropped in the same batch.
+    pub fn finalize_atomic(
+        &self,
+        block: &SettlementBlock,
+        updated_records: &[BceRecord],
+        provenance: &[BlockProvenanceEntry],
+    ) -> Result<(), RocksError> {
+        let provenance_cf = self.db.cf_handle("block_provenance").ok_or_else(|| {
+            RocksError::Other("block_provenance column family not found".to_string())
+        })?;
+
+        let mut batch = WriteBatch::default();
+        for record in updated_records {
+            self.batch_bce_record(&mut batch, record)?;
+        }
+        batch.put_cf(&provenance_cf, block.block_hash.as_bytes(), serde_json::to_vec(provenance)?);
+        let extends_tip = self.batch_settlement_block(&mut batch, block)?;
+        self.batch_consensus_round_removal(&mut batch, &block.block_hash)?;
+
+        #[cfg(test)]
+        if self.fail_next_finalize.swap(false, Ordering::SeqCst) {
+            return Err(RocksError::Other("injected finalize failure".to_string()));
+        }
+
+        self.db.write(batch)?;
+        self.settlement_block_written(block, extends_tip)?;
+
+        println!("🧱 Finalized settlement block #{} with {} records in one RocksDB batch",
+                 block.block_number, updated_records.len());
+        Ok(())
+    }
+
+    /// Make the next `finalize_atomic` or `import_atomic` fail before anything is written
+    #[cfg(test)]
+    pub(crate) fn fail_next_finalize(&self) {
+        self.fail_next_finalize.store(true, Ordering::SeqCst);
+    }
+
+    /// Add a block and, when it extends the tip, the updated aggregates to `batch`.
+    /// Returns whether the block extends the tip.
+    fn batch_settlement_block(&self, batch: &mut WriteBatch, block: &SettlementBlock) -> Result<bool, RocksError> {
         let cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
             RocksError::Other("settlement_blocks column family not found".to_string())
         })?;
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
 
         let key = format!("block_{:08}", block.block_number);
         let value = serde_json::to_vec(block)?;
 
+        // A new tip extends the aggregates; rewriting an existing height needs a recount
+        let mut aggregates = self.get_chain_aggregates()?;
+        let extends_tip = block.block_number == aggregates.block_count as u64;
+
+        batch.put_cf(&cf, key.as_bytes(), value);
+        if extends_tip {
+            aggregates.apply(block);
+            batch.put_cf(&meta_cf, AGGREGATES_KEY, serde_json::to_vec(&aggregates)?);
+        }
+        Ok(extends_tip)
+    }
+
+    /// Bring the block cache, chain index and aggregates up to date after a block was written
+    fn settlement_block_written(&self, block: &SettlementBlock, extends_tip: bool) -> Result<(), RocksError> {
+        // A (re)written block makes any cached copy of it and its descendants stale
+        if let Ok(mut cache) = self.block_cache.lock() {
+            cache.invalidate_from(block.block_number);
+        }
+
+        // Extend the chain index in place; anything but a new tip forces a rebuild
+        if let Ok(mut index) = self.chain_index.lock() {
+            if index.as_mut().is_some_and(|index| !index.append(block)) {
+                *index = None;
+            }
+        }
+
+        if !extends_tip {
+            self.recompute_aggregates()?;
+        }
+        Ok(())
+    }
+
+    /// Persist a block that is waiting for consensus
+    pub fn store_proposed_block(&self, block: &SettlementBlock) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("consensus_rounds").ok_or_else(|| {
+            RocksError::Other("consensus_rounds column family not found".to_string())
+        })?;
+
+        let key = format!("block_{}", block.block_hash.to_hex());
+        self.db.put_cf(&cf, key.as_bytes(), serde_json::to_vec(block)?)?;
+        Ok(())
+    }
+
+    /// Persist a vote collected for an in-flight consensus round
+    pub fn store_consensus_vote(&self, vote: &Vote) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("consensus_rounds").ok_or_else(|| {
+            RocksError::Other("consensus_rounds column family not found".to_string())
+        })?;
+
+        let key = format!("vote_{}_{}", vote.block_hash.to_hex(), vote.validator_id);
+        self.db.put_cf(&cf, key.as_bytes(), serde_json::to_vec(vote)?)?;
+        Ok(())
+    }
+
+    /// Drop a consensus round's proposed block and votes once it is finalized or abandoned
+    pub fn remove_consensus_round(&self, block_hash: &Blake2bHash) -> Result<(), RocksError> {
+        let mut batch = WriteBatch::default();
+        self.batch_consensus_round_removal(&mut batch, block_hash)?;
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Add the deletion of a consensus round's proposed block and votes to `batch`
+    fn batch_consensus_round_removal(&self, batch: &mut WriteBatch, block_hash: &Blake2bHash) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("consensus_rounds").ok_or_else(|| {
+            RocksError::Other("consensus_rounds column family not found".to_string())
+        })?;
+
+        batch.delete_cf(&cf, format!("block_{}", block_hash.to_hex()).as_bytes());
+
+        let vote_prefix = format!("vote_{}_", block_hash.to_hex());
+        let iter = self.db.iterator_cf(&cf, rocksdb::IteratorMode::From(vote_prefix.as_bytes(), rocksdb::Direction::Forward));
+        for item in iter {
+            let (key, _) = item?;
+            if !key.starts_with(vote_prefix.as_bytes()) {
+                break;
+            }
+            batch.delete_cf(&cf, key);
+        }
+        Ok(())
+    }
+
+    /// Get every persisted in-flight round as its proposed block and collected votes
+    pub fn get_consensus_rounds(&self) -> Result<Vec<(SettlementBlock, Vec<Vote>)>, RocksError> {
+        let cf = self.db.cf_handle("consensus_rounds").ok_or_else(|| {
+            RocksError::Other("consensus_rounds column family not found".to_string())
+        })?;
+
+        let mut blocks = Vec::new();
+        let mut votes: HashMap<Blake2bHash, Vec<Vote>> = HashMap::new();
+        for item in self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            if key.starts_with(b"block_") {
+                blocks.push(serde_json::from_slice::<SettlementBlock>(&value)?);
+            } else if key.starts_with(b"vote_") {
+                let vote: Vote = serde_json::from_slice(&value)?;
+                votes.entry(vote.block_hash).or_default().push(vote);
+            }
+        }
+
+        Ok(blocks.into_iter()
+            .map(|block| {
+                let block_votes = votes.remove(&block.block_hash).unwrap_or_default();
+                (block, block_votes)
+            })
+            .collect())
+    }
+
+    /// Get the persisted chain aggregates, computing them once for stores that predate them
+    pub fn get_chain_aggregates(&self) -> Result<ChainAggregates, RocksError> {
+        let cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        match self.db.get_cf(&cf, AGGREGATES_KEY)? {
+            Some(data) => Ok(serde_json::from_slice(&data)?),
+            None => self.recompute_aggregates(),
+        }
+    }
+
+    /// Aggregates plus the newest `max_blocks` blocks, all read from one RocksDB snapshot.
+    /// Blocks and aggregates are written in the same batch, so the two always agree.
+    pub fn get_chain_snapshot(&self, max_blocks: usize) -> Result<ChainSnapshot, RocksError> {
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+        let blocks_cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
+            RocksError::Other("settlement_blocks column family not found".to_string())
+        })?;
+
+        let snapshot = self.db.snapshot();
+        let aggregates = match snapshot.get_cf(&meta_cf, AGGREGATES_KEY)? {
+            Some(data) => serde_json::from_slice(&data)?,
+            // Stores that predate the aggregates get them written once, then are read again
+            None => return self.recompute_aggregates().and_then(|_| self.get_chain_snapshot(max_blocks)),
+        };
+
+        // Block keys are zero-padded numbers, so iterating from the end walks down from the tip
+        let mut recent_blocks = Vec::new();
+        for item in snapshot.iterator_cf(&blocks_cf, rocksdb::IteratorMode::End) {
+            if recent_blocks.len() >= max_blocks {
+                break;
+            }
+            let (_, value) = item?;
+            recent_blocks.push(self.decode_block(&value)?);
+        }
+        recent_blocks.reverse();
+
+        Ok(ChainSnapshot { aggregates, recent_blocks })
+    }
+
+    /// Rebuild the chain aggregates from a full scan of stored blocks (maintenance/repair)
+    pub fn recompute_aggregates(&self) -> Result<ChainAggregates, RocksError> {
+        let cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        // Pruned blocks are no longer stored, so count them from the checkpoint
+        let base = self.get_prune_checkpoint()?.map(|c| c.aggregates).unwrap_or_default();
+        let aggregates = ChainAggregates::from_blocks(base, &self.get_all_blocks()?);
+        self.db.put_cf(&cf, AGGREGATES_KEY, serde_json::to_vec(&aggregates)?)?;
+        Ok(aggregates)
+    }
+
+    /// Get the checkpoint left by the last prune, if the chain was ever pruned
+    pub fn get_prune_checkpoint(&self) -> Result<Option<PruneCheckpoint>, RocksError> {
+        let cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        match self.db.get_cf(&cf, PRUNE_CHECKPOINT_KEY)? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether a record was settled and then deleted by pruning
+    pub fn is_record_pruned(&self, record_id: &str) -> Result<bool, RocksError> {
+        let cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+        Ok(self.db.get_cf(&cf, pruned_record_key(record_id).as_bytes())?.is_some())
+    }
+
+    /// Write another node's chain export into an empty store in one write batch: the
+    /// checkpoint the export starts after, every record, and every block with its provenance.
+    /// A failed import leaves the store empty, so it can simply be retried.
+    pub fn import_atomic(
+        &self,
+        checkpoint: Option<&PruneCheckpoint>,
+        records: &[BceRecord],
+        blocks: &[SettlementBlock],
+        provenance: &[Vec<BlockProvenanceEntry>],
+    ) -> Result<(), RocksError> {
+        let blocks_cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
+            RocksError::Other("settlement_blocks column family not found".to_string())
+        })?;
+        let provenance_cf = self.db.cf_handle("block_provenance").ok_or_else(|| {
+            RocksError::Other("block_provenance column family not found".to_string())
+        })?;
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        let mut batch = WriteBatch::default();
+        if let Some(checkpoint) = checkpoint {
+            batch.put_cf(&meta_cf, PRUNE_CHECKPOINT_KEY, serde_json::to_vec(checkpoint)?);
+        }
+        for record in records {
+            self.batch_bce_record(&mut batch, record)?;
+        }
+        for (block, entries) in blocks.iter().zip(provenance) {
+            batch.put_cf(&blocks_cf, format!("block_{:08}", block.block_number).as_bytes(), serde_json::to_vec(block)?);
+            batch.put_cf(&provenance_cf, block.block_hash.as_bytes(), serde_json::to_vec(entries)?);
+        }
+
+        // Pruned blocks are not part of the export, so count them from the checkpoint
+        let base = checkpoint.map(|c| c.aggregates.clone()).unwrap_or_default();
+        let aggregates = ChainAggregates::from_blocks(base, blocks);
+        batch.put_cf(&meta_cf, AGGREGATES_KEY, serde_json::to_vec(&aggregates)?);
+
+        #[cfg(test)]
+        if self.fail_next_finalize.swap(false, Ordering::SeqCst) {
+            return Err(RocksError::Other("injected import failure".to_string()));
+        }
+
+        self.db.write(batch)?;
+
+        if let Ok(mut cache) = self.block_cache.lock() {
+            cache.invalidate_from(0);
+        }
+        if let Ok(mut index) = self.chain_index.lock() {
+            *index = None;
+        }
+
+        println!("📥 Imported {} blocks and {} records in one RocksDB batch", blocks.len(), records.len());
+        Ok(())
+    }
+
+    /// Delete finalized blocks below `block_number` together with their records, provenance
+    /// and index entries, leaving a checkpoint in their place. The tip is never pruned and
+    /// every record of a pruned block must be settled. Returns the new checkpoint.
+    pub fn prune_blocks_before(&self, block_number: u64) -> Result<PruneCheckpoint, RocksError> {
+        let blocks_cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
+            RocksError::Other("settlement_blocks column family not found".to_string())
+        })?;
+        let records_cf = self.db.cf_handle("bce_records").ok_or_else(|| {
+            RocksError::Other("bce_records column family not found".to_string())
+        })?;
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
+        let provenance_cf = self.db.cf_handle("block_provenance").ok_or_else(|| {
+            RocksError::Other("block_provenance column family not found".to_string())
+        })?;
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        let blocks = self.get_all_blocks()?;
+        let tip = blocks.last()
+            .ok_or_else(|| RocksError::PruneRejected("no stored blocks to prune".to_string()))?;
+        if block_number > tip.block_number {
+            return Err(RocksError::PruneRejected(format!(
+                "cannot prune up to block #{}: the tip #{} must be kept", block_number, tip.block_number
+            )));
+        }
+
+        let (pruned, kept): (Vec<_>, Vec<_>) = blocks.iter().partition(|b| b.block_number < block_number);
+        let boundary_block = match pruned.last() {
+            Some(block) => (*block).clone(),
+            None => return Err(RocksError::PruneRejected(format!("no stored blocks below #{}", block_number))),
+        };
+
+        // A record id may only be removed once no remaining block refers to it
+        let still_referenced: std::collections::HashSet<&str> = kept.iter()
+            .flat_map(|b| b.record_ids.iter().map(String::as_str))
+            .collect();
+
+        let mut batch = WriteBatch::default();
+        for block in &pruned {
+            for record_id in &block.record_ids {
+                let Some(record) = self.get_bce_record(record_id)? else { continue };
+                if record.settlement_status != SettlementStatus::Settled {
+                    return Err(RocksError::PruneRejected(format!(
+                        "block #{} contains record {} in status {}",
+                        block.block_number, record_id, record.settlement_status.as_str()
+                    )));
+                }
+                if still_referenced.contains(record_id.as_str()) {
+                    continue;
+                }
+                batch.delete_cf(&index_cf, status_index_key(&record.settlement_status, record_id).as_bytes());
+                batch.delete_cf(&index_cf, pair_index_key(&record).as_bytes());
+                if let Some(correction_key) = correction_index_key(&record) {
+                    batch.delete_cf(&index_cf, correction_key.as_bytes());
+                }
+                batch.delete_cf(&records_cf, record_id.as_bytes());
+                // Keep the id so the record can never be submitted and settled a second time
+                batch.put_cf(&meta_cf, pruned_record_key(record_id).as_bytes(), serde_json::to_vec(&block.block_number)?);
+            }
+            batch.delete_cf(&blocks_cf, format!("block_{:08}", block.block_number).as_bytes());
+            batch.delete_cf(&provenance_cf, block.block_hash.as_bytes());
+            batch.delete_cf(&meta_cf, legacy_block_digest_key(block.block_number).as_bytes());
+        }
+
+        // Fold the pruned blocks into the previous checkpoint's totals
+        let previous = self.get_prune_checkpoint()?;
+        let pruned_blocks: Vec<SettlementBlock> = pruned.into_iter().cloned().collect();
+        let mut ledger_state = previous.as_ref().map(|c| c.ledger_state.clone()).unwrap_or_default();
+        for block in &pruned_blocks {
+            ledger_state.apply_block(block);
+            ledger_state.apply_block_records(&self.get_block_records(block)?);
+        }
+        let checkpoint = PruneCheckpoint {
+            boundary_block,
+            aggregates: ChainAggregates::from_blocks(previous.map(|c| c.aggregates).unwrap_or_default(), &pruned_blocks),
+            ledger_state,
+        };
+        batch.put_cf(&meta_cf, PRUNE_CHECKPOINT_KEY, serde_json::to_vec(&checkpoint)?);
+        self.db.write(batch)?;
+
+        // Pruned blocks must not be served from the cache or the chain index
+        if let Ok(mut cache) = self.block_cache.lock() {
+            cache.clear();
+        }
+        if let Ok(mut index) = self.chain_index.lock() {
+            *index = None;
+        }
+
+        println!("✂️  Pruned {} settlement blocks below #{}", pruned_blocks.len(), block_number);
+        Ok(checkpoint)
+    }
+
+    /// Scan the stored chain for missing heights, `previous_hash` links that do not match
+    /// the prior block and block record ids without a stored record. A pruned chain is
+    /// checked from its checkpoint.
+    pub fn verify_integrity(&self) -> Result<IntegrityReport, RocksError> {
+        let checkpoint = self.get_prune_checkpoint()?;
+        let blocks = self.get_all_blocks()?;
+
+        let (mut expected_number, mut expected_previous) = match &checkpoint {
+            Some(checkpoint) => (checkpoint.pruned_before(), checkpoint.boundary_block.block_hash),
+            None => (0, Blake2bHash::hash(b"genesis")),
+        };
+
+        let mut report = IntegrityReport { blocks_checked: blocks.len(), ..Default::default() };
+        for block in &blocks {
+            if block.block_number != expected_number {
+                report.gaps.push((expected_number, block.block_number));
+            } else if block.previous_hash != expected_previous {
+                // Links are only comparable between adjacent heights
+                report.broken_links.push(block.block_number);
+            }
+
+            for record_id in &block.record_ids {
+                if self.get_bce_record(record_id)?.is_none() {
+                    report.dangling_records.push((block.block_number, record_id.clone()));
+                }
+            }
+
+            expected_number = block.block_number + 1;
+            expected_previous = block.block_hash;
+        }
+
+        Ok(report)
+    }
+
+    /// Store the submitter/trace provenance index of a finalized block
+    pub fn store_block_provenance(&self, block_hash: &Blake2bHash, entries: &[BlockProvenanceEntry]) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("block_provenance").ok_or_else(|| {
+            RocksError::Other("block_provenance column family not found".to_string())
+        })?;
+
+        let value = serde_json::to_vec(entries)?;
+        self.db.put_cf(&cf, block_hash.as_bytes(), value)?;
+
+        println!("🧾 Stored provenance for {} records of block {}", entries.len(), block_hash);
+        Ok(())
+    }
+
+    /// Get the submitter/trace provenance index of a finalized block
+    pub fn get_block_provenance(&self, block_hash: &Blake2bHash) -> Result<Option<Vec<BlockProvenanceEntry>>, RocksError> {
+        let cf = self.db.cf_handle("block_provenance").ok_or_else(|| {
+            RocksError::Other("block_provenance column family not found".to_string())
+        })?;
+
+        match self.db.get_cf(&cf, block_hash.as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Record a failed proof verification for later investigation
+    pub fn store_verification_failure(&self, failure: &VerificationFailure) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("verification_failures").ok_or_else(|| {
+            RocksError::Other("verification_failures column family not found".to_string())
+        })?;
+
+        // Nanosecond prefix keeps failures in chronological order and keys unique
+        let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let key = format!("{:020}_{}", nanos, failure.record_id);
+        let value = serde_json::to_vec(failure)?;
+
         self.db.put_cf(&cf, key.as_bytes(), value)?;
+        Ok(())
+    }
 
-        println!("🧱 Stored settlement block #{} in RocksDB", block.block_number);
+    /// Store the audit record of a contract execution, keyed by contract address and execution id
+    pub fn store_contract_execution(&self, execution: &ContractExecutionRecord) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("contract_executions").ok_or_else(|| {
+            RocksError::Other("contract_executions column family not found".to_string())
+        })?;
+
+        let key = format!("{}_{}", execution.contract_address.to_hex(), execution.execution_id);
+        self.db.put_cf(&cf, key.as_bytes(), serde_json::to_vec(execution)?)?;
+        Ok(())
+    }
+
+    /// Get all recorded executions of a contract, oldest first
+    pub fn get_contract_executions(&self, contract_address: &Blake2bHash) -> Result<Vec<ContractExecutionRecord>, RocksError> {
+        let cf = self.db.cf_handle("contract_executions").ok_or_else(|| {
+            RocksError::Other("contract_executions column family not found".to_string())
+        })?;
+
+        let prefix = format!("{}_", contract_address.to_hex());
+        let mut executions = Vec::new();
+        let iter = self.db.iterator_cf(&cf, rocksdb::IteratorMode::From(prefix.as_bytes(), rocksdb::Direction::Forward));
+        for item in iter {
+            let (key, value) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            executions.push(serde_json::from_slice(&value)?);
+        }
+
+        Ok(executions)
+    }
+
+    /// Remember the result of a submission made with an idempotency key
+    pub fn store_idempotency_entry(&self, key: &str, entry: &IdempotencyEntry) -> Result<(), RocksError> {
+        let cf = self.db.cf_handle("idempotency_keys").ok_or_else(|| {
+            RocksError::Other("idempotency_keys column family not found".to_string())
+        })?;
+
+        self.db.put_cf(&cf, key.as_bytes(), serde_json::to_vec(entry)?)?;
         Ok(())
     }
 
+    /// Get the stored result for an idempotency key, if any
+    pub fn get_idempotency_entry(&self, key: &str) -> Result<Option<IdempotencyEntry>, RocksError> {
+        let cf = self.db.cf_handle("idempotency_keys").ok_or_else(|| {
+            RocksError::Other("idempotency_keys column family not found".to_string())
+        })?;
+
+        match self.db.get_cf(&cf, key.as_bytes())? {
+            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete idempotency entries created before the cutoff, returning how many were removed
+    pub fn purge_idempotency_entries(&self, created_before: u64) -> Result<usize, RocksError> {
+        let cf = self.db.cf_handle("idempotency_keys").ok_or_else(|| {
+            RocksError::Other("idempotency_keys column family not found".to_string())
+        })?;
+
+        let mut batch = WriteBatch::default();
+        let mut purged = 0;
+        for item in self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            let entry: IdempotencyEntry = serde_json::from_slice(&value)?;
+            if entry.created_at < created_before {
+                batch.delete_cf(&cf, key);
+                purged += 1;
+            }
+        }
+
+        self.db.write(batch)?;
+        Ok(purged)
+    }
+
+    /// Get all recorded proof verification failures, oldest first
+    pub fn get_verification_failures(&self) -> Result<Vec<VerificationFailure>, RocksError> {
+        let cf = self.db.cf_handle("verification_failures").ok_or_else(|| {
+            RocksError::Other("verification_failures column family not found".to_string())
+        })?;
+
+        let mut failures = Vec::new();
+        for item in self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+            let (_, value) = item?;
+            failures.push(serde_json::from_slice(&value)?);
+        }
+
+        Ok(failures)
+    }
+
     /// Get BCE record from persistent storage
     pub fn get_bce_record(&self, record_id: &str) -> Result<Option<BceRecord>, RocksError> {
         let cf = self.db.cf_handle("bce_records").ok_or_else(|| {
@@ -82,25 +1038,41 @@ impl RocksSettlementStore {
         let key = record_id.as_bytes();
 
         match self.db.get_cf(&cf, key)? {
-            Some(data) => {
-                let record: BceRecord = serde_json::from_slice(&data)?;
-                Ok(Some(record))
-            },
+            Some(data) => Ok(Some(decode_bce_record(&data)?)),
             None => Ok(None),
         }
     }
 
-    /// Get settlement block from persistent storage
-    pub fn get_settlement_block(&self, block_number: u64) -> Result<Option<SettlementBlock>, RocksError> {
+    /// Stored records of a block; ids without a stored record are skipped
+    pub fn get_block_records(&self, block: &SettlementBlock) -> Result<Vec<BceRecord>, RocksError> {
+        let mut records = Vec::with_capacity(block.record_ids.len());
+        for record_id in &block.record_ids {
+            if let Some(record) = self.get_bce_record(record_id)? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Get settlement block #n from persistent storage (direct key lookup,
+    /// blocks are keyed by zero-padded block number)
+    pub fn get_block_by_number(&self, block_number: u64) -> Result<Option<SettlementBlock>, RocksError> {
         let cf = self.db.cf_handle("settlement_blocks").ok_or_else(|| {
             RocksError::Other("settlement_blocks column family not found".to_string())
         })?;
 
+        if let Some(block) = self.block_cache.lock().ok().and_then(|mut cache| cache.get(block_number)) {
+            return Ok(Some(block));
+        }
+
         let key = format!("block_{:08}", block_number);
 
         match self.db.get_cf(&cf, key.as_bytes())? {
             Some(data) => {
-                let block: SettlementBlock = serde_json::from_slice(&data)?;
+                let block = self.decode_block(&data)?;
+                if let Ok(mut cache) = self.block_cache.lock() {
+                    cache.insert(block.clone());
+                }
                 Ok(Some(block))
             },
             None => Ok(None),
@@ -113,12 +1085,31 @@ impl RocksSettlementStore {
             RocksError::Other("settlement_blocks column family not found".to_string())
         })?;
 
+        self.chain_scans.fetch_add(1, Ordering::Relaxed);
+
         let mut blocks = Vec::new();
         let iter = self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start);
+        let mut cache = self.block_cache.lock().map_err(|_| {
+            RocksError::Other("block cache lock poisoned".to_string())
+        })?;
 
         for item in iter {
-            let (_, value) = item?;
-            let block: SettlementBlock = serde_json::from_slice(&value)?;
+            let (key, value) = item?;
+
+            // Skip deserialization for blocks already in the cache
+            let block_number = std::str::from_utf8(&key).ok()
+                .and_then(|k| k.strip_prefix("block_"))
+                .and_then(|n| n.parse::<u64>().ok());
+
+            let cached = block_number.and_then(|n| cache.get(n));
+            let block = match cached {
+                Some(block) => block,
+                None => {
+                    let block = self.decode_block(&value)?;
+                    cache.insert(block.clone());
+                    block
+                }
+            };
             blocks.push(block);
         }
 
@@ -138,19 +1129,164 @@ impl RocksSettlementStore {
 
         for item in iter {
             let (_, value) = item?;
-            let record: BceRecord = serde_json::from_slice(&value)?;
-            records.push(record);
+            records.push(decode_bce_record(&value)?);
         }
 
         Ok(records)
     }
 
+    /// Get BCE records in the given settlement status using the status index
+    pub fn get_bce_records_by_status(&self, status: &SettlementStatus) -> Result<Vec<BceRecord>, RocksError> {
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
+
+        let prefix = format!("status/{}/", status.as_str());
+        let mut records = Vec::new();
+        for record_id in self.indexed_record_ids(&index_cf, &prefix)? {
+            // Re-check the record itself so a stale index entry never leaks a wrong status
+            if let Some(record) = self.get_bce_record(&record_id)? {
+                if &record.settlement_status == status {
+                    records.push(record);
+                }
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Get BCE records between a home and visited operator using the operator pair index
+    pub fn get_bce_records_for_pair(&self, home_operator: &str, visited_operator: &str) -> Result<Vec<BceRecord>, RocksError> {
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
+
+        let prefix = pair_index_prefix(home_operator, visited_operator);
+        let mut records = Vec::new();
+        for record_id in self.indexed_record_ids(&index_cf, &prefix)? {
+            if let Some(record) = self.get_bce_record(&record_id)? {
+                if record.home_operator == home_operator && record.visited_operator == visited_operator {
+                    records.push(record);
+                }
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Credit records that correct `original_id`, whatever their status, using the correction index
+    pub fn get_credits_for_record(&self, original_id: &str) -> Result<Vec<BceRecord>, RocksError> {
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
+
+        let mut credits = Vec::new();
+        for record_id in self.indexed_record_ids(&index_cf, &correction_index_prefix(original_id))? {
+            if let Some(record) = self.get_bce_record(&record_id)? {
+                if record.corrects_record_id.as_deref() == Some(original_id) {
+                    credits.push(record);
+                }
+            }
+        }
+
+        Ok(credits)
+    }
+
+    /// Record ids stored in the record index under a key prefix
+    fn indexed_record_ids(&self, index_cf: &impl rocksdb::AsColumnFamilyRef, prefix: &str) -> Result<Vec<String>, RocksError> {
+        let mut record_ids = Vec::new();
+        let iter = self.db.iterator_cf(index_cf, rocksdb::IteratorMode::From(prefix.as_bytes(), rocksdb::Direction::Forward));
+        for item in iter {
+            let (key, _) = item?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            record_ids.push(String::from_utf8_lossy(&key[prefix.len()..]).into_owned());
+        }
+
+        Ok(record_ids)
+    }
+
+    /// Build the record index from a full scan if it is missing or from an older layout
+    fn ensure_record_index(&self) -> Result<(), RocksError> {
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        let current = self.db.get_cf(&meta_cf, RECORD_INDEX_VERSION_KEY)?
+            .and_then(|data| serde_json::from_slice::<u32>(&data).ok());
+        if current == Some(RECORD_INDEX_VERSION) {
+            return Ok(());
+        }
+
+        self.rebuild_record_index().map(|_| ())
+    }
+
+    /// Rebuild the record index from a full scan of stored records (maintenance/repair).
+    /// Returns the number of records indexed.
+    pub fn rebuild_record_index(&self) -> Result<usize, RocksError> {
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(&index_cf, rocksdb::IteratorMode::Start) {
+            let (key, _) = item?;
+            batch.delete_cf(&index_cf, key);
+        }
+
+        let records = self.get_all_bce_records()?;
+        for record in &records {
+            batch.put_cf(&index_cf, status_index_key(&record.settlement_status, &record.record_id).as_bytes(), b"");
+            batch.put_cf(&index_cf, pair_index_key(record).as_bytes(), b"");
+            if let Some(correction_key) = correction_index_key(record) {
+                batch.put_cf(&index_cf, correction_key.as_bytes(), b"");
+            }
+        }
+        batch.put_cf(&meta_cf, RECORD_INDEX_VERSION_KEY, serde_json::to_vec(&RECORD_INDEX_VERSION)?);
+        self.db.write(batch)?;
+
+        println!("🗂️  Rebuilt record index for {} BCE records", records.len());
+        Ok(records.len())
+    }
+
+    /// Recompute the aggregates and rebuild the record index from the stored blocks and
+    /// records, dropping cached chain state first. Returns the aggregates and the number
+    /// of records indexed.
+    pub fn rebuild_derived_state(&self) -> Result<(ChainAggregates, usize), RocksError> {
+        self.invalidate_cached_blocks_from(0);
+        let aggregates = self.recompute_aggregates()?;
+        let indexed_records = self.rebuild_record_index()?;
+        Ok((aggregates, indexed_records))
+    }
+
+    /// Simulate drift: drop a record's index entries and zero the persisted aggregates
+    #[cfg(test)]
+    pub(crate) fn corrupt_derived_state(&self, record: &BceRecord) -> Result<(), RocksError> {
+        let index_cf = self.db.cf_handle("record_index").ok_or_else(|| {
+            RocksError::Other("record_index column family not found".to_string())
+        })?;
+        let meta_cf = self.db.cf_handle("chain_metadata").ok_or_else(|| {
+            RocksError::Other("chain_metadata column family not found".to_string())
+        })?;
+
+        let mut batch = WriteBatch::default();
+        batch.delete_cf(&index_cf, status_index_key(&record.settlement_status, &record.record_id).as_bytes());
+        batch.delete_cf(&index_cf, pair_index_key(record).as_bytes());
+        batch.put_cf(&meta_cf, AGGREGATES_KEY, serde_json::to_vec(&ChainAggregates::default())?);
+        self.db.write(batch)?;
+        Ok(())
+    }
+
     /// Get storage statistics
     pub fn get_stats(&self) -> Result<(usize, usize), RocksError> {
         let records = self.get_all_bce_records()?;
-        let blocks = self.get_all_blocks()?;
+        let blocks = self.get_chain_aggregates()?.block_count;
 
-        Ok((records.len(), blocks.len()))
+        Ok((records.len(), blocks))
     }
 
     /// List all stored data for debugging
@@ -183,4 +1319,388 @@ impl RocksSettlementStore {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+/// Deserialize a stored BCE record, reading its proof in the current format
+fn decode_bce_record(data: &[u8]) -> Result<BceRecord, RocksError> {
+    let mut record: BceRecord = serde_json::from_slice(data)?;
+    if let Some(proof_bytes) = record.zkp_proof.as_mut() {
+        migrate_proof_bytes(proof_bytes);
+    }
+    Ok(record)
+}
+
+/// Rewrite a Groth16 proof stored before proofs were versioned in the current format.
+/// Bytes that are not a Groth16 proof, such as development mock proofs, are left as stored.
+fn migrate_proof_bytes(proof_bytes: &mut Vec<u8>) {
+    if let Ok(migrated) = migrate_unversioned_proof(proof_bytes) {
+        *proof_bytes = migrated;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Blake2bHash;
+    use crate::simple_blockchain::SettlementSummary;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn test_block(block_number: u64, total_amount_cents: u64) -> SettlementBlock {
+        SettlementBlock {
+            block_hash: Blake2bHash::hash(format!("block-{}-{}", block_number, total_amount_cents).as_bytes()),
+            previous_hash: Blake2bHash::hash(b"genesis"),
+            block_number,
+            timestamp: chrono::Utc::now(),
+            settlement_summary: SettlementSummary {
+                total_records: 1,
+                total_amount_cents,
+                operator_balances: HashMap::new(),
+                currency_balances: HashMap::new(),
+                gross_amount_cents: 0,
+                net_amount_cents: 0,
+                net_transfers: Vec::new(),
+            },
+            record_count: 1,
+            record_ids: vec![format!("BCE-{}", block_number)],
+            balance_root: Blake2bHash::zero(),
+            records_root: Blake2bHash::zero(),
+            state_root: Blake2bHash::zero(),
+            block_proof: None,
+            conservation_proof: None,
+            hash_version: crate::simple_blockchain::BLOCK_HASH_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_block_cache_hits_and_invalidation() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+
+        store.store_settlement_block(&test_block(0, 100)).unwrap();
+
+        // First lookup deserializes, second is served from cache
+        store.get_block_by_number(0).unwrap().unwrap();
+        store.get_block_by_number(0).unwrap().unwrap();
+        let stats = store.get_block_cache_stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+
+        // Finalizing a replacement block must not leave the old copy cached
+        store.store_settlement_block(&test_block(0, 200)).unwrap();
+        let block = store.get_block_by_number(0).unwrap().unwrap();
+        assert_eq!(block.settlement_summary.total_amount_cents, 200);
+        assert_eq!(store.get_block_cache_stats().misses, 2);
+    }
+
+    #[test]
+    fn test_chain_summary_does_not_rescan_chain() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+
+        for n in 0..300 {
+            store.store_settlement_block(&test_block(n, 100)).unwrap();
+        }
+        // Storing blocks never rescans the chain
+        let scans = store.get_chain_scan_count();
+        assert!(scans <= 1);
+
+        // Only the first lookup scans the chain, however often stats are read
+        for _ in 0..100 {
+            let summary = store.get_chain_summary().unwrap();
+            assert_eq!(summary.block_count, 300);
+            assert_eq!(summary.total_amount_cents, 30_000);
+            assert_eq!(store.get_stats().unwrap().1, 300);
+        }
+        assert_eq!(store.get_chain_scan_count(), scans + 1);
+
+        // Appending a block extends the index in place
+        store.store_settlement_block(&test_block(300, 500)).unwrap();
+        let summary = store.get_chain_summary().unwrap();
+        assert_eq!(summary.block_count, 301);
+        assert_eq!(summary.total_records, 301);
+        assert_eq!(summary.latest_block.unwrap().block_number, 300);
+        let middle = test_block(150, 100);
+        assert_eq!(store.get_block_by_hash(&middle.block_hash).unwrap().unwrap().block_number, 150);
+        assert_eq!(store.get_chain_scan_count(), scans + 1);
+
+        // Rewriting an existing height invalidates the index
+        store.store_settlement_block(&test_block(10, 1_000)).unwrap();
+        assert_eq!(store.get_chain_summary().unwrap().total_amount_cents, 30_000 + 500 + 900);
+        assert!(store.get_block_by_hash(&middle.block_hash).unwrap().is_some());
+        assert!(store.get_chain_scan_count() > scans + 1);
+    }
+
+    #[test]
+    fn test_corrupt_block_fails_hash_verification() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+        store.set_verify_block_hashes(true);
+
+        let mut block = test_block(0, 100);
+        block.block_hash = block.compute_hash().unwrap();
+        assert!(block.verify_hash());
+        store.store_settlement_block(&block).unwrap();
+        store.invalidate_cached_blocks_from(0);
+        assert!(store.get_block_by_number(0).unwrap().is_some());
+
+        // Tamper with the stored bytes behind the store's back
+        let cf = store.db.cf_handle("settlement_blocks").unwrap();
+        let raw = String::from_utf8(store.db.get_cf(&cf, b"block_00000000").unwrap().unwrap()).unwrap();
+        let tampered = raw.replace("\"total_amount_cents\":100", "\"total_amount_cents\":900");
+        assert_ne!(raw, tampered);
+        store.db.put_cf(&cf, b"block_00000000", tampered.as_bytes()).unwrap();
+        store.invalidate_cached_blocks_from(0);
+
+        let corrupt: SettlementBlock = serde_json::from_str(&tampered).unwrap();
+        assert!(!corrupt.verify_hash());
+        assert!(matches!(store.get_block_by_number(0), Err(RocksError::CorruptBlock(0))));
+        assert!(matches!(store.get_all_blocks(), Err(RocksError::CorruptBlock(0))));
+        assert!(matches!(store.verify_stored_blocks(), Err(RocksError::CorruptBlock(0))));
+
+        // Without verification the block is returned as stored
+        store.set_verify_block_hashes(false);
+        store.invalidate_cached_blocks_from(0);
+        assert_eq!(store.get_block_by_number(0).unwrap().unwrap().settlement_summary.total_amount_cents, 900);
+    }
+
+    #[test]
+    fn test_legacy_blocks_verify_against_recorded_digests() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+
+        // A block hashed before canonical hashing: its hash cannot be recomputed
+        let mut legacy = test_block(0, 100);
+        legacy.hash_version = 0;
+        let mut current = test_block(1, 200);
+        current.block_hash = current.compute_hash().unwrap();
+        store.store_settlement_block(&legacy).unwrap();
+        store.store_settlement_block(&current).unwrap();
+
+        store.set_verify_block_hashes(true);
+        assert!(matches!(store.verify_stored_blocks(), Err(RocksError::CorruptBlock(0))));
+
+        // After the migration the legacy block verifies, and only the legacy block got a digest
+        assert_eq!(store.record_legacy_block_digests().unwrap(), 1);
+        assert_eq!(store.record_legacy_block_digests().unwrap(), 0);
+        assert_eq!(store.verify_stored_blocks().unwrap(), 2);
+        assert_eq!(store.get_all_blocks().unwrap().len(), 2);
+
+        // Later tampering with the legacy block is still caught
+        let cf = store.db.cf_handle("settlement_blocks").unwrap();
+        let raw = String::from_utf8(store.db.get_cf(&cf, b"block_00000000").unwrap().unwrap()).unwrap();
+        let tampered = raw.replace("\"total_amount_cents\":100", "\"total_amount_cents\":900");
+        store.db.put_cf(&cf, b"block_00000000", tampered.as_bytes()).unwrap();
+        store.invalidate_cached_blocks_from(0);
+        assert!(matches!(store.verify_stored_blocks(), Err(RocksError::CorruptBlock(0))));
+    }
+
+    #[test]
+    fn test_next_block_number_comes_from_stored_keys() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+        assert_eq!(store.next_block_number().unwrap(), 0);
+
+        // With a gap the count of blocks would point at an existing height
+        store.store_settlement_block(&test_block(0, 100)).unwrap();
+        store.store_settlement_block(&test_block(2, 100)).unwrap();
+        assert_eq!(store.next_block_number().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_blocking_propagates_results_and_errors() {
+        let temp_dir = tempdir().unwrap();
+        let store = Arc::new(RocksSettlementStore::new(temp_dir.path()).unwrap());
+
+        store.run_blocking(|s| s.store_settlement_block(&test_block(0, 100))).await.unwrap();
+        let block = store.run_blocking(|s| s.get_block_by_number(0)).await.unwrap().unwrap();
+        assert_eq!(block.settlement_summary.total_amount_cents, 100);
+
+        let err = store.run_blocking(|_| Err::<(), _>(RocksError::Other("disk failure".to_string()))).await.unwrap_err();
+        assert!(matches!(err, RocksError::Other(ref msg) if msg == "disk failure"));
+
+        // A panicking storage task surfaces as an error instead of tearing down the caller
+        let err = store.run_blocking(|_| -> Result<(), RocksError> { panic!("corrupt column family") }).await.unwrap_err();
+        assert!(matches!(err, RocksError::Other(ref msg) if msg.starts_with("Storage task failed")));
+    }
+
+    #[test]
+    fn test_get_block_by_number() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+
+        for n in 0..3 {
+            store.store_settlement_block(&test_block(n, 100 * (n + 1))).unwrap();
+        }
+
+        let block = store.get_block_by_number(1).unwrap().unwrap();
+        assert_eq!(block.block_number, 1);
+        assert_eq!(block.settlement_summary.total_amount_cents, 200);
+        assert!(store.get_block_by_number(3).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_record_index_rebuilt_for_existing_records() {
+        let temp_dir = tempdir().unwrap();
+        {
+            let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+            // Simulate a database written before the record index existed
+            let cf = store.db.cf_handle("bce_records").unwrap();
+            for (id, status) in [("BCE-1", SettlementStatus::Pending), ("BCE-2", SettlementStatus::Disputed)] {
+                let record = BceRecord {
+                    record_id: id.to_string(),
+                    settlement_status: status,
+                    ..Default::default()
+                };
+                store.db.put_cf(&cf, id.as_bytes(), serde_json::to_vec(&record).unwrap()).unwrap();
+            }
+            let meta_cf = store.db.cf_handle("chain_metadata").unwrap();
+            store.db.delete_cf(&meta_cf, RECORD_INDEX_VERSION_KEY).unwrap();
+        }
+
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+        let disputed = store.get_bce_records_by_status(&SettlementStatus::Disputed).unwrap();
+        assert_eq!(disputed.len(), 1);
+        assert_eq!(disputed[0].record_id, "BCE-2");
+        assert_eq!(store.get_bce_records_by_status(&SettlementStatus::Pending).unwrap().len(), 1);
+    }
+
+    fn store_linked_chain(store: &RocksSettlementStore, length: u64) -> Vec<SettlementBlock> {
+        let mut blocks: Vec<SettlementBlock> = Vec::new();
+        for block_number in 0..length {
+            let mut block = test_block(block_number, 100);
+            if let Some(previous) = blocks.last() {
+                block.previous_hash = previous.block_hash;
+            }
+            store.store_bce_record(&BceRecord {
+                record_id: format!("BCE-{}", block_number),
+                ..Default::default()
+            }).unwrap();
+            store.store_settlement_block(&block).unwrap();
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    #[test]
+    fn test_integrity_scan_accepts_consistent_chain() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+        store_linked_chain(&store, 3);
+
+        let report = store.verify_integrity().unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.blocks_checked, 3);
+    }
+
+    #[test]
+    fn test_integrity_scan_reports_corruption() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+        let blocks = store_linked_chain(&store, 5);
+
+        // Relink block 2 to the wrong parent, drop block 3 and a record of block 4
+        let mut relinked = blocks[2].clone();
+        relinked.previous_hash = Blake2bHash::hash(b"somewhere else");
+        store.store_settlement_block(&relinked).unwrap();
+        let blocks_cf = store.db.cf_handle("settlement_blocks").unwrap();
+        store.db.delete_cf(&blocks_cf, b"block_00000003").unwrap();
+        let records_cf = store.db.cf_handle("bce_records").unwrap();
+        store.db.delete_cf(&records_cf, b"BCE-4").unwrap();
+        store.invalidate_cached_blocks_from(0);
+
+        let report = store.verify_integrity().unwrap();
+        assert!(!report.is_consistent());
+        assert_eq!(report.blocks_checked, 4);
+        assert_eq!(report.broken_links, vec![2]);
+        assert_eq!(report.gaps, vec![(3, 4)]);
+        assert_eq!(report.dangling_records, vec![(4, "BCE-4".to_string())]);
+    }
+
+    #[test]
+    fn test_blocks_and_records_iterate_in_separate_column_families() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+        store_linked_chain(&store, 3);
+        store.store_bce_record(&BceRecord { record_id: "BCE-extra".to_string(), ..Default::default() }).unwrap();
+
+        let scan = |name: &str| -> Vec<(Vec<u8>, Vec<u8>)> {
+            let cf = store.db.cf_handle(name).unwrap();
+            store.db.iterator_cf(&cf, rocksdb::IteratorMode::Start)
+                .map(|item| {
+                    let (key, value) = item.unwrap();
+                    (key.to_vec(), value.to_vec())
+                })
+                .collect()
+        };
+
+        let blocks = scan("settlement_blocks");
+        assert_eq!(blocks.len(), 3);
+        for (key, value) in &blocks {
+            assert!(key.starts_with(b"block_"));
+            serde_json::from_slice::<SettlementBlock>(value).unwrap();
+        }
+
+        let records = scan("bce_records");
+        assert_eq!(records.len(), 4);
+        for (key, value) in &records {
+            let record: BceRecord = serde_json::from_slice(value).unwrap();
+            assert_eq!(key.as_slice(), record.record_id.as_bytes());
+        }
+
+        // Index and metadata entries never show up in either scan
+        assert!(!scan("record_index").is_empty());
+        assert!(scan("chain_metadata").iter().any(|(key, _)| key.as_slice() == AGGREGATES_KEY));
+        assert_eq!(store.get_all_blocks().unwrap().len(), 3);
+        assert_eq!(store.get_all_bce_records().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_finalize_atomic_drops_consensus_round() {
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+
+        let block = test_block(0, 100);
+        store.store_proposed_block(&block).unwrap();
+        store.store_consensus_vote(&Vote {
+            validator_id: "tmobile-de".to_string(),
+            block_hash: block.block_hash,
+            approve: true,
+            signature: vec![],
+            timestamp: std::time::SystemTime::now(),
+        }).unwrap();
+        assert_eq!(store.get_consensus_rounds().unwrap().len(), 1);
+
+        // A failed finalize keeps the round so it resumes on restart
+        store.fail_next_finalize();
+        assert!(store.finalize_atomic(&block, &[], &[]).is_err());
+        assert_eq!(store.get_consensus_rounds().unwrap().len(), 1);
+
+        store.finalize_atomic(&block, &[], &[]).unwrap();
+        assert!(store.get_consensus_rounds().unwrap().is_empty());
+        assert!(store.get_block_by_number(0).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_unversioned_record_proofs_migrate_on_load() {
+        use ark_serialize::CanonicalSerialize;
+
+        let temp_dir = tempdir().unwrap();
+        let store = RocksSettlementStore::new(temp_dir.path()).unwrap();
+
+        let proof = ark_groth16::Proof::<ark_bn254::Bn254>::default();
+        let mut unversioned = Vec::new();
+        proof.serialize_compressed(&mut unversioned).unwrap();
+        let mock = vec![7u8; 40];
+
+        store.store_bce_records(&[
+            BceRecord { record_id: "BCE-OLD".to_string(), zkp_proof: Some(unversioned.clone()), ..Default::default() },
+            BceRecord { record_id: "BCE-MOCK".to_string(), zkp_proof: Some(mock.clone()), ..Default::default() },
+        ]).unwrap();
+
+        let migrated = store.get_bce_record("BCE-OLD").unwrap().unwrap().zkp_proof.unwrap();
+        assert_eq!(migrated, crate::zkp::settlement_proofs::encode_proof(&proof).unwrap());
+        assert_eq!(store.get_bce_record("BCE-MOCK").unwrap().unwrap().zkp_proof.unwrap(), mock);
+        assert!(store.get_all_bce_records().unwrap().iter()
+            .any(|record| record.zkp_proof.as_ref() == Some(&migrated)));
+    }
+}
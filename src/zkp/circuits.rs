@@ -6,11 +6,20 @@ use ark_r1cs_std::{
     alloc::AllocVar,
     boolean::Boolean,
     eq::EqGadget,
-    fields::fp::FpVar,
+    fields::{fp::FpVar, FieldVar},
 };
+use ark_crypto_primitives::sponge::constraints::CryptographicSpongeVar;
+use ark_crypto_primitives::sponge::poseidon::constraints::PoseidonSpongeVar;
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge};
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
 use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
+use crate::hash::Blake2bHash;
+
 /// Range check utility for ZK circuits - enhanced for 5-party constraints
 fn enforce_range_check<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
@@ -33,6 +42,18 @@ fn enforce_range_check<F: PrimeField>(
     Ok(())
 }
 
+/// Conservation law: offset-encoded net positions must sum to exactly `positions.len() * offset`,
+/// i.e. every debit is matched by a credit and no money is created
+fn enforce_conservation_law<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    positions: &[FpVar<F>],
+    offset: u64,
+) -> Result<(), SynthesisError> {
+    let total_positions = positions.iter().fold(FpVar::zero(), |acc, position| acc + position);
+    let expected_total = FpVar::new_constant(cs, F::from(offset) * F::from(positions.len() as u64))?;
+    total_positions.enforce_equal(&expected_total)
+}
+
 /// BCE Privacy Circuit - Enhanced for 5-party SP consortium
 /// Proves that encrypted BCE data represents correct settlement amounts
 /// without revealing individual call/data/SMS records across 5 networks
@@ -240,56 +261,117 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for BCEPrivacyCircuit<F> {
     }
 }
 
-/// Settlement Calculation Circuit - Enhanced for 5-party netting
-/// Proves that 5-party multilateral netting calculations are correct
+/// Offset added to signed net positions so they fit unsigned field elements
+pub const NET_POSITION_OFFSET: i64 = 10_000_000;
+
+/// Parties in the original SP consortium (T-Mobile, Vodafone, Orange, Telefónica, SFR)
+pub const CONSORTIUM_PARTIES: usize = 5;
+
+/// Number of bilateral amounts between `parties` parties: each party pays every other one
+pub const fn bilateral_amount_count(parties: usize) -> usize {
+    parties * parties.saturating_sub(1)
+}
+
+/// Result of multilateral netting over the bilateral amounts of a consortium
+#[derive(Debug, Clone, PartialEq)]
+pub struct NettingSummary {
+    /// Outgoing minus incoming per party, in party order
+    pub net_positions: Vec<i64>,
+    pub net_settlement_count: u64,
+    /// Sum of positive net positions - the amount that actually moves
+    pub total_net_amount: u64,
+    pub gross_total: u64,
+    /// Defined as 0 when there is no bilateral volume
+    pub savings_percentage: u64,
+}
+
+/// Bilateral amounts that do not match the number of parties they are netted for
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{parties} parties need {expected} bilateral amounts, got {actual}")]
+pub struct NettingShapeError {
+    pub parties: usize,
+    pub expected: usize,
+    pub actual: usize,
+}
+
+/// Compute net positions and savings for the 5-party consortium from the 20 bilateral
+/// amounts laid out as in `SettlementCalculationCircuit::new`
+pub fn compute_multilateral_netting(bilateral_amounts: &[u64; 20]) -> NettingSummary {
+    compute_n_party_netting(CONSORTIUM_PARTIES, bilateral_amounts)
+        .expect("20 bilateral amounts always fit the 5-party consortium")
+}
+
+/// Compute net positions and savings from bilateral amounts of `parties` parties
+/// (party p's outgoing at indices p*(N-1)..(p+1)*(N-1), to the other parties in order)
+pub fn compute_n_party_netting(parties: usize, bilateral_amounts: &[u64]) -> Result<NettingSummary, NettingShapeError> {
+    let expected = bilateral_amount_count(parties);
+    if bilateral_amounts.len() != expected {
+        return Err(NettingShapeError { parties, expected, actual: bilateral_amounts.len() });
+    }
+
+    let mut net_positions = vec![0i64; parties];
+    for from in 0..parties {
+        let receivers = (0..parties).filter(|to| *to != from);
+        for (slot, to) in receivers.enumerate() {
+            let amount = bilateral_amounts[from * (parties - 1) + slot] as i64;
+            net_positions[from] += amount;
+            net_positions[to] -= amount;
+        }
+    }
+
+    let gross_total: u64 = bilateral_amounts.iter().sum();
+    let total_net_amount: u64 = net_positions.iter()
+        .filter(|position| **position > 0)
+        .map(|position| *position as u64)
+        .sum();
+
+    // n parties with a non-zero position can always be settled in n-1 transfers
+    let active_parties = net_positions.iter().filter(|position| **position != 0).count() as u64;
+    let net_settlement_count = active_parties.saturating_sub(1);
+
+    // Empty consortium: nothing to net, so no savings rather than a division by zero
+    let savings_percentage = if gross_total == 0 {
+        0
+    } else {
+        ((gross_total - total_net_amount) * 100) / gross_total
+    };
+
+    Ok(NettingSummary {
+        net_positions,
+        net_settlement_count,
+        total_net_amount,
+        gross_total,
+        savings_percentage,
+    })
+}
+
+/// Settlement Calculation Circuit - multilateral netting for N parties
+/// Proves that multilateral netting calculations are correct
 /// without revealing individual bilateral amounts
 #[derive(Clone)]
 pub struct SettlementCalculationCircuit<F: PrimeField> {
-    // Private inputs: all bilateral settlement amounts for 5 parties
-    // T-Mobile, Vodafone, Orange, Telefónica, SFR
-    pub tmobile_to_vodafone: Option<F>,
-    pub tmobile_to_orange: Option<F>,
-    pub tmobile_to_telenor: Option<F>,
-    pub tmobile_to_sfr: Option<F>,
-    
-    pub vodafone_to_tmobile: Option<F>,
-    pub vodafone_to_orange: Option<F>,
-    pub vodafone_to_telenor: Option<F>,
-    pub vodafone_to_sfr: Option<F>,
-    
-    pub orange_to_tmobile: Option<F>,
-    pub orange_to_vodafone: Option<F>,
-    pub orange_to_telenor: Option<F>,
-    pub orange_to_sfr: Option<F>,
-    
-    pub telenor_to_tmobile: Option<F>,
-    pub telenor_to_vodafone: Option<F>,
-    pub telenor_to_orange: Option<F>,
-    pub telenor_to_sfr: Option<F>,
-    
-    pub sfr_to_tmobile: Option<F>,
-    pub sfr_to_vodafone: Option<F>,
-    pub sfr_to_orange: Option<F>,
-    pub sfr_to_telenor: Option<F>,
-
-    // Private: net positions after multilateral netting
-    pub tmobile_position: Option<F>,
-    pub vodafone_position: Option<F>,
-    pub orange_position: Option<F>,
-    pub telenor_position: Option<F>,
-    pub sfr_position: Option<F>,
+    /// Number of parties the circuit is shaped for; proving keys are per party count
+    pub parties: usize,
+
+    // Private inputs: N*(N-1) bilateral settlement amounts, party p's outgoing
+    // at indices p*(N-1)..(p+1)*(N-1), to the other parties in order
+    pub bilateral_amounts: Vec<Option<F>>,
+
+    // Private: N net positions after multilateral netting, offset by NET_POSITION_OFFSET
+    pub net_positions: Vec<Option<F>>,
 
     // Public inputs: final net settlements
-    pub net_settlement_count: Option<F>,    // Number of final settlements (max 10 for 5 parties)
+    pub net_settlement_count: Option<F>,    // Number of final settlements (max N*(N-1)/2)
     pub total_net_amount: Option<F>,        // Total net settlement volume
     pub period_hash: Option<F>,             // Settlement period
     pub savings_percentage: Option<F>,       // Percentage reduction achieved
-    pub consortium_hash: Option<F>,         // 5-party consortium hash
+    pub consortium_hash: Option<F>,         // Consortium hash
 
     _phantom: PhantomData<F>,
 }
 
 impl<F: PrimeField> SettlementCalculationCircuit<F> {
+    /// 5-party circuit, kept for the existing consortium setup and proving keys
     pub fn new(
         bilateral_amounts: [u64; 20], // All 20 bilateral amounts (5x4 each)
         net_positions: [i64; 5],      // Net positions for all 5 parties
@@ -299,44 +381,40 @@ impl<F: PrimeField> SettlementCalculationCircuit<F> {
         savings_percentage: u64,
         consortium_hash: u64,
     ) -> Self {
-        Self {
-            // T-Mobile outgoing (indices 0-3)
-            tmobile_to_vodafone: Some(F::from(bilateral_amounts[0])),
-            tmobile_to_orange: Some(F::from(bilateral_amounts[1])),
-            tmobile_to_telenor: Some(F::from(bilateral_amounts[2])),
-            tmobile_to_sfr: Some(F::from(bilateral_amounts[3])),
-            
-            // Vodafone outgoing (indices 4-7)
-            vodafone_to_tmobile: Some(F::from(bilateral_amounts[4])),
-            vodafone_to_orange: Some(F::from(bilateral_amounts[5])),
-            vodafone_to_telenor: Some(F::from(bilateral_amounts[6])),
-            vodafone_to_sfr: Some(F::from(bilateral_amounts[7])),
-            
-            // Orange outgoing (indices 8-11)
-            orange_to_tmobile: Some(F::from(bilateral_amounts[8])),
-            orange_to_vodafone: Some(F::from(bilateral_amounts[9])),
-            orange_to_telenor: Some(F::from(bilateral_amounts[10])),
-            orange_to_sfr: Some(F::from(bilateral_amounts[11])),
-            
-            // Telefónica outgoing (indices 12-15)
-            telenor_to_tmobile: Some(F::from(bilateral_amounts[12])),
-            telenor_to_vodafone: Some(F::from(bilateral_amounts[13])),
-            telenor_to_orange: Some(F::from(bilateral_amounts[14])),
-            telenor_to_sfr: Some(F::from(bilateral_amounts[15])),
-            
-            // SFR outgoing (indices 16-19)
-            sfr_to_tmobile: Some(F::from(bilateral_amounts[16])),
-            sfr_to_vodafone: Some(F::from(bilateral_amounts[17])),
-            sfr_to_orange: Some(F::from(bilateral_amounts[18])),
-            sfr_to_telenor: Some(F::from(bilateral_amounts[19])),
+        Self::new_n_party(
+            CONSORTIUM_PARTIES,
+            &bilateral_amounts,
+            &net_positions,
+            net_settlement_count,
+            total_net_amount,
+            period_hash,
+            savings_percentage,
+            consortium_hash,
+        )
+    }
 
+    /// Circuit for `parties` parties. A mismatch between `parties` and the number of
+    /// amounts or positions is reported as unsatisfiable during constraint generation.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_n_party(
+        parties: usize,
+        bilateral_amounts: &[u64],
+        net_positions: &[i64],
+        net_settlement_count: u64,
+        total_net_amount: u64,
+        period_hash: [u8; 8],
+        savings_percentage: u64,
+        consortium_hash: u64,
+    ) -> Self {
+        Self {
+            parties,
+            bilateral_amounts: bilateral_amounts.iter()
+                .map(|amount| Some(F::from(*amount)))
+                .collect(),
             // Handle negative positions by adding large offset
-            tmobile_position: Some(F::from((net_positions[0] + 10_000_000) as u64)),
-            vodafone_position: Some(F::from((net_positions[1] + 10_000_000) as u64)),
-            orange_position: Some(F::from((net_positions[2] + 10_000_000) as u64)),
-            telenor_position: Some(F::from((net_positions[3] + 10_000_000) as u64)),
-            sfr_position: Some(F::from((net_positions[4] + 10_000_000) as u64)),
-
+            net_positions: net_positions.iter()
+                .map(|position| Some(F::from((position + NET_POSITION_OFFSET) as u64)))
+                .collect(),
             net_settlement_count: Some(F::from(net_settlement_count)),
             total_net_amount: Some(F::from(total_net_amount)),
             period_hash: Some(F::from(u64::from_le_bytes(period_hash))),
@@ -346,33 +424,46 @@ impl<F: PrimeField> SettlementCalculationCircuit<F> {
         }
     }
 
+    /// Build the 5-party circuit from bilateral amounts, deriving netting outputs
+    pub fn from_bilateral(
+        bilateral_amounts: [u64; 20],
+        period_hash: [u8; 8],
+        consortium_hash: u64,
+    ) -> Self {
+        Self::from_bilateral_n_party(CONSORTIUM_PARTIES, &bilateral_amounts, period_hash, consortium_hash)
+            .expect("20 bilateral amounts always fit the 5-party consortium")
+    }
+
+    /// Build the circuit for `parties` parties from bilateral amounts, deriving netting outputs
+    pub fn from_bilateral_n_party(
+        parties: usize,
+        bilateral_amounts: &[u64],
+        period_hash: [u8; 8],
+        consortium_hash: u64,
+    ) -> Result<Self, NettingShapeError> {
+        let netting = compute_n_party_netting(parties, bilateral_amounts)?;
+        Ok(Self::new_n_party(
+            parties,
+            bilateral_amounts,
+            &netting.net_positions,
+            netting.net_settlement_count,
+            netting.total_net_amount,
+            period_hash,
+            netting.savings_percentage,
+            consortium_hash,
+        ))
+    }
+
     pub fn empty() -> Self {
+        Self::empty_n_party(CONSORTIUM_PARTIES)
+    }
+
+    /// Unassigned circuit for `parties` parties, for key generation
+    pub fn empty_n_party(parties: usize) -> Self {
         Self {
-            tmobile_to_vodafone: None,
-            tmobile_to_orange: None,
-            tmobile_to_telenor: None,
-            tmobile_to_sfr: None,
-            vodafone_to_tmobile: None,
-            vodafone_to_orange: None,
-            vodafone_to_telenor: None,
-            vodafone_to_sfr: None,
-            orange_to_tmobile: None,
-            orange_to_vodafone: None,
-            orange_to_telenor: None,
-            orange_to_sfr: None,
-            telenor_to_tmobile: None,
-            telenor_to_vodafone: None,
-            telenor_to_orange: None,
-            telenor_to_sfr: None,
-            sfr_to_tmobile: None,
-            sfr_to_vodafone: None,
-            sfr_to_orange: None,
-            sfr_to_telenor: None,
-            tmobile_position: None,
-            vodafone_position: None,
-            orange_position: None,
-            telenor_position: None,
-            sfr_position: None,
+            parties,
+            bilateral_amounts: vec![None; bilateral_amount_count(parties)],
+            net_positions: vec![None; parties],
             net_settlement_count: None,
             total_net_amount: None,
             period_hash: None,
@@ -385,88 +476,23 @@ impl<F: PrimeField> SettlementCalculationCircuit<F> {
 
 impl<F: PrimeField> ConstraintSynthesizer<F> for SettlementCalculationCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        // Allocate all 20 bilateral amount witnesses
-        let tmo_vod = FpVar::new_witness(cs.clone(), || {
-            self.tmobile_to_vodafone.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tmo_org = FpVar::new_witness(cs.clone(), || {
-            self.tmobile_to_orange.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tmo_tel = FpVar::new_witness(cs.clone(), || {
-            self.tmobile_to_telenor.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tmo_sfr = FpVar::new_witness(cs.clone(), || {
-            self.tmobile_to_sfr.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-
-        let vod_tmo = FpVar::new_witness(cs.clone(), || {
-            self.vodafone_to_tmobile.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let vod_org = FpVar::new_witness(cs.clone(), || {
-            self.vodafone_to_orange.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let vod_tel = FpVar::new_witness(cs.clone(), || {
-            self.vodafone_to_telenor.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let vod_sfr = FpVar::new_witness(cs.clone(), || {
-            self.vodafone_to_sfr.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-
-        let org_tmo = FpVar::new_witness(cs.clone(), || {
-            self.orange_to_tmobile.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let org_vod = FpVar::new_witness(cs.clone(), || {
-            self.orange_to_vodafone.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let org_tel = FpVar::new_witness(cs.clone(), || {
-            self.orange_to_telenor.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let org_sfr = FpVar::new_witness(cs.clone(), || {
-            self.orange_to_sfr.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-
-        let tel_tmo = FpVar::new_witness(cs.clone(), || {
-            self.telenor_to_tmobile.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tel_vod = FpVar::new_witness(cs.clone(), || {
-            self.telenor_to_vodafone.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tel_org = FpVar::new_witness(cs.clone(), || {
-            self.telenor_to_orange.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tel_sfr = FpVar::new_witness(cs.clone(), || {
-            self.telenor_to_sfr.ok_or(SynthesisError::AssignmentMissing)
-        })?;
+        let parties = self.parties;
+        if parties < 2
+            || self.bilateral_amounts.len() != bilateral_amount_count(parties)
+            || self.net_positions.len() != parties
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
 
-        let sfr_tmo = FpVar::new_witness(cs.clone(), || {
-            self.sfr_to_tmobile.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let sfr_vod = FpVar::new_witness(cs.clone(), || {
-            self.sfr_to_vodafone.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let sfr_org = FpVar::new_witness(cs.clone(), || {
-            self.sfr_to_orange.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let sfr_tel = FpVar::new_witness(cs.clone(), || {
-            self.sfr_to_telenor.ok_or(SynthesisError::AssignmentMissing)
-        })?;
+        // Allocate all N*(N-1) bilateral amount witnesses
+        let bilateral = self.bilateral_amounts.iter()
+            .map(|amount| FpVar::new_witness(cs.clone(), || amount.ok_or(SynthesisError::AssignmentMissing)))
+            .collect::<Result<Vec<_>, _>>()?;
 
         // Allocate net position witnesses (with offset to handle negatives)
-        let tmo_pos = FpVar::new_witness(cs.clone(), || {
-            self.tmobile_position.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let vod_pos = FpVar::new_witness(cs.clone(), || {
-            self.vodafone_position.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let org_pos = FpVar::new_witness(cs.clone(), || {
-            self.orange_position.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let tel_pos = FpVar::new_witness(cs.clone(), || {
-            self.telenor_position.ok_or(SynthesisError::AssignmentMissing)
-        })?;
-        let sfr_pos = FpVar::new_witness(cs.clone(), || {
-            self.sfr_position.ok_or(SynthesisError::AssignmentMissing)
-        })?;
+        let positions = self.net_positions.iter()
+            .map(|position| FpVar::new_witness(cs.clone(), || position.ok_or(SynthesisError::AssignmentMissing)))
+            .collect::<Result<Vec<_>, _>>()?;
 
         // Allocate public inputs
         let net_count = FpVar::new_input(cs.clone(), || {
@@ -485,82 +511,518 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for SettlementCalculationCircuit<F>
             self.consortium_hash.ok_or(SynthesisError::AssignmentMissing)
         })?;
 
-        let offset = FpVar::new_constant(cs.clone(), F::from(10_000_000u64))?;
-
-        // Constraint 1: Verify net position calculations for all 5 parties
-        // T-Mobile net = (outgoing to all 4) - (incoming from all 4)
-        let tmo_outgoing = &tmo_vod + &tmo_org + &tmo_tel + &tmo_sfr;
-        let tmo_incoming = &vod_tmo + &org_tmo + &tel_tmo + &sfr_tmo;
-        let tmo_net_calculated = &tmo_outgoing - &tmo_incoming + &offset;
-        tmo_pos.enforce_equal(&tmo_net_calculated)?;
-
-        // Vodafone net
-        let vod_outgoing = &vod_tmo + &vod_org + &vod_tel + &vod_sfr;
-        let vod_incoming = &tmo_vod + &org_vod + &tel_vod + &sfr_vod;
-        let vod_net_calculated = &vod_outgoing - &vod_incoming + &offset;
-        vod_pos.enforce_equal(&vod_net_calculated)?;
-
-        // Orange net
-        let org_outgoing = &org_tmo + &org_vod + &org_tel + &org_sfr;
-        let org_incoming = &tmo_org + &vod_org + &tel_org + &sfr_org;
-        let org_net_calculated = &org_outgoing - &org_incoming + &offset;
-        org_pos.enforce_equal(&org_net_calculated)?;
-
-        // Telefónica net
-        let tel_outgoing = &tel_tmo + &tel_vod + &tel_org + &tel_sfr;
-        let tel_incoming = &tmo_tel + &vod_tel + &org_tel + &sfr_tel;
-        let tel_net_calculated = &tel_outgoing - &tel_incoming + &offset;
-        tel_pos.enforce_equal(&tel_net_calculated)?;
-
-        // SFR net
-        let sfr_outgoing = &sfr_tmo + &sfr_vod + &sfr_org + &sfr_tel;
-        let sfr_incoming = &tmo_sfr + &vod_sfr + &org_sfr + &tel_sfr;
-        let sfr_net_calculated = &sfr_outgoing - &sfr_incoming + &offset;
-        sfr_pos.enforce_equal(&sfr_net_calculated)?;
-
-        // Constraint 2: Conservation law - net positions sum to zero (with 5x offset)
-        let total_positions = &tmo_pos + &vod_pos + &org_pos + &tel_pos + &sfr_pos;
-        let expected_total = FpVar::new_constant(cs.clone(), F::from(50_000_000u64))?; // 5 * 10M offset
-        total_positions.enforce_equal(&expected_total)?;
-
-        // Constraint 3: Enhanced Security Range Checks for 5-Party Settlement
+        let offset = FpVar::new_constant(cs.clone(), F::from(NET_POSITION_OFFSET as u64))?;
+
+        // Constraint 1: Verify net position calculations for every party
+        // net = (outgoing to all others) - (incoming from all others)
+        let mut outgoing = vec![FpVar::<F>::zero(); parties];
+        let mut incoming = vec![FpVar::<F>::zero(); parties];
+        for from in 0..parties {
+            let receivers = (0..parties).filter(|to| *to != from);
+            for (slot, to) in receivers.enumerate() {
+                let amount = &bilateral[from * (parties - 1) + slot];
+                outgoing[from] += amount;
+                incoming[to] += amount;
+            }
+        }
+        for ((position, outgoing), incoming) in positions.iter().zip(&outgoing).zip(&incoming) {
+            let net_calculated = outgoing - incoming + &offset;
+            position.enforce_equal(&net_calculated)?;
+        }
+
+        // Constraint 2: Conservation law - net positions sum to zero (with N x offset)
+        enforce_conservation_law(cs.clone(), &positions, NET_POSITION_OFFSET as u64)?;
+
+        // Constraint 3: Range checks
         // Each bilateral amount: 0 to €500K (50M cents) for large consortium settlements
         let bilateral_max = 50_000_000u64;
-        enforce_range_check(cs.clone(), &tmo_vod, bilateral_max, 26, "tmobile_to_vodafone")?;
-        enforce_range_check(cs.clone(), &tmo_org, bilateral_max, 26, "tmobile_to_orange")?;
-        enforce_range_check(cs.clone(), &tmo_tel, bilateral_max, 26, "tmobile_to_telenor")?;
-        enforce_range_check(cs.clone(), &tmo_sfr, bilateral_max, 26, "tmobile_to_sfr")?;
-        
-        // Apply range checks to all 20 bilateral amounts (abbreviated for brevity)
-        // In production, all 20 would have range checks
-        
-        // Net settlement count: 0 to 10 (maximum possible in 5-party system)
-        enforce_range_check(cs.clone(), &net_count, 10, 4, "net_settlement_count")?;
+        for (index, amount) in bilateral.iter().enumerate() {
+            enforce_range_check(cs.clone(), amount, bilateral_max, 26, &format!("bilateral_amount_{}", index))?;
+        }
 
-        // Total net amount: Up to €5M for large 5-party settlements
+        // Net settlement count: at most one settlement per pair of parties
+        let max_net_count = (parties * (parties - 1) / 2) as u64;
+        let net_count_bits = (u64::BITS - max_net_count.leading_zeros()) as usize;
+        enforce_range_check(cs.clone(), &net_count, max_net_count, net_count_bits, "net_settlement_count")?;
+
+        // Total net amount: Up to €5M for large consortium settlements
         enforce_range_check(cs.clone(), &total_net, 500_000_000, 29, "total_net_amount")?;
 
         // Savings percentage: 0 to 100%
         enforce_range_check(cs.clone(), &savings_pct, 100, 7, "savings_percentage")?;
 
-        // Constraint 4: 5-Party Settlement Logic Validation
-        let gross_total = &tmo_vod + &tmo_org + &tmo_tel + &tmo_sfr +
-                         &vod_tmo + &vod_org + &vod_tel + &vod_sfr +
-                         &org_tmo + &org_vod + &org_tel + &org_sfr +
-                         &tel_tmo + &tel_vod + &tel_org + &tel_sfr +
-                         &sfr_tmo + &sfr_vod + &sfr_org + &sfr_tel;
+        // Constraint 4: Settlement Logic Validation
+        let gross_total = bilateral.iter().fold(FpVar::zero(), |acc, amount| acc + amount);
 
-        // Range check the gross total (max €10M for 20 settlements)
+        // Range check the gross total (max €10M)
         enforce_range_check(cs.clone(), &gross_total, 1_000_000_000, 30, "gross_total")?;
 
-        // Constraint 5: Consortium validation
-        let valid_consortium = FpVar::new_constant(cs.clone(), F::from(54321u64))?; // 5-party hash
+        // Constraint 5: Empty consortium - with no bilateral volume there is
+        // nothing to net, so savings, net volume and settlement count must be 0
+        let gross_is_zero = gross_total.is_zero()?;
+        let zero = FpVar::<F>::zero();
+        savings_pct.conditional_enforce_equal(&zero, &gross_is_zero)?;
+        total_net.conditional_enforce_equal(&zero, &gross_is_zero)?;
+        net_count.conditional_enforce_equal(&zero, &gross_is_zero)?;
+
+        // Constraint 6: Consortium validation
+        let valid_consortium = FpVar::new_constant(cs.clone(), F::from(54321u64))?; // consortium hash
         consortium_hash.enforce_equal(&valid_consortium)?;
 
         Ok(())
     }
 }
 
+/// Maximum number of operators a single balance conservation proof covers
+pub const CONSERVATION_PROOF_CAPACITY: usize = 16;
+
+/// Commitment to a block's operator balances, ordered by operator.
+/// Slots past the last operator are absorbed as zeros, as in `BalanceConservationCircuit`.
+pub fn operator_balances_commitment<F: PrimeField + Absorb>(operator_balances: &HashMap<String, i64>) -> F {
+    let mut operators: Vec<&String> = operator_balances.keys().collect();
+    operators.sort();
+
+    let mut elements = Vec::with_capacity(2 * CONSERVATION_PROOF_CAPACITY);
+    for i in 0..CONSERVATION_PROOF_CAPACITY {
+        match operators.get(i) {
+            Some(operator) => {
+                elements.push(record_id_field(operator));
+                elements.push(signed_charge_field(operator_balances[*operator]));
+            }
+            None => elements.extend([F::zero(), F::zero()]),
+        }
+    }
+
+    let mut sponge = PoseidonSponge::<F>::new(&records_commitment_config());
+    sponge.absorb(&elements);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+/// Public inputs of `BalanceConservationCircuit`, in allocation order
+pub fn balance_conservation_public_inputs<F: PrimeField>(balances_commitment: F, block_hash: &[u8]) -> Vec<F> {
+    vec![balances_commitment, F::from_le_bytes_mod_order(block_hash)]
+}
+
+/// Balance Conservation Circuit
+/// Proves that a block's operator balances sum to zero, using the same conservation law as
+/// the settlement calculation circuit. The balances are bound to the proof through a Poseidon
+/// commitment that validators recompute from the block's settlement summary.
+#[derive(Clone)]
+pub struct BalanceConservationCircuit<F: PrimeField> {
+    // Private inputs (witness): operator ids and signed balances, ordered by operator
+    pub operator_ids: Option<Vec<F>>,
+    pub balances_cents: Option<Vec<i64>>,
+
+    // Public inputs
+    pub balances_commitment: Option<F>,
+    pub block_binding: Option<F>, // Block hash, so the proof cannot be replayed for another block
+
+    _phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> BalanceConservationCircuit<F> {
+    pub fn new(operator_balances: &HashMap<String, i64>, block_hash: &[u8]) -> Self {
+        let mut operators: Vec<&String> = operator_balances.keys().collect();
+        operators.sort();
+
+        let inputs = balance_conservation_public_inputs::<F>(operator_balances_commitment(operator_balances), block_hash);
+        Self {
+            operator_ids: Some(operators.iter().map(|operator| record_id_field(operator)).collect()),
+            balances_cents: Some(operators.iter().map(|operator| operator_balances[*operator]).collect()),
+            balances_commitment: Some(inputs[0]),
+            block_binding: Some(inputs[1]),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> BalanceConservationCircuit<F> {
+    pub fn empty() -> Self {
+        Self {
+            operator_ids: None,
+            balances_cents: None,
+            balances_commitment: None,
+            block_binding: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for BalanceConservationCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let balances = self.balances_cents.as_ref();
+        let operator_ids = self.operator_ids.as_ref();
+        if balances.is_some_and(|b| b.len() > CONSERVATION_PROOF_CAPACITY)
+            || operator_ids.map(Vec::len) != balances.map(Vec::len)
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        // Every slot is allocated so the constraint system does not depend on the operator count;
+        // unused slots are committed as zeros, so they cannot hide a balance
+        let mut positions = Vec::with_capacity(CONSERVATION_PROOF_CAPACITY);
+        let mut committed = Vec::with_capacity(2 * CONSERVATION_PROOF_CAPACITY);
+        for i in 0..CONSERVATION_PROOF_CAPACITY {
+            let operator_id = FpVar::new_witness(cs.clone(), || {
+                operator_ids.map(|ids| ids.get(i).copied().unwrap_or_else(F::zero))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let balance = FpVar::new_witness(cs.clone(), || {
+                balances.map(|b| signed_charge_field::<F>(b.get(i).copied().unwrap_or(0)))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+            positions.push(&balance + F::from(NET_POSITION_OFFSET as u64));
+            committed.push(operator_id);
+            committed.push(balance);
+        }
+
+        // Allocate public inputs (order must match balance_conservation_public_inputs)
+        let balances_commitment = FpVar::new_input(cs.clone(), || {
+            self.balances_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let block_binding = FpVar::new_input(cs.clone(), || {
+            self.block_binding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // Constraint 1: the offset positions conserve, so the balances sum to zero
+        enforce_conservation_law(cs.clone(), &positions, NET_POSITION_OFFSET as u64)?;
+
+        // Constraint 2: the balances are the ones committed to for the block
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &records_commitment_config());
+        sponge.absorb(&committed)?;
+        sponge.squeeze_field_elements(1)?[0].enforce_equal(&balances_commitment)?;
+
+        // An input that appears in no constraint is not bound by the proof
+        let _ = block_binding.square()?;
+
+        Ok(())
+    }
+}
+
+/// Consortium-agreed band that operator rates must fall within (inclusive, in cents)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateBounds {
+    pub min_call_rate_cents: u64,
+    pub max_call_rate_cents: u64,
+    pub min_data_rate_cents: u64,
+    pub max_data_rate_cents: u64,
+}
+
+impl RateBounds {
+    pub fn contains(&self, call_rate_cents: u64, data_rate_cents: u64) -> bool {
+        (self.min_call_rate_cents..=self.max_call_rate_cents).contains(&call_rate_cents)
+            && (self.min_data_rate_cents..=self.max_data_rate_cents).contains(&data_rate_cents)
+    }
+
+    /// Public inputs of `RateValidationCircuit`, in allocation order
+    pub fn public_inputs<F: PrimeField>(&self) -> Vec<F> {
+        vec![
+            F::from(self.min_call_rate_cents),
+            F::from(self.max_call_rate_cents),
+            F::from(self.min_data_rate_cents),
+            F::from(self.max_data_rate_cents),
+        ]
+    }
+}
+
+/// Rate Validation Circuit
+/// Proves that an operator's call and data rates lie within the consortium-agreed
+/// band without revealing the rates themselves
+#[derive(Clone)]
+pub struct RateValidationCircuit<F: PrimeField> {
+    // Private inputs (witness)
+    pub call_rate_cents: Option<F>,
+    pub data_rate_cents: Option<F>,
+
+    // Public inputs: the agreed band
+    pub min_call_rate_cents: Option<F>,
+    pub max_call_rate_cents: Option<F>,
+    pub min_data_rate_cents: Option<F>,
+    pub max_data_rate_cents: Option<F>,
+
+    _phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField> RateValidationCircuit<F> {
+    pub fn new(call_rate_cents: u64, data_rate_cents: u64, bounds: &RateBounds) -> Self {
+        Self {
+            call_rate_cents: Some(F::from(call_rate_cents)),
+            data_rate_cents: Some(F::from(data_rate_cents)),
+            min_call_rate_cents: Some(F::from(bounds.min_call_rate_cents)),
+            max_call_rate_cents: Some(F::from(bounds.max_call_rate_cents)),
+            min_data_rate_cents: Some(F::from(bounds.min_data_rate_cents)),
+            max_data_rate_cents: Some(F::from(bounds.max_data_rate_cents)),
+            _phantom: PhantomData,
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            call_rate_cents: None,
+            data_rate_cents: None,
+            min_call_rate_cents: None,
+            max_call_rate_cents: None,
+            min_data_rate_cents: None,
+            max_data_rate_cents: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for RateValidationCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        // Allocate private rates
+        let call_rate = FpVar::new_witness(cs.clone(), || {
+            self.call_rate_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let data_rate = FpVar::new_witness(cs.clone(), || {
+            self.data_rate_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // Allocate public bounds (order must match RateBounds::public_inputs)
+        let min_call_rate = FpVar::new_input(cs.clone(), || {
+            self.min_call_rate_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max_call_rate = FpVar::new_input(cs.clone(), || {
+            self.max_call_rate_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let min_data_rate = FpVar::new_input(cs.clone(), || {
+            self.min_data_rate_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max_data_rate = FpVar::new_input(cs.clone(), || {
+            self.max_data_rate_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // Constraint 1: min_call_rate <= call_rate <= max_call_rate
+        // enforce_cmp also bounds both sides to (p-1)/2, so a "negative" rate cannot wrap around
+        call_rate.enforce_cmp(&min_call_rate, Ordering::Greater, true)?;
+        call_rate.enforce_cmp(&max_call_rate, Ordering::Less, true)?;
+
+        // Constraint 2: min_data_rate <= data_rate <= max_data_rate
+        data_rate.enforce_cmp(&min_data_rate, Ordering::Greater, true)?;
+        data_rate.enforce_cmp(&max_data_rate, Ordering::Less, true)?;
+
+        Ok(())
+    }
+}
+
+/// Maximum number of records a single block total proof covers
+pub const BLOCK_PROOF_CAPACITY: usize = 128;
+
+/// Record charges are u32 cents
+const RECORD_CHARGE_BITS: usize = 32;
+
+/// Wide enough for a full block of maximum-size credits
+const SHORTFALL_BITS: usize = 48;
+
+/// Allocate a witness constrained to `bits` bits by boolean decomposition
+fn alloc_bounded_witness<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    value: Option<u64>,
+    bits: usize,
+) -> Result<FpVar<F>, SynthesisError> {
+    let mut recomposed = FpVar::zero();
+    let mut coefficient = F::one();
+    for i in 0..bits {
+        let bit = Boolean::new_witness(cs.clone(), || {
+            value.map(|v| (v >> i) & 1 == 1).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        recomposed += FpVar::from(bit) * coefficient;
+        coefficient.double_in_place();
+    }
+    Ok(recomposed)
+}
+
+/// Poseidon parameters for the block records commitment (rate 2, x^5 S-box)
+fn records_commitment_config<F: PrimeField>() -> PoseidonConfig<F> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(F::MODULUS_BIT_SIZE as u64, 2, 8, 57, 0);
+    PoseidonConfig::new(8, 57, 5, mds, ark, 2, 1)
+}
+
+/// Field element standing for a record id in the records commitment
+pub fn record_id_field<F: PrimeField>(record_id: &str) -> F {
+    F::from_le_bytes_mod_order(Blake2bHash::hash(record_id.as_bytes()).as_bytes())
+}
+
+/// Signed charge as a field element; credits are negated
+fn signed_charge_field<F: PrimeField>(charge_cents: i64) -> F {
+    let magnitude = F::from(charge_cents.unsigned_abs());
+    if charge_cents < 0 { -magnitude } else { magnitude }
+}
+
+/// Commitment to a block's (record id, signed charge) pairs, in block order.
+/// Slots past the last record are absorbed as zeros, as in `BlockTotalCircuit`.
+pub fn block_records_commitment<F: PrimeField + Absorb>(records: &[(&str, i64)]) -> F {
+    let mut elements = Vec::with_capacity(2 * BLOCK_PROOF_CAPACITY);
+    for i in 0..BLOCK_PROOF_CAPACITY {
+        match records.get(i) {
+            Some((record_id, charge)) => {
+                elements.push(record_id_field(record_id));
+                elements.push(signed_charge_field(*charge));
+            }
+            None => elements.extend([F::zero(), F::zero()]),
+        }
+    }
+
+    let mut sponge = PoseidonSponge::<F>::new(&records_commitment_config());
+    sponge.absorb(&elements);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+/// Public inputs of `BlockTotalCircuit`, in allocation order
+pub fn block_total_public_inputs<F: PrimeField>(
+    total_amount_cents: u64,
+    record_count: u32,
+    records_commitment: F,
+    block_hash: &[u8],
+) -> Vec<F> {
+    vec![
+        F::from(total_amount_cents),
+        F::from(record_count as u64),
+        records_commitment,
+        F::from_le_bytes_mod_order(block_hash),
+    ]
+}
+
+/// Block Total Circuit
+/// Proves that a block's public settlement total is the net of the charges of its records
+/// (credits subtracted, floored at zero). The charges stay hidden from the proof itself;
+/// it is bound to them through a Poseidon commitment over every (record id, charge) pair,
+/// which validators holding the block's records recompute.
+#[derive(Clone)]
+pub struct BlockTotalCircuit<F: PrimeField> {
+    // Private inputs (witness): record ids and signed settlement charges, negative for credits
+    pub record_ids: Option<Vec<F>>,
+    pub signed_charges_cents: Option<Vec<i64>>,
+
+    // Public inputs
+    pub total_amount_cents: Option<F>,
+    pub record_count: Option<F>,
+    pub records_commitment: Option<F>,
+    pub block_binding: Option<F>, // Block hash, so the proof cannot be replayed for another block
+
+    _phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> BlockTotalCircuit<F> {
+    pub fn new(records: &[(&str, i64)], total_amount_cents: u64, block_hash: &[u8]) -> Self {
+        let inputs = block_total_public_inputs::<F>(
+            total_amount_cents,
+            records.len() as u32,
+            block_records_commitment(records),
+            block_hash,
+        );
+        Self {
+            record_ids: Some(records.iter().map(|(record_id, _)| record_id_field(record_id)).collect()),
+            signed_charges_cents: Some(records.iter().map(|(_, charge)| *charge).collect()),
+            total_amount_cents: Some(inputs[0]),
+            record_count: Some(inputs[1]),
+            records_commitment: Some(inputs[2]),
+            block_binding: Some(inputs[3]),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> BlockTotalCircuit<F> {
+    pub fn empty() -> Self {
+        Self {
+            record_ids: None,
+            signed_charges_cents: None,
+            total_amount_cents: None,
+            record_count: None,
+            records_commitment: None,
+            block_binding: None,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for BlockTotalCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let charges = self.signed_charges_cents.as_ref();
+        let record_ids = self.record_ids.as_ref();
+        if charges.is_some_and(|c| c.len() > BLOCK_PROOF_CAPACITY)
+            || record_ids.map(Vec::len) != charges.map(Vec::len)
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let mut net_total = FpVar::zero();
+        let mut active_count = FpVar::zero();
+        let mut committed = Vec::with_capacity(2 * BLOCK_PROOF_CAPACITY);
+
+        // Every slot is allocated so the constraint system does not depend on the record count;
+        // unused slots are inactive and must carry a zero id and charge
+        for i in 0..BLOCK_PROOF_CAPACITY {
+            let charge = charges.map(|c| c.get(i).copied().unwrap_or(0));
+
+            let record_id = FpVar::new_witness(cs.clone(), || {
+                record_ids.map(|ids| ids.get(i).copied().unwrap_or_else(F::zero))
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let magnitude = alloc_bounded_witness(cs.clone(), charge.map(i64::unsigned_abs), RECORD_CHARGE_BITS)?;
+            let is_credit = Boolean::new_witness(cs.clone(), || {
+                charge.map(|c| c < 0).ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let active = Boolean::new_witness(cs.clone(), || {
+                charges.map(|c| i < c.len()).ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+            // Constraint 1: inactive slots carry no record and no charge
+            let inactive = FpVar::from(!active.clone());
+            magnitude.mul_equals(&inactive, &FpVar::zero())?;
+            record_id.mul_equals(&inactive, &FpVar::zero())?;
+
+            // Credits flow back and reduce the total
+            let signed = is_credit.select(&magnitude.negate()?, &magnitude)?;
+            net_total += &signed;
+            active_count += FpVar::from(active);
+            committed.push(record_id);
+            committed.push(signed);
+        }
+
+        // Shortfall lifts a negative net back to zero: net + shortfall = total, shortfall * total = 0
+        let shortfall = alloc_bounded_witness(
+            cs.clone(),
+            charges.map(|c| c.iter().sum::<i64>().min(0).unsigned_abs()),
+            SHORTFALL_BITS,
+        )?;
+
+        // Allocate public inputs (order must match block_total_public_inputs)
+        let total_amount = FpVar::new_input(cs.clone(), || {
+            self.total_amount_cents.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let record_count = FpVar::new_input(cs.clone(), || {
+            self.record_count.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let records_commitment = FpVar::new_input(cs.clone(), || {
+            self.records_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let block_binding = FpVar::new_input(cs.clone(), || {
+            self.block_binding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        // Constraint 2: the public total is the net of the hidden charges, floored at zero
+        (&net_total + &shortfall).enforce_equal(&total_amount)?;
+        shortfall.mul_equals(&total_amount, &FpVar::zero())?;
+
+        // Constraint 3: the public record count matches the active slots
+        active_count.enforce_equal(&record_count)?;
+
+        // Constraint 4: the charges are the ones committed to for the block's records
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &records_commitment_config());
+        sponge.absorb(&committed)?;
+        sponge.squeeze_field_elements(1)?[0].enforce_equal(&records_commitment)?;
+
+        // An input that appears in no constraint is not bound by the proof
+        let _ = block_binding.square()?;
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -637,4 +1099,169 @@ mod tests {
         assert!(cs.is_satisfied().unwrap());
         println!("✅ 5-Party Settlement Circuit: {} constraints", cs.num_constraints());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_all_zero_bilateral_amounts() {
+        let netting = compute_multilateral_netting(&[0u64; 20]);
+        assert_eq!(netting.net_positions, [0; 5]);
+        assert_eq!(netting.gross_total, 0);
+        assert_eq!(netting.total_net_amount, 0);
+        assert_eq!(netting.net_settlement_count, 0);
+        assert_eq!(netting.savings_percentage, 0);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::from_bilateral([0u64; 20], [0; 8], 54321);
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(cs.is_satisfied().unwrap());
+
+        // Claiming savings on an empty consortium must not satisfy the circuit
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::new([0u64; 20], [0; 5], 0, 0, [0; 8], 50, 54321);
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_single_active_party() {
+        // Only T-Mobile sends traffic, all of it to Vodafone
+        let mut bilateral = [0u64; 20];
+        bilateral[0] = 50000;
+
+        let netting = compute_multilateral_netting(&bilateral);
+        assert_eq!(netting.net_positions, [50000, -50000, 0, 0, 0]);
+        assert_eq!(netting.gross_total, 50000);
+        assert_eq!(netting.total_net_amount, 50000);
+        assert_eq!(netting.net_settlement_count, 1);
+        assert_eq!(netting.savings_percentage, 0); // Nothing to offset against
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::from_bilateral(bilateral, [0; 8], 54321);
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_3party_settlement_circuit() {
+        // Each party's outgoing to the other two, in party order
+        let bilateral = [
+            30000, 10000, // Party 0 -> 1, 2
+            20000, 5000,  // Party 1 -> 0, 2
+            15000, 25000, // Party 2 -> 0, 1
+        ];
+
+        let netting = compute_n_party_netting(3, &bilateral).unwrap();
+        assert_eq!(netting.net_positions, vec![5000, -30000, 25000]);
+        assert_eq!(netting.net_settlement_count, 2);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::from_bilateral_n_party(3, &bilateral, [0; 8], 54321).unwrap();
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(cs.is_satisfied().unwrap());
+
+        // Shifting value between two parties breaks the per-party net calculation
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::new_n_party(
+            3, &bilateral, &[6000, -31000, 25000], 2, 30000, [0; 8], netting.savings_percentage, 54321,
+        );
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_6party_settlement_circuit() {
+        let bilateral: Vec<u64> = (0..bilateral_amount_count(6) as u64)
+            .map(|index| 10000 + index * 1500)
+            .collect();
+
+        let netting = compute_n_party_netting(6, &bilateral).unwrap();
+        assert_eq!(netting.net_positions.len(), 6);
+        assert_eq!(netting.net_positions.iter().sum::<i64>(), 0);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::from_bilateral_n_party(6, &bilateral, [0; 8], 54321).unwrap();
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(cs.is_satisfied().unwrap());
+
+        // Amounts shaped for a different party count cannot be synthesized
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let circuit = SettlementCalculationCircuit::<Fr>::new_n_party(
+            6, &bilateral[..20], &netting.net_positions, netting.net_settlement_count,
+            netting.total_net_amount, [0; 8], netting.savings_percentage, 54321,
+        );
+        assert!(circuit.generate_constraints(cs).is_err());
+
+        // Netting refuses amounts shaped for a different party count
+        assert_eq!(
+            compute_n_party_netting(6, &bilateral[..20]).unwrap_err(),
+            NettingShapeError { parties: 6, expected: 30, actual: 20 }
+        );
+    }
+
+    #[test]
+    fn test_balance_conservation_circuit() {
+        let balances = HashMap::from([
+            ("T-Mobile-DE".to_string(), -75_000i64),
+            ("Vodafone-UK".to_string(), 50_000),
+            ("Orange-FR".to_string(), 25_000),
+        ]);
+        let block_hash = [3u8; 32];
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        BalanceConservationCircuit::<Fr>::new(&balances, &block_hash)
+            .generate_constraints(cs.clone())
+            .expect("Circuit should synthesize");
+        assert!(cs.is_satisfied().unwrap());
+
+        // Crediting an operator without a matching debit breaks conservation
+        let mut tampered = balances.clone();
+        *tampered.get_mut("Vodafone-UK").unwrap() += 1;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        BalanceConservationCircuit::<Fr>::new(&tampered, &block_hash)
+            .generate_constraints(cs.clone())
+            .expect("Circuit should synthesize");
+        assert!(!cs.is_satisfied().unwrap());
+
+        // Conserving balances that differ from the committed ones are refused too
+        let mut circuit = BalanceConservationCircuit::<Fr>::new(&balances, &block_hash);
+        circuit.balances_cents = Some(vec![25_000, -74_000, 49_000]);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    const TEST_RATE_BOUNDS: RateBounds = RateBounds {
+        min_call_rate_cents: 5,
+        max_call_rate_cents: 50,
+        min_data_rate_cents: 1,
+        max_data_rate_cents: 20,
+    };
+
+    #[test]
+    fn test_rate_validation_circuit_in_band() {
+        // Rates strictly inside and exactly on the band edges are both accepted
+        for (call_rate, data_rate) in [(15, 5), (5, 1), (50, 20)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let circuit = RateValidationCircuit::<Fr>::new(call_rate, data_rate, &TEST_RATE_BOUNDS);
+            circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+            assert!(cs.is_satisfied().unwrap(), "rates {}/{} should be in band", call_rate, data_rate);
+        }
+    }
+
+    #[test]
+    fn test_rate_validation_circuit_out_of_band() {
+        for (call_rate, data_rate) in [(51, 5), (4, 5), (15, 21), (15, 0)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let circuit = RateValidationCircuit::<Fr>::new(call_rate, data_rate, &TEST_RATE_BOUNDS);
+            circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+            assert!(!cs.is_satisfied().unwrap(), "rates {}/{} should be out of band", call_rate, data_rate);
+            assert!(!TEST_RATE_BOUNDS.contains(call_rate, data_rate));
+        }
+
+        // A "negative" rate wrapping around the field must not slip under the maximum
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let mut circuit = RateValidationCircuit::<Fr>::new(15, 5, &TEST_RATE_BOUNDS);
+        circuit.call_rate_cents = Some(-Fr::from(1u64));
+        circuit.generate_constraints(cs.clone()).expect("Circuit should synthesize");
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
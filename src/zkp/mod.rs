@@ -1,6 +1,7 @@
 pub mod settlement_proofs;
 pub mod circuit;
 pub mod trusted_setup;
+pub mod powers_of_tau;
 pub mod circuits;
 pub mod smart_contracts;
 
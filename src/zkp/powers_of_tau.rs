@@ -0,0 +1,319 @@
+// Phase-1 (powers of tau) parameters shared by every circuit of the SP consortium
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{One, Zero};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_std::UniformRand;
+
+use crate::hash::Blake2bHash;
+use super::trusted_setup::TrustedSetupError;
+
+/// QAP domain the consortium's phase-1 must cover; the block total circuit is the largest
+pub const CONSORTIUM_PHASE1_DOMAIN_SIZE: usize = 1 << 16;
+
+/// Phase-1 accumulator of a powers-of-tau ceremony for QAP domains of up to n points:
+/// [τⁱ]₁ for i < 2n-1, [τⁱ]₂ for i < n, [ατⁱ]₁ and [βτⁱ]₁ for i < n, and [β]₂.
+/// Nobody knows τ, α or β as long as one contributor discarded its secrets.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PowersOfTau {
+    pub tau_g1: Vec<G1Affine>,
+    pub tau_g2: Vec<G2Affine>,
+    pub alpha_tau_g1: Vec<G1Affine>,
+    pub beta_tau_g1: Vec<G1Affine>,
+    pub beta_g2: G2Affine,
+}
+
+/// Public keys [τ']₂, [α']₂ and [β']₂ of one contributor's secrets, so anyone can check
+/// the accumulator moved by exactly those factors
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct TauContribution {
+    pub tau_g2: G2Affine,
+    pub alpha_g2: G2Affine,
+    pub beta_g2: G2Affine,
+}
+
+impl PowersOfTau {
+    /// Starting accumulator (τ = α = β = 1) for domains of up to `max_domain_size` points,
+    /// rounded up to a power of two. It hides nothing until contributions are applied.
+    pub fn new(max_domain_size: usize) -> Self {
+        let n = max_domain_size.max(2).next_power_of_two();
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        Self {
+            tau_g1: vec![g1; 2 * n - 1],
+            tau_g2: vec![g2; n],
+            alpha_tau_g1: vec![g1; n],
+            beta_tau_g1: vec![g1; n],
+            beta_g2: g2,
+        }
+    }
+
+    /// Largest QAP domain the accumulator can derive circuit keys for
+    pub fn max_domain_size(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    /// Multiply τ, α and β by fresh secrets and return their public keys.
+    /// The secrets are dropped on return.
+    pub fn contribute<R: RngCore + CryptoRng>(&mut self, rng: &mut R) -> TauContribution {
+        let tau = nonzero_scalar(rng);
+        let alpha = nonzero_scalar(rng);
+        let beta = nonzero_scalar(rng);
+
+        let mut tau_powers = Vec::with_capacity(self.tau_g1.len());
+        let mut power = Fr::one();
+        for _ in 0..self.tau_g1.len() {
+            tau_powers.push(power);
+            power *= tau;
+        }
+        let n = self.tau_g2.len();
+
+        self.tau_g1 = scale::<G1Projective>(&self.tau_g1, tau_powers.iter().copied());
+        self.tau_g2 = scale::<G2Projective>(&self.tau_g2, tau_powers[..n].iter().copied());
+        self.alpha_tau_g1 = scale::<G1Projective>(&self.alpha_tau_g1, tau_powers[..n].iter().map(|p| *p * alpha));
+        self.beta_tau_g1 = scale::<G1Projective>(&self.beta_tau_g1, tau_powers[..n].iter().map(|p| *p * beta));
+        self.beta_g2 = (self.beta_g2 * beta).into_affine();
+
+        let g2 = G2Affine::generator();
+        TauContribution {
+            tau_g2: (g2 * tau).into_affine(),
+            alpha_g2: (g2 * alpha).into_affine(),
+            beta_g2: (g2 * beta).into_affine(),
+        }
+    }
+
+    /// Check `next` is `previous` with τ, α and β multiplied by the secrets behind
+    /// `contribution`, and is itself a well-formed accumulator
+    pub fn verify_contribution<R: RngCore>(
+        previous: &PowersOfTau,
+        next: &PowersOfTau,
+        contribution: &TauContribution,
+        rng: &mut R,
+    ) -> bool {
+        let g2 = G2Affine::generator();
+        previous.tau_g2.len() == next.tau_g2.len()
+            && !contribution.tau_g2.is_zero()
+            && Bn254::pairing(next.tau_g1[1], g2) == Bn254::pairing(previous.tau_g1[1], contribution.tau_g2)
+            && Bn254::pairing(next.alpha_tau_g1[0], g2) == Bn254::pairing(previous.alpha_tau_g1[0], contribution.alpha_g2)
+            && Bn254::pairing(next.beta_tau_g1[0], g2) == Bn254::pairing(previous.beta_tau_g1[0], contribution.beta_g2)
+            && next.is_well_formed(rng)
+    }
+
+    /// Check every vector holds consecutive powers of the same τ, with α and β applied
+    /// consistently. Each sequence is checked on a random linear combination.
+    pub fn is_well_formed<R: RngCore>(&self, rng: &mut R) -> bool {
+        let n = self.tau_g2.len();
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+
+        let shaped = n >= 2
+            && n.is_power_of_two()
+            && self.tau_g1.len() == 2 * n - 1
+            && self.alpha_tau_g1.len() == n
+            && self.beta_tau_g1.len() == n;
+        if !shaped || self.tau_g1[0] != g1 || self.tau_g2[0] != g2 {
+            return false;
+        }
+        if self.tau_g1[1].is_zero() || self.alpha_tau_g1[0].is_zero() || self.beta_tau_g1[0].is_zero() {
+            return false;
+        }
+
+        let tau_g2 = self.tau_g2[1];
+        [&self.tau_g1, &self.alpha_tau_g1, &self.beta_tau_g1].into_iter().all(|powers| {
+            let (current, next) = consecutive_combinations::<G1Projective, R>(powers, rng);
+            Bn254::pairing(next, g2) == Bn254::pairing(current, tau_g2)
+        }) && {
+            let (current, next) = consecutive_combinations::<G2Projective, R>(&self.tau_g2, rng);
+            Bn254::pairing(g1, next) == Bn254::pairing(self.tau_g1[1], current)
+        } && Bn254::pairing(self.beta_tau_g1[0], g2) == Bn254::pairing(g1, self.beta_g2)
+    }
+
+    /// Hash identifying the accumulator, recorded in ceremony transcripts
+    pub fn hash(&self) -> Result<Blake2bHash, TrustedSetupError> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .map_err(|e| TrustedSetupError::Serialization(format!("Powers of tau serialization error: {}", e)))?;
+        Ok(Blake2bHash::hash(&bytes))
+    }
+
+    /// Phase-2 starting keys for `circuit`, with δ = γ = 1, laid out as ark-groth16's
+    /// generator lays them out for the libsnark QAP reduction. δ contributions then make
+    /// the keys circuit-specific.
+    pub fn circuit_keys<C: ConstraintSynthesizer<Fr>>(&self, circuit: C) -> Result<ProvingKey<Bn254>, TrustedSetupError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())
+            .map_err(|e| TrustedSetupError::Ceremony(format!("Circuit synthesis failed: {}", e)))?;
+        cs.finalize();
+
+        let matrices = cs.to_matrices()
+            .ok_or_else(|| TrustedSetupError::Ceremony("Circuit has no constraint matrices".to_string()))?;
+        let num_constraints = cs.num_constraints();
+        let num_instance = cs.num_instance_variables();
+        let num_variables = num_instance + cs.num_witness_variables();
+
+        let domain = GeneralEvaluationDomain::<Fr>::new(num_constraints + num_instance)
+            .ok_or_else(|| TrustedSetupError::Ceremony("Circuit too large for an evaluation domain".to_string()))?;
+        let n = domain.size();
+        if n > self.max_domain_size() {
+            return Err(TrustedSetupError::Ceremony(format!(
+                "Circuit needs a domain of {} points, powers of tau cover {}", n, self.max_domain_size()
+            )));
+        }
+
+        // Lagrange basis at τ in the exponent: the inverse FFT of the first n powers
+        let lagrange_g1 = lagrange_basis::<G1Projective>(&domain, &self.tau_g1[..n]);
+        let lagrange_g2 = lagrange_basis::<G2Projective>(&domain, &self.tau_g2[..n]);
+        let alpha_lagrange = lagrange_basis::<G1Projective>(&domain, &self.alpha_tau_g1[..n]);
+        let beta_lagrange = lagrange_basis::<G1Projective>(&domain, &self.beta_tau_g1[..n]);
+
+        // uⱼ(τ), vⱼ(τ) and βuⱼ(τ) + αvⱼ(τ) + wⱼ(τ) per variable
+        let mut a = vec![G1Projective::zero(); num_variables];
+        let mut b_g1 = vec![G1Projective::zero(); num_variables];
+        let mut b_g2 = vec![G2Projective::zero(); num_variables];
+        let mut combined = vec![G1Projective::zero(); num_variables];
+
+        // Instance variables are also bound by one extra row each after the constraints
+        for j in 0..num_instance {
+            a[j] += lagrange_g1[num_constraints + j];
+            combined[j] += beta_lagrange[num_constraints + j];
+        }
+        for i in 0..num_constraints {
+            for &(coeff, index) in &matrices.a[i] {
+                a[index] += lagrange_g1[i] * coeff;
+                combined[index] += beta_lagrange[i] * coeff;
+            }
+            for &(coeff, index) in &matrices.b[i] {
+                b_g1[index] += lagrange_g1[i] * coeff;
+                b_g2[index] += lagrange_g2[i] * coeff;
+                combined[index] += alpha_lagrange[i] * coeff;
+            }
+            for &(coeff, index) in &matrices.c[i] {
+                combined[index] += lagrange_g1[i] * coeff;
+            }
+        }
+
+        // τⁱ·Z(τ) = τⁿ⁺ⁱ - τⁱ for the vanishing polynomial Z(x) = xⁿ - 1
+        let h_query: Vec<G1Projective> = (0..n - 1)
+            .map(|i| self.tau_g1[n + i].into_group() - self.tau_g1[i])
+            .collect();
+
+        let combined = G1Projective::normalize_batch(&combined);
+        let vk = VerifyingKey::<Bn254> {
+            alpha_g1: self.alpha_tau_g1[0],
+            beta_g2: self.beta_g2,
+            gamma_g2: G2Affine::generator(),
+            delta_g2: G2Affine::generator(),
+            gamma_abc_g1: combined[..num_instance].to_vec(),
+        };
+
+        Ok(ProvingKey {
+            vk,
+            beta_g1: self.beta_tau_g1[0],
+            delta_g1: G1Affine::generator(),
+            a_query: G1Projective::normalize_batch(&a),
+            b_g1_query: G1Projective::normalize_batch(&b_g1),
+            b_g2_query: G2Projective::normalize_batch(&b_g2),
+            h_query: G1Projective::normalize_batch(&h_query),
+            l_query: combined[num_instance..].to_vec(),
+        })
+    }
+}
+
+fn nonzero_scalar<R: RngCore>(rng: &mut R) -> Fr {
+    loop {
+        let scalar = Fr::rand(rng);
+        if !scalar.is_zero() {
+            return scalar;
+        }
+    }
+}
+
+/// Multiply each point by the matching scalar
+fn scale<G: CurveGroup<ScalarField = Fr>>(points: &[G::Affine], scalars: impl Iterator<Item = Fr>) -> Vec<G::Affine> {
+    let scaled: Vec<G> = points.iter().zip(scalars).map(|(point, scalar)| *point * scalar).collect();
+    G::normalize_batch(&scaled)
+}
+
+/// Random combinations Σrᵢpᵢ and Σrᵢpᵢ₊₁, equal up to a factor τ if the points are powers of τ
+fn consecutive_combinations<G: CurveGroup<ScalarField = Fr>, R: RngCore>(points: &[G::Affine], rng: &mut R) -> (G, G) {
+    let scalars: Vec<Fr> = (0..points.len() - 1).map(|_| Fr::rand(rng)).collect();
+    (
+        G::msm_unchecked(&points[..points.len() - 1], &scalars),
+        G::msm_unchecked(&points[1..], &scalars),
+    )
+}
+
+/// [Lᵢ(τ)] for every point of `domain`, from [τʲ] for j < n
+fn lagrange_basis<G: CurveGroup<ScalarField = Fr>>(domain: &GeneralEvaluationDomain<Fr>, powers: &[G::Affine]) -> Vec<G> {
+    let mut points: Vec<G> = powers.iter().map(|p| p.into_group()).collect();
+    domain.ifft_in_place(&mut points);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_groth16::Groth16;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_snark::SNARK;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use super::super::circuits::SettlementCalculationCircuit;
+
+    #[test]
+    fn test_contributions_chain_and_verify() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut accumulator = PowersOfTau::new(8);
+        assert_eq!(accumulator.max_domain_size(), 8);
+
+        for _ in 0..3 {
+            let previous = accumulator.clone();
+            let contribution = accumulator.contribute(&mut rng);
+            assert!(PowersOfTau::verify_contribution(&previous, &accumulator, &contribution, &mut rng));
+
+            // Claiming someone else's contribution does not verify
+            let mut other = previous.clone();
+            let other_contribution = other.contribute(&mut rng);
+            assert!(!PowersOfTau::verify_contribution(&previous, &accumulator, &other_contribution, &mut rng));
+        }
+
+        // Breaking a single power is caught
+        let mut broken = accumulator.clone();
+        broken.tau_g1[5] = (broken.tau_g1[5] * Fr::from(2u64)).into_affine();
+        assert!(!broken.is_well_formed(&mut rng));
+    }
+
+    #[test]
+    fn test_derived_circuit_keys_prove_and_verify() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut accumulator = PowersOfTau::new(256);
+        accumulator.contribute(&mut rng);
+        accumulator.contribute(&mut rng);
+
+        let pk = accumulator.circuit_keys(SettlementCalculationCircuit::<Fr>::empty()).unwrap();
+
+        let mut bilateral = [0u64; 20];
+        bilateral[0] = 50_000;
+        bilateral[4] = 20_000;
+        let circuit = SettlementCalculationCircuit::<Fr>::from_bilateral(bilateral, [1; 8], 54321);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone()).unwrap();
+        let public_inputs = cs.borrow().unwrap().instance_assignment[1..].to_vec();
+
+        let proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+        assert!(Groth16::<Bn254>::verify(&pk.vk, &public_inputs, &proof).unwrap());
+
+        let mut wrong_inputs = public_inputs.clone();
+        wrong_inputs[0] += Fr::one();
+        assert!(!Groth16::<Bn254>::verify(&pk.vk, &wrong_inputs, &proof).unwrap());
+
+        // A domain larger than the accumulator covers is refused
+        assert!(PowersOfTau::new(64).circuit_keys(SettlementCalculationCircuit::<Fr>::empty()).is_err());
+    }
+}
@@ -3,10 +3,17 @@ use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
 use ark_snark::SNARK;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::hash::Blake2bHash;
 use crate::zkp::circuit::{SettlementCircuit, SettlementWitness};
+use crate::zkp::circuits::{
+    balance_conservation_public_inputs, block_records_commitment, block_total_public_inputs, operator_balances_commitment,
+    BalanceConservationCircuit, BlockTotalCircuit, RateBounds, RateValidationCircuit, BLOCK_PROOF_CAPACITY,
+    CONSERVATION_PROOF_CAPACITY,
+};
 
 /// Metrics for ZKP operations
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -26,13 +33,80 @@ pub struct ZkpMetrics {
     pub system_start_time: u64,
     pub last_proof_generated: Option<u64>,
     pub last_proof_verified: Option<u64>,
+    #[serde(default)]
+    pub proof_cache_hits: u64,
+    #[serde(default)]
+    pub proof_cache_misses: u64,
+}
+
+/// Default number of settlement proofs kept for reuse
+pub const DEFAULT_PROOF_CACHE_CAPACITY: usize = 1024;
+
+/// Most proofs accepted by one `batch_verify` call
+pub const MAX_BATCH_VERIFY_PROOFS: usize = 100;
+
+/// Least-recently-used cache of proofs keyed by a hash of their inputs, such as
+/// `ProofParameters::cache_key`
+pub struct ProofCache<P = SettlementProof> {
+    capacity: usize,
+    proofs: HashMap<Blake2bHash, P>,
+    // Front = least recently used
+    order: VecDeque<Blake2bHash>,
+}
+
+impl<P: Clone> ProofCache<P> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            proofs: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&mut self, key: &Blake2bHash) -> Option<P> {
+        let proof = self.proofs.get(key)?.clone();
+        self.order.retain(|k| k != key);
+        self.order.push_back(*key);
+        Some(proof)
+    }
+
+    pub fn insert(&mut self, key: Blake2bHash, proof: P) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.proofs.insert(key, proof).is_some() {
+            self.order.retain(|k| k != &key);
+        }
+        self.order.push_back(key);
+        self.evict();
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.proofs.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.proofs.remove(&evicted);
+            }
+        }
+    }
 }
 
 /// Zero-Knowledge Proof system for privacy-preserving settlement validation
 pub struct SettlementProofSystem {
     proving_key: ProvingKey<Bn254>,
     verifying_key: VerifyingKey<Bn254>,
+    rate_proving_key: ProvingKey<Bn254>,
+    rate_verifying_key: VerifyingKey<Bn254>,
+    block_proving_key: ProvingKey<Bn254>,
+    block_verifying_key: VerifyingKey<Bn254>,
+    conservation_proving_key: ProvingKey<Bn254>,
+    conservation_verifying_key: VerifyingKey<Bn254>,
     metrics: Arc<Mutex<ZkpMetrics>>,
+    proof_cache: Mutex<ProofCache>,
 }
 
 /// ZK Proof for settlement transactions
@@ -42,6 +116,60 @@ pub struct SettlementProof {
     pub public_inputs: Vec<String>, // Serialized field elements
 }
 
+/// Marks serialized proofs that carry a format header
+const PROOF_MAGIC: [u8; 2] = *b"ZP";
+/// Bump whenever the circuits or the proof encoding change incompatibly
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+/// Curve identifier written into the proof header
+pub const PROOF_CURVE_BN254: u8 = 1;
+/// Magic, format version and curve identifier in front of the compressed proof
+pub const PROOF_HEADER_LEN: usize = 4;
+/// Size of a compressed BN254 Groth16 proof, as stored before proofs carried a header
+const UNVERSIONED_PROOF_LEN: usize = 128;
+
+/// Serialize a proof behind the current format header
+pub fn encode_proof(proof: &Proof<Bn254>) -> Result<Vec<u8>, ZkpError> {
+    let mut proof_bytes = Vec::with_capacity(PROOF_HEADER_LEN + UNVERSIONED_PROOF_LEN);
+    proof_bytes.extend_from_slice(&PROOF_MAGIC);
+    proof_bytes.push(PROOF_FORMAT_VERSION);
+    proof_bytes.push(PROOF_CURVE_BN254);
+    proof.serialize_compressed(&mut proof_bytes)
+        .map_err(|e| ZkpError::SerializationFailed(format!("Proof serialization failed: {:?}", e)))?;
+    Ok(proof_bytes)
+}
+
+/// Deserialize a proof, rejecting other format versions or curves up front instead of
+/// failing somewhere inside deserialization. Proofs written before the header existed
+/// are still read.
+pub fn decode_proof(proof_bytes: &[u8]) -> Result<Proof<Bn254>, ZkpError> {
+    let body = match proof_bytes {
+        [m0, m1, version, curve, body @ ..] if [*m0, *m1] == PROOF_MAGIC && proof_bytes.len() != UNVERSIONED_PROOF_LEN => {
+            if *version != PROOF_FORMAT_VERSION || *curve != PROOF_CURVE_BN254 {
+                return Err(ZkpError::VersionMismatch(format!(
+                    "proof has format version {} for curve {}, this node reads version {} for curve {}",
+                    version, curve, PROOF_FORMAT_VERSION, PROOF_CURVE_BN254
+                )));
+            }
+            body
+        }
+        _ if proof_bytes.len() == UNVERSIONED_PROOF_LEN => proof_bytes,
+        _ => {
+            return Err(ZkpError::VersionMismatch(format!(
+                "unrecognized proof format ({} bytes without a format header)", proof_bytes.len()
+            )));
+        }
+    };
+
+    Proof::<Bn254>::deserialize_compressed(body)
+        .map_err(|e| ZkpError::DeserializationFailed(format!("Proof deserialization failed: {:?}", e)))
+}
+
+/// Rewrite a proof stored before proofs were versioned in the current format.
+/// Proofs already in the current format are returned unchanged.
+pub fn migrate_unversioned_proof(proof_bytes: &[u8]) -> Result<Vec<u8>, ZkpError> {
+    encode_proof(&decode_proof(proof_bytes)?)
+}
+
 /// Parameters for proof generation
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ProofParameters {
@@ -52,6 +180,38 @@ pub struct ProofParameters {
     pub private_rates: Vec<u64>,   // Hidden rate information
 }
 
+impl ProofParameters {
+    /// The public operator count must match the private amounts it describes,
+    /// otherwise the proof would commit to an inconsistent value
+    pub fn validate(&self) -> Result<(), ZkpError> {
+        if self.operator_count as usize != self.private_amounts.len() {
+            return Err(ZkpError::InvalidParameters(format!(
+                "operator_count {} does not match {} private amounts",
+                self.operator_count,
+                self.private_amounts.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Hash of every field the proof commits to, so two parameter sets share a
+    /// cache entry only if they would be proven identically
+    pub fn cache_key(&self) -> Blake2bHash {
+        let mut hasher = crate::hash::Blake2bHasher::new();
+        hasher.update(&self.total_amount_cents.to_le_bytes());
+        hasher.update(&self.operator_count.to_le_bytes());
+        hasher.update(&self.settlement_hash);
+        // Length prefixes keep amounts and rates from shifting into each other
+        for values in [&self.private_amounts, &self.private_rates] {
+            hasher.update(&(values.len() as u64).to_le_bytes());
+            for value in values {
+                hasher.update(&value.to_le_bytes());
+            }
+        }
+        hasher.finalize()
+    }
+}
+
 impl SettlementProofSystem {
     /// Initialize the proof system with trusted setup keys from disk
     pub fn new(provider_name: &str) -> Result<Self, ZkpError> {
@@ -76,6 +236,10 @@ impl SettlementProofSystem {
 
         println!("✅ ZKP trusted setup loaded from {} keys", provider_name);
 
+        let (rate_pk, rate_vk) = load_rate_keys(keys_dir)?;
+        let (block_pk, block_vk) = load_block_keys(keys_dir)?;
+        let (conservation_pk, conservation_vk) = load_conservation_keys(keys_dir)?;
+
         let mut initial_metrics = ZkpMetrics::default();
         initial_metrics.system_start_time = chrono::Utc::now().timestamp() as u64;
         initial_metrics.min_proof_generation_time_ms = u64::MAX;
@@ -84,12 +248,44 @@ impl SettlementProofSystem {
         Ok(Self {
             proving_key: pk,
             verifying_key: vk,
+            rate_proving_key: rate_pk,
+            rate_verifying_key: rate_vk,
+            block_proving_key: block_pk,
+            block_verifying_key: block_vk,
+            conservation_proving_key: conservation_pk,
+            conservation_verifying_key: conservation_vk,
             metrics: Arc::new(Mutex::new(initial_metrics)),
+            proof_cache: Mutex::new(ProofCache::new(DEFAULT_PROOF_CACHE_CAPACITY)),
         })
     }
 
-    /// Generate a privacy-preserving proof for a settlement
+    /// Change how many settlement proofs are kept for reuse; 0 disables the cache
+    pub fn set_proof_cache_capacity(&self, capacity: usize) {
+        self.proof_cache.lock().unwrap().set_capacity(capacity);
+    }
+
+    /// Generate a privacy-preserving proof for a settlement. A proof generated earlier
+    /// for identical parameters is returned instead of proving again.
     pub fn generate_proof(&self, params: ProofParameters) -> Result<SettlementProof, ZkpError> {
+        if let Err(e) = params.validate() {
+            self.metrics.lock().unwrap().proofs_failed_generation += 1;
+            return Err(e);
+        }
+
+        let cache_key = params.cache_key();
+        if let Some(proof) = self.proof_cache.lock().unwrap().get(&cache_key) {
+            self.metrics.lock().unwrap().proof_cache_hits += 1;
+            println!("♻️  Reusing cached ZK proof for settlement");
+            return Ok(proof);
+        }
+        self.metrics.lock().unwrap().proof_cache_misses += 1;
+
+        let proof = self.prove_settlement(params)?;
+        self.proof_cache.lock().unwrap().insert(cache_key, proof.clone());
+        Ok(proof)
+    }
+
+    fn prove_settlement(&self, params: ProofParameters) -> Result<SettlementProof, ZkpError> {
         let start_time = Instant::now();
         println!("🛡️  Generating ZK proof for settlement...");
 
@@ -130,11 +326,13 @@ impl SettlementProofSystem {
         match proof_result {
             Ok(proof) => {
                 // Serialize proof
-                let mut proof_bytes = Vec::new();
-                if let Err(e) = proof.serialize_compressed(&mut proof_bytes) {
-                    metrics.proofs_failed_generation += 1;
-                    return Err(ZkpError::SerializationFailed(format!("Proof serialization failed: {:?}", e)));
-                }
+                let proof_bytes = match encode_proof(&proof) {
+                    Ok(proof_bytes) => proof_bytes,
+                    Err(e) => {
+                        metrics.proofs_failed_generation += 1;
+                        return Err(e);
+                    }
+                };
 
                 // Serialize public inputs
                 let public_inputs_str: Vec<String> = public_inputs
@@ -178,8 +376,7 @@ impl SettlementProofSystem {
         println!("🔍 Verifying ZK settlement proof...");
 
         // Deserialize proof
-        let proof = Proof::<Bn254>::deserialize_compressed(&settlement_proof.proof_bytes[..])
-            .map_err(|e| ZkpError::DeserializationFailed(format!("Proof deserialization failed: {:?}", e)))?;
+        let proof = decode_proof(&settlement_proof.proof_bytes)?;
 
         // Deserialize public inputs
         let public_inputs: Result<Vec<Fr>, _> = settlement_proof
@@ -255,16 +452,30 @@ impl SettlementProofSystem {
         self.generate_proof(params)
     }
 
-    /// Batch verify multiple settlement proofs
+    /// Batch verify multiple settlement proofs. A proof that cannot even be decoded
+    /// counts as invalid rather than failing the whole batch.
     pub fn batch_verify(&self, proofs: &[SettlementProof]) -> Result<Vec<bool>, ZkpError> {
+        if proofs.len() > MAX_BATCH_VERIFY_PROOFS {
+            return Err(ZkpError::InvalidParameters(format!(
+                "{} proofs in batch, at most {} allowed",
+                proofs.len(),
+                MAX_BATCH_VERIFY_PROOFS
+            )));
+        }
         println!("🔍 Batch verifying {} ZK proofs...", proofs.len());
 
-        let results: Result<Vec<bool>, _> = proofs
+        let results: Vec<bool> = proofs
             .iter()
-            .map(|proof| self.verify_proof(proof))
+            .enumerate()
+            .map(|(index, proof)| match self.verify_proof(proof) {
+                Ok(is_valid) => is_valid,
+                Err(e) => {
+                    println!("❌ Proof #{} in batch could not be verified: {}", index, e);
+                    false
+                }
+            })
             .collect();
 
-        let results = results?;
         let valid_count = results.iter().filter(|&&valid| valid).count();
 
         println!("✅ Batch verification complete: {}/{} proofs valid", valid_count, proofs.len());
@@ -290,6 +501,9 @@ impl SettlementProofSystem {
         let rng = &mut ark_std::rand::thread_rng();
         let (pk, _) = Groth16::<Bn254>::circuit_specific_setup(dummy_circuit, rng)
             .map_err(|e| ZkpError::SetupFailed(format!("Dummy setup failed: {:?}", e)))?;
+        let (rate_pk, rate_vk) = setup_rate_keys()?;
+        let (block_pk, block_vk) = setup_block_keys()?;
+        let (conservation_pk, conservation_vk) = setup_conservation_keys()?;
 
         let mut initial_metrics = ZkpMetrics::default();
         initial_metrics.system_start_time = chrono::Utc::now().timestamp() as u64;
@@ -299,10 +513,306 @@ impl SettlementProofSystem {
         Ok(Self {
             proving_key: pk,
             verifying_key: vk,
+            rate_proving_key: rate_pk,
+            rate_verifying_key: rate_vk,
+            block_proving_key: block_pk,
+            block_verifying_key: block_vk,
+            conservation_proving_key: conservation_pk,
+            conservation_verifying_key: conservation_vk,
             metrics: Arc::new(Mutex::new(initial_metrics)),
+            proof_cache: Mutex::new(ProofCache::new(DEFAULT_PROOF_CACHE_CAPACITY)),
+        })
+    }
+
+    /// Prove that call and data rates lie within the agreed band without revealing them
+    pub fn prove_rate_validation(
+        &self,
+        call_rate_cents: u64,
+        data_rate_cents: u64,
+        bounds: &RateBounds,
+    ) -> Result<SettlementProof, ZkpError> {
+        let start_time = Instant::now();
+        println!("🛡️  Generating ZK rate validation proof...");
+
+        // Groth16 happily proves an unsatisfied circuit, so reject out-of-band rates up front
+        if !bounds.contains(call_rate_cents, data_rate_cents) {
+            self.metrics.lock().unwrap().proofs_failed_generation += 1;
+            return Err(ZkpError::InvalidParameters("rates outside the agreed band".to_string()));
+        }
+
+        let circuit = RateValidationCircuit::<Fr>::new(call_rate_cents, data_rate_cents, bounds);
+        let rng = &mut ark_std::rand::thread_rng();
+        let proof_result = Groth16::<Bn254>::prove(&self.rate_proving_key, circuit, rng);
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metrics = self.metrics.lock().unwrap();
+        let proof = match proof_result {
+            Ok(proof) => proof,
+            Err(e) => {
+                metrics.proofs_failed_generation += 1;
+                return Err(ZkpError::ProofGenFailed(format!("Rate proof generation failed: {:?}", e)));
+            }
+        };
+
+        let proof_bytes = encode_proof(&proof)?;
+
+        let public_inputs = bounds.public_inputs::<Fr>()
+            .iter()
+            .map(|input| {
+                let mut bytes = Vec::new();
+                input.serialize_compressed(&mut bytes)
+                    .map_err(|e| ZkpError::SerializationFailed(format!("Public input serialization failed: {:?}", e)))?;
+                Ok(hex::encode(bytes))
+            })
+            .collect::<Result<Vec<String>, ZkpError>>()?;
+
+        metrics.proofs_generated += 1;
+        metrics.total_proof_generation_time_ms += duration_ms;
+        metrics.avg_proof_generation_time_ms =
+            metrics.total_proof_generation_time_ms as f64 / metrics.proofs_generated as f64;
+        metrics.max_proof_generation_time_ms = metrics.max_proof_generation_time_ms.max(duration_ms);
+        if metrics.min_proof_generation_time_ms == u64::MAX || duration_ms < metrics.min_proof_generation_time_ms {
+            metrics.min_proof_generation_time_ms = duration_ms;
+        }
+        metrics.last_proof_generated = Some(chrono::Utc::now().timestamp() as u64);
+
+        println!("✅ ZK rate validation proof generated ({} bytes, {}ms)", proof_bytes.len(), duration_ms);
+
+        Ok(SettlementProof {
+            proof_bytes,
+            public_inputs,
+        })
+    }
+
+    /// Verify a rate validation proof against the band the verifier agreed to.
+    /// The proof's own public inputs are ignored so a prover cannot pick a wider band.
+    pub fn verify_rate_validation(&self, rate_proof: &SettlementProof, bounds: &RateBounds) -> Result<bool, ZkpError> {
+        let proof = decode_proof(&rate_proof.proof_bytes)?;
+
+        let verify_result = Groth16::<Bn254>::verify(&self.rate_verifying_key, &bounds.public_inputs::<Fr>(), &proof);
+
+        let mut metrics = self.metrics.lock().unwrap();
+        match verify_result {
+            Ok(is_valid) => {
+                metrics.proofs_verified += 1;
+                metrics.last_proof_verified = Some(chrono::Utc::now().timestamp() as u64);
+                Ok(is_valid)
+            }
+            Err(e) => {
+                metrics.proofs_failed_verification += 1;
+                Err(ZkpError::VerificationFailed(format!("Rate proof verification failed: {:?}", e)))
+            }
+        }
+    }
+
+    /// Prove that a block's public total is the net of its records' signed charges, given
+    /// as (record id, charge) pairs in block order. The proof is bound to the block hash,
+    /// the record count and a commitment to every pair.
+    pub fn prove_block_total(
+        &self,
+        records: &[(&str, i64)],
+        total_amount_cents: u64,
+        block_hash: &[u8],
+    ) -> Result<SettlementProof, ZkpError> {
+        let start_time = Instant::now();
+        println!("🛡️  Generating ZK block total proof for {} records...", records.len());
+
+        // Groth16 happily proves an unsatisfied circuit, so reject inconsistent totals up front
+        let net_total = records.iter().map(|(_, charge)| charge).sum::<i64>().max(0) as u64;
+        let invalid = if records.len() > BLOCK_PROOF_CAPACITY {
+            Some(format!("{} records exceed block proof capacity {}", records.len(), BLOCK_PROOF_CAPACITY))
+        } else if records.iter().any(|(_, charge)| charge.unsigned_abs() > u32::MAX as u64) {
+            Some("record charge exceeds 32 bits".to_string())
+        } else if net_total != total_amount_cents {
+            Some(format!("records net to {} cents, block claims {}", net_total, total_amount_cents))
+        } else {
+            None
+        };
+        if let Some(reason) = invalid {
+            self.metrics.lock().unwrap().proofs_failed_generation += 1;
+            return Err(ZkpError::InvalidParameters(reason));
+        }
+
+        let circuit = BlockTotalCircuit::<Fr>::new(records, total_amount_cents, block_hash);
+        let rng = &mut ark_std::rand::thread_rng();
+        let proof_result = Groth16::<Bn254>::prove(&self.block_proving_key, circuit, rng);
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metrics = self.metrics.lock().unwrap();
+        let proof = match proof_result {
+            Ok(proof) => proof,
+            Err(e) => {
+                metrics.proofs_failed_generation += 1;
+                return Err(ZkpError::ProofGenFailed(format!("Block total proof generation failed: {:?}", e)));
+            }
+        };
+
+        let proof_bytes = encode_proof(&proof)?;
+
+        let public_inputs = block_total_public_inputs::<Fr>(
+            total_amount_cents,
+            records.len() as u32,
+            block_records_commitment(records),
+            block_hash,
+        )
+            .iter()
+            .map(|input| {
+                let mut bytes = Vec::new();
+                input.serialize_compressed(&mut bytes)
+                    .map_err(|e| ZkpError::SerializationFailed(format!("Public input serialization failed: {:?}", e)))?;
+                Ok(hex::encode(bytes))
+            })
+            .collect::<Result<Vec<String>, ZkpError>>()?;
+
+        metrics.proofs_generated += 1;
+        metrics.total_proof_generation_time_ms += duration_ms;
+        metrics.avg_proof_generation_time_ms =
+            metrics.total_proof_generation_time_ms as f64 / metrics.proofs_generated as f64;
+        metrics.max_proof_generation_time_ms = metrics.max_proof_generation_time_ms.max(duration_ms);
+        if metrics.min_proof_generation_time_ms == u64::MAX || duration_ms < metrics.min_proof_generation_time_ms {
+            metrics.min_proof_generation_time_ms = duration_ms;
+        }
+        metrics.last_proof_generated = Some(chrono::Utc::now().timestamp() as u64);
+
+        println!("✅ ZK block total proof generated ({} bytes, {}ms)", proof_bytes.len(), duration_ms);
+
+        Ok(SettlementProof {
+            proof_bytes,
+            public_inputs,
+        })
+    }
+
+    /// Verify a block total proof against the block's own public fields and records.
+    /// The proof's embedded public inputs are ignored so they cannot disagree with the block.
+    pub fn verify_block_total(
+        &self,
+        block_proof: &SettlementProof,
+        total_amount_cents: u64,
+        records: &[(&str, i64)],
+        block_hash: &[u8],
+    ) -> Result<bool, ZkpError> {
+        let proof = decode_proof(&block_proof.proof_bytes)?;
+
+        let public_inputs = block_total_public_inputs::<Fr>(
+            total_amount_cents,
+            records.len() as u32,
+            block_records_commitment(records),
+            block_hash,
+        );
+        let verify_result = Groth16::<Bn254>::verify(&self.block_verifying_key, &public_inputs, &proof);
+
+        let mut metrics = self.metrics.lock().unwrap();
+        match verify_result {
+            Ok(is_valid) => {
+                metrics.proofs_verified += 1;
+                metrics.last_proof_verified = Some(chrono::Utc::now().timestamp() as u64);
+                Ok(is_valid)
+            }
+            Err(e) => {
+                metrics.proofs_failed_verification += 1;
+                Err(ZkpError::VerificationFailed(format!("Block total proof verification failed: {:?}", e)))
+            }
+        }
+    }
+
+    /// Prove that a block's operator balances sum to zero, bound to the block hash and a
+    /// commitment to every balance
+    pub fn prove_balance_conservation(
+        &self,
+        operator_balances: &HashMap<String, i64>,
+        block_hash: &[u8],
+    ) -> Result<SettlementProof, ZkpError> {
+        let start_time = Instant::now();
+        println!("🛡️  Generating ZK balance conservation proof for {} operators...", operator_balances.len());
+
+        // Groth16 happily proves an unsatisfied circuit, so reject non-conserving balances up front
+        let balance_sum = operator_balances.values().try_fold(0i64, |sum, balance| sum.checked_add(*balance));
+        let invalid = if operator_balances.len() > CONSERVATION_PROOF_CAPACITY {
+            Some(format!("{} operators exceed conservation proof capacity {}", operator_balances.len(), CONSERVATION_PROOF_CAPACITY))
+        } else if balance_sum != Some(0) {
+            Some("operator balances do not sum to zero".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = invalid {
+            self.metrics.lock().unwrap().proofs_failed_generation += 1;
+            return Err(ZkpError::InvalidParameters(reason));
+        }
+
+        let circuit = BalanceConservationCircuit::<Fr>::new(operator_balances, block_hash);
+        let rng = &mut ark_std::rand::thread_rng();
+        let proof_result = Groth16::<Bn254>::prove(&self.conservation_proving_key, circuit, rng);
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metrics = self.metrics.lock().unwrap();
+        let proof = match proof_result {
+            Ok(proof) => proof,
+            Err(e) => {
+                metrics.proofs_failed_generation += 1;
+                return Err(ZkpError::ProofGenFailed(format!("Balance conservation proof generation failed: {:?}", e)));
+            }
+        };
+
+        let proof_bytes = encode_proof(&proof)?;
+
+        let public_inputs = balance_conservation_public_inputs::<Fr>(operator_balances_commitment(operator_balances), block_hash)
+            .iter()
+            .map(|input| {
+                let mut bytes = Vec::new();
+                input.serialize_compressed(&mut bytes)
+                    .map_err(|e| ZkpError::SerializationFailed(format!("Public input serialization failed: {:?}", e)))?;
+                Ok(hex::encode(bytes))
+            })
+            .collect::<Result<Vec<String>, ZkpError>>()?;
+
+        metrics.proofs_generated += 1;
+        metrics.total_proof_generation_time_ms += duration_ms;
+        metrics.avg_proof_generation_time_ms =
+            metrics.total_proof_generation_time_ms as f64 / metrics.proofs_generated as f64;
+        metrics.max_proof_generation_time_ms = metrics.max_proof_generation_time_ms.max(duration_ms);
+        if metrics.min_proof_generation_time_ms == u64::MAX || duration_ms < metrics.min_proof_generation_time_ms {
+            metrics.min_proof_generation_time_ms = duration_ms;
+        }
+        metrics.last_proof_generated = Some(chrono::Utc::now().timestamp() as u64);
+
+        println!("✅ ZK balance conservation proof generated ({} bytes, {}ms)", proof_bytes.len(), duration_ms);
+
+        Ok(SettlementProof {
+            proof_bytes,
+            public_inputs,
         })
     }
 
+    /// Verify a balance conservation proof against the block's own balances and hash.
+    /// The proof's embedded public inputs are ignored so they cannot disagree with the block.
+    pub fn verify_balance_conservation(
+        &self,
+        conservation_proof: &SettlementProof,
+        operator_balances: &HashMap<String, i64>,
+        block_hash: &[u8],
+    ) -> Result<bool, ZkpError> {
+        if operator_balances.len() > CONSERVATION_PROOF_CAPACITY {
+            return Ok(false);
+        }
+        let proof = decode_proof(&conservation_proof.proof_bytes)?;
+
+        let public_inputs = balance_conservation_public_inputs::<Fr>(operator_balances_commitment(operator_balances), block_hash);
+        let verify_result = Groth16::<Bn254>::verify(&self.conservation_verifying_key, &public_inputs, &proof);
+
+        let mut metrics = self.metrics.lock().unwrap();
+        match verify_result {
+            Ok(is_valid) => {
+                metrics.proofs_verified += 1;
+                metrics.last_proof_verified = Some(chrono::Utc::now().timestamp() as u64);
+                Ok(is_valid)
+            }
+            Err(e) => {
+                metrics.proofs_failed_verification += 1;
+                Err(ZkpError::VerificationFailed(format!("Balance conservation proof verification failed: {:?}", e)))
+            }
+        }
+    }
+
     /// Get system information for status checks
     pub fn get_system_info(&self) -> Result<serde_json::Value, ZkpError> {
         // Get verifying key info
@@ -312,9 +822,11 @@ impl SettlementProofSystem {
             "system_type": "Groth16 zk-SNARKs",
             "curve": "BN254",
             "verifying_key_size_bytes": vk_bytes.len(),
+            "proof_format_version": PROOF_FORMAT_VERSION,
+            "proof_size_bytes": PROOF_HEADER_LEN + UNVERSIONED_PROOF_LEN,
             "proving_key_available": true,
             "system_initialized": true,
-            "supported_circuits": ["settlement_privacy", "cdr_validation"],
+            "supported_circuits": ["settlement_privacy", "cdr_validation", "rate_validation", "block_total", "balance_conservation"],
             "max_operators": 5,
             "version": "1.0.0"
         }))
@@ -360,7 +872,9 @@ impl SettlementProofSystem {
                     "failures": metrics.proofs_failed_generation,
                     "success_rate": if metrics.proofs_generated + metrics.proofs_failed_generation > 0 {
                         metrics.proofs_generated as f64 / (metrics.proofs_generated + metrics.proofs_failed_generation) as f64 * 100.0
-                    } else { 0.0 }
+                    } else { 0.0 },
+                    "cache_hits": metrics.proof_cache_hits,
+                    "cache_misses": metrics.proof_cache_misses
                 },
                 "proof_verification": {
                     "total_proofs_verified": metrics.proofs_verified,
@@ -387,6 +901,14 @@ impl SettlementProofSystem {
         }))
     }
 
+    /// Whether the proof system is healthy or degraded, but not failing most operations
+    pub fn is_operational(&self) -> bool {
+        self.health_check()
+            .ok()
+            .and_then(|health| health["health"]["status"].as_str().map(|s| s != "unhealthy"))
+            .unwrap_or(false)
+    }
+
     /// Perform comprehensive health check
     pub fn health_check(&self) -> Result<serde_json::Value, ZkpError> {
         let metrics = self.metrics.lock().unwrap();
@@ -465,6 +987,81 @@ impl SettlementProofSystem {
     }
 }
 
+/// Load the rate validation keys produced by the trusted setup ceremony. As with the block
+/// total keys, a locally generated key would only verify this node's own proofs.
+fn load_rate_keys(keys_dir: &str) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), ZkpError> {
+    let pk_path = format!("{}/rate_validation.pk", keys_dir);
+    let vk_path = format!("{}/rate_validation.vk", keys_dir);
+
+    let pk_bytes = std::fs::read(&pk_path)
+        .map_err(|e| ZkpError::SetupFailed(format!("Failed to load rate validation proving key from {}: {}", pk_path, e)))?;
+    let pk = ProvingKey::<Bn254>::deserialize_compressed(&pk_bytes[..])
+        .map_err(|e| ZkpError::SetupFailed(format!("Rate proving key deserialization failed: {:?}", e)))?;
+
+    let vk_bytes = std::fs::read(&vk_path)
+        .map_err(|e| ZkpError::SetupFailed(format!("Failed to load rate validation verifying key from {}: {}", vk_path, e)))?;
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(&vk_bytes[..])
+        .map_err(|e| ZkpError::SetupFailed(format!("Rate verifying key deserialization failed: {:?}", e)))?;
+
+    Ok((pk, vk))
+}
+
+fn setup_rate_keys() -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), ZkpError> {
+    let rng = &mut ark_std::rand::thread_rng();
+    Groth16::<Bn254>::circuit_specific_setup(RateValidationCircuit::<Fr>::empty(), rng)
+        .map_err(|e| ZkpError::SetupFailed(format!("Rate validation setup failed: {:?}", e)))
+}
+
+/// Load the block total keys produced by the trusted setup ceremony. A locally generated
+/// key would only verify this node's own proofs, so missing keys are an error.
+fn load_block_keys(keys_dir: &str) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), ZkpError> {
+    let pk_path = format!("{}/block_total.pk", keys_dir);
+    let vk_path = format!("{}/block_total.vk", keys_dir);
+
+    let pk_bytes = std::fs::read(&pk_path)
+        .map_err(|e| ZkpError::SetupFailed(format!("Failed to load block total proving key from {}: {}", pk_path, e)))?;
+    let pk = ProvingKey::<Bn254>::deserialize_compressed(&pk_bytes[..])
+        .map_err(|e| ZkpError::SetupFailed(format!("Block total proving key deserialization failed: {:?}", e)))?;
+
+    let vk_bytes = std::fs::read(&vk_path)
+        .map_err(|e| ZkpError::SetupFailed(format!("Failed to load block total verifying key from {}: {}", vk_path, e)))?;
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(&vk_bytes[..])
+        .map_err(|e| ZkpError::SetupFailed(format!("Block total verifying key deserialization failed: {:?}", e)))?;
+
+    Ok((pk, vk))
+}
+
+fn setup_block_keys() -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), ZkpError> {
+    let rng = &mut ark_std::rand::thread_rng();
+    Groth16::<Bn254>::circuit_specific_setup(BlockTotalCircuit::<Fr>::empty(), rng)
+        .map_err(|e| ZkpError::SetupFailed(format!("Block total setup failed: {:?}", e)))
+}
+
+/// Load the balance conservation keys produced by the trusted setup ceremony. A locally
+/// generated key would only verify this node's own proofs, so missing keys are an error.
+fn load_conservation_keys(keys_dir: &str) -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), ZkpError> {
+    let pk_path = format!("{}/balance_conservation.pk", keys_dir);
+    let vk_path = format!("{}/balance_conservation.vk", keys_dir);
+
+    let pk_bytes = std::fs::read(&pk_path)
+        .map_err(|e| ZkpError::SetupFailed(format!("Failed to load balance conservation proving key from {}: {}", pk_path, e)))?;
+    let pk = ProvingKey::<Bn254>::deserialize_compressed(&pk_bytes[..])
+        .map_err(|e| ZkpError::SetupFailed(format!("Balance conservation proving key deserialization failed: {:?}", e)))?;
+
+    let vk_bytes = std::fs::read(&vk_path)
+        .map_err(|e| ZkpError::SetupFailed(format!("Failed to load balance conservation verifying key from {}: {}", vk_path, e)))?;
+    let vk = VerifyingKey::<Bn254>::deserialize_compressed(&vk_bytes[..])
+        .map_err(|e| ZkpError::SetupFailed(format!("Balance conservation verifying key deserialization failed: {:?}", e)))?;
+
+    Ok((pk, vk))
+}
+
+fn setup_conservation_keys() -> Result<(ProvingKey<Bn254>, VerifyingKey<Bn254>), ZkpError> {
+    let rng = &mut ark_std::rand::thread_rng();
+    Groth16::<Bn254>::circuit_specific_setup(BalanceConservationCircuit::<Fr>::empty(), rng)
+        .map_err(|e| ZkpError::SetupFailed(format!("Balance conservation setup failed: {:?}", e)))
+}
+
 impl Default for SettlementProofSystem {
     fn default() -> Self {
         Self::new("default").expect("ZKP system initialization failed")
@@ -490,6 +1087,12 @@ pub enum ZkpError {
 
     #[error("Invalid parameters: {0}")]
     InvalidParameters(String),
+
+    #[error("Incompatible proof format: {0}")]
+    VersionMismatch(String),
+
+    #[error("Proof generation timed out after {0:?}")]
+    Timeout(std::time::Duration),
 }
 
 #[cfg(test)]
@@ -513,4 +1116,247 @@ mod tests {
 
         assert!(is_valid);
     }
+
+    #[test]
+    fn test_operator_count_mismatch_rejected() {
+        let (_, vk) = Groth16::<Bn254>::circuit_specific_setup(
+            SettlementCircuit::new_dummy(),
+            &mut ark_std::rand::thread_rng(),
+        ).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let zkp_system = SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap();
+
+        let params = ProofParameters {
+            total_amount_cents: 10000,
+            operator_count: 2,
+            settlement_hash: [1u8; 32],
+            private_amounts: vec![5000, 3000, 2000],
+            private_rates: vec![100, 150, 200],
+        };
+
+        let err = zkp_system.generate_proof(params).unwrap_err();
+        assert!(matches!(err, ZkpError::InvalidParameters(ref msg) if msg.contains("operator_count 2")));
+        assert_eq!(zkp_system.get_metrics().proofs_failed_generation, 1);
+    }
+
+    #[test]
+    fn test_rate_validation_proof_binds_agreed_band() {
+        let (_, vk) = Groth16::<Bn254>::circuit_specific_setup(
+            SettlementCircuit::new_dummy(),
+            &mut ark_std::rand::thread_rng(),
+        ).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let zkp_system = SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap();
+
+        let bounds = RateBounds {
+            min_call_rate_cents: 1,
+            max_call_rate_cents: 49,
+            min_data_rate_cents: 1,
+            max_data_rate_cents: 19,
+        };
+
+        let proof = zkp_system.prove_rate_validation(30, 15, &bounds).unwrap();
+        assert!(zkp_system.verify_rate_validation(&proof, &bounds).unwrap());
+
+        // The same proof does not convince a verifier expecting a different band
+        let narrower = RateBounds { max_call_rate_cents: 20, ..bounds };
+        assert!(!zkp_system.verify_rate_validation(&proof, &narrower).unwrap());
+
+        let err = zkp_system.prove_rate_validation(60, 15, &bounds).unwrap_err();
+        assert!(matches!(err, ZkpError::InvalidParameters(_)));
+    }
+
+    #[test]
+    fn test_block_total_proof_verifies_against_block() {
+        let (_, vk) = Groth16::<Bn254>::circuit_specific_setup(
+            SettlementCircuit::new_dummy(),
+            &mut ark_std::rand::thread_rng(),
+        ).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let zkp_system = SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap();
+
+        let block_hash = [7u8; 32];
+        let records = [("BCE-001", 1200), ("BCE-002", 800), ("BCE-003", -500), ("BCE-004", 3000)];
+
+        let proof = zkp_system.prove_block_total(&records, 4500, &block_hash).unwrap();
+        assert!(zkp_system.verify_block_total(&proof, 4500, &records, &block_hash).unwrap());
+
+        // The proof does not vouch for a different total or block
+        assert!(!zkp_system.verify_block_total(&proof, 4600, &records, &block_hash).unwrap());
+        assert!(!zkp_system.verify_block_total(&proof, 4500, &records, &[8u8; 32]).unwrap());
+
+        // Nor for other records with the same total: shifted charges, a swapped id or an extra record
+        let shifted = [("BCE-001", 1300), ("BCE-002", 700), ("BCE-003", -500), ("BCE-004", 3000)];
+        assert!(!zkp_system.verify_block_total(&proof, 4500, &shifted, &block_hash).unwrap());
+        let renamed = [("BCE-001", 1200), ("BCE-002", 800), ("BCE-003", -500), ("BCE-005", 3000)];
+        assert!(!zkp_system.verify_block_total(&proof, 4500, &renamed, &block_hash).unwrap());
+        let extra = [("BCE-001", 1200), ("BCE-002", 800), ("BCE-003", -500), ("BCE-004", 3000), ("BCE-005", 0)];
+        assert!(!zkp_system.verify_block_total(&proof, 4500, &extra, &block_hash).unwrap());
+
+        // A block of credits settles nothing
+        let credits = [("BCE-006", -300), ("BCE-007", 100)];
+        let proof = zkp_system.prove_block_total(&credits, 0, &block_hash).unwrap();
+        assert!(zkp_system.verify_block_total(&proof, 0, &credits, &block_hash).unwrap());
+
+        let err = zkp_system.prove_block_total(&records, 9999, &block_hash).unwrap_err();
+        assert!(matches!(err, ZkpError::InvalidParameters(_)));
+    }
+
+    #[test]
+    fn test_balance_conservation_proof_verifies_against_block() {
+        let (_, vk) = Groth16::<Bn254>::circuit_specific_setup(
+            SettlementCircuit::new_dummy(),
+            &mut ark_std::rand::thread_rng(),
+        ).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let zkp_system = SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap();
+
+        let block_hash = [7u8; 32];
+        let balances = HashMap::from([
+            ("T-Mobile-DE".to_string(), -1500i64),
+            ("Vodafone-UK".to_string(), 1000),
+            ("Orange-FR".to_string(), 500),
+        ]);
+
+        let proof = zkp_system.prove_balance_conservation(&balances, &block_hash).unwrap();
+        assert!(zkp_system.verify_balance_conservation(&proof, &balances, &block_hash).unwrap());
+
+        // The proof does not vouch for other balances or another block
+        let shifted = HashMap::from([
+            ("T-Mobile-DE".to_string(), -1400i64),
+            ("Vodafone-UK".to_string(), 900),
+            ("Orange-FR".to_string(), 500),
+        ]);
+        assert!(!zkp_system.verify_balance_conservation(&proof, &shifted, &block_hash).unwrap());
+        assert!(!zkp_system.verify_balance_conservation(&proof, &balances, &[8u8; 32]).unwrap());
+
+        let mut unbalanced = balances.clone();
+        *unbalanced.get_mut("Orange-FR").unwrap() += 1;
+        let err = zkp_system.prove_balance_conservation(&unbalanced, &block_hash).unwrap_err();
+        assert!(matches!(err, ZkpError::InvalidParameters(_)));
+    }
+
+    #[test]
+    fn test_identical_parameters_reuse_cached_proof() {
+        let (_, vk) = Groth16::<Bn254>::circuit_specific_setup(
+            SettlementCircuit::new_dummy(),
+            &mut ark_std::rand::thread_rng(),
+        ).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let zkp_system = SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap();
+
+        let params = ProofParameters {
+            total_amount_cents: 50,
+            operator_count: 2,
+            settlement_hash: [0u8; 32],
+            private_amounts: vec![5, 0],
+            private_rates: vec![5, 0],
+        };
+        let cached = SettlementProof { proof_bytes: vec![9u8; 192], public_inputs: vec![] };
+        zkp_system.proof_cache.lock().unwrap().insert(params.cache_key(), cached.clone());
+
+        // A hit is served from the cache without running the prover
+        let proof = zkp_system.generate_proof(params.clone()).unwrap();
+        assert_eq!(proof.proof_bytes, cached.proof_bytes);
+        let metrics = zkp_system.get_metrics();
+        assert_eq!((metrics.proof_cache_hits, metrics.proof_cache_misses), (1, 0));
+        assert_eq!(metrics.proofs_generated, 0);
+
+        // Every field the proof commits to is part of the key
+        let variants = [
+            ProofParameters { total_amount_cents: 51, ..params.clone() },
+            ProofParameters { settlement_hash: [1u8; 32], ..params.clone() },
+            ProofParameters { private_amounts: vec![0, 5], ..params.clone() },
+            ProofParameters { private_rates: vec![5, 1], ..params.clone() },
+            ProofParameters { private_amounts: vec![5], private_rates: vec![0, 5, 0], ..params.clone() },
+        ];
+        for variant in &variants {
+            assert_ne!(variant.cache_key(), params.cache_key());
+        }
+
+        // Least recently used proofs are evicted once the cache is full
+        zkp_system.set_proof_cache_capacity(1);
+        zkp_system.proof_cache.lock().unwrap().insert(variants[0].cache_key(), cached);
+        assert!(zkp_system.proof_cache.lock().unwrap().get(&params.cache_key()).is_none());
+    }
+
+    #[test]
+    fn test_batch_verify_reports_each_proof() {
+        let witness = SettlementWitness {
+            total_amount: 10000,
+            operator_count: 2,
+            settlement_hash: [1u8; 32],
+            private_amounts: vec![6000, 4000],
+            private_rates: vec![100, 150],
+        };
+        let rng = &mut ark_std::rand::thread_rng();
+        let (pk, vk) = Groth16::<Bn254>::circuit_specific_setup(SettlementCircuit::new(witness.clone()), rng).unwrap();
+        let mut vk_bytes = Vec::new();
+        vk.serialize_compressed(&mut vk_bytes).unwrap();
+        let zkp_system = SettlementProofSystem::from_verifying_key(&vk_bytes).unwrap();
+
+        let proof = Groth16::<Bn254>::prove(&pk, SettlementCircuit::new(witness), rng).unwrap();
+        let proof_bytes = encode_proof(&proof).unwrap();
+        let public_inputs = |total: u64| {
+            // The settlement hash enters as its first 8 bytes
+            [Fr::from(total), Fr::from(2u64), Fr::from(u64::from_le_bytes([1u8; 8]))]
+                .iter()
+                .map(|input| {
+                    let mut bytes = Vec::new();
+                    input.serialize_compressed(&mut bytes).unwrap();
+                    hex::encode(bytes)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let valid = SettlementProof { proof_bytes: proof_bytes.clone(), public_inputs: public_inputs(10000) };
+        let wrong_total = SettlementProof { proof_bytes, public_inputs: public_inputs(9999) };
+        let garbage = SettlementProof { proof_bytes: vec![0xff; 16], public_inputs: public_inputs(10000) };
+
+        let results = zkp_system.batch_verify(&[valid.clone(), wrong_total, garbage, valid.clone()]).unwrap();
+        assert_eq!(results, vec![true, false, false, true]);
+
+        let oversized = vec![valid; MAX_BATCH_VERIFY_PROOFS + 1];
+        assert!(matches!(zkp_system.batch_verify(&oversized), Err(ZkpError::InvalidParameters(_))));
+    }
+
+    #[test]
+    fn test_proof_format_header_round_trip_and_migration() {
+        let witness = SettlementWitness {
+            total_amount: 10000,
+            operator_count: 2,
+            settlement_hash: [1u8; 32],
+            private_amounts: vec![6000, 4000],
+            private_rates: vec![100, 150],
+        };
+        let rng = &mut ark_std::rand::thread_rng();
+        let (pk, _) = Groth16::<Bn254>::circuit_specific_setup(SettlementCircuit::new(witness.clone()), rng).unwrap();
+        let proof = Groth16::<Bn254>::prove(&pk, SettlementCircuit::new(witness), rng).unwrap();
+
+        let proof_bytes = encode_proof(&proof).unwrap();
+        assert_eq!(proof_bytes.len(), PROOF_HEADER_LEN + UNVERSIONED_PROOF_LEN);
+        assert_eq!(proof_bytes[2], PROOF_FORMAT_VERSION);
+        assert_eq!(decode_proof(&proof_bytes).unwrap(), proof);
+
+        // Proofs stored before the header existed are still read and can be rewritten
+        let mut unversioned = Vec::new();
+        proof.serialize_compressed(&mut unversioned).unwrap();
+        assert_eq!(decode_proof(&unversioned).unwrap(), proof);
+        assert_eq!(migrate_unversioned_proof(&unversioned).unwrap(), proof_bytes);
+        assert_eq!(migrate_unversioned_proof(&proof_bytes).unwrap(), proof_bytes);
+
+        // A proof from an incompatible format or curve is reported as such
+        let mut future = proof_bytes.clone();
+        future[2] = PROOF_FORMAT_VERSION + 1;
+        assert!(matches!(decode_proof(&future), Err(ZkpError::VersionMismatch(_))));
+        let mut other_curve = proof_bytes.clone();
+        other_curve[3] = PROOF_CURVE_BN254 + 1;
+        assert!(matches!(decode_proof(&other_curve), Err(ZkpError::VersionMismatch(_))));
+        assert!(matches!(decode_proof(&[0xff; 16]), Err(ZkpError::VersionMismatch(_))));
+    }
 }
\ No newline at end of file
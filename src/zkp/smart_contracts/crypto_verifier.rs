@@ -1,5 +1,8 @@
 // Cryptographic verification for 5-party SP consortium smart contracts
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use log::{info, error};
 use thiserror::Error;
 
@@ -17,6 +20,8 @@ pub enum CryptoError {
     SignatureVerificationFailed,
     #[error("Invalid consortium member: {0}")]
     InvalidConsortiumMember(String),
+    #[error("Invalid member keys: {0}")]
+    InvalidMemberKeys(String),
 }
 
 type Result<T> = std::result::Result<T, CryptoError>;
@@ -75,6 +80,9 @@ pub enum SignatureType {
 pub struct CryptoVerifier {
     /// Valid consortium member IDs
     consortium_members: Vec<String>,
+
+    /// Registered Ed25519 key of each member; signatures under any other key are rejected
+    member_keys: HashMap<String, VerifyingKey>,
     
     /// ZKP verification enabled flag
     zkp_enabled: bool,
@@ -96,11 +104,38 @@ impl CryptoVerifier {
         
         Self {
             consortium_members,
+            member_keys: HashMap::new(),
             zkp_enabled: true,
             signature_verification_enabled: true,
         }
     }
     
+    /// Register the Ed25519 public key a consortium member signs with
+    pub fn register_member_key(&mut self, member_id: &str, public_key: VerifyingKey) -> Result<()> {
+        if !self.consortium_members.iter().any(|m| m == member_id) {
+            return Err(CryptoError::InvalidConsortiumMember(member_id.to_string()));
+        }
+        self.member_keys.insert(member_id.to_string(), public_key);
+        Ok(())
+    }
+
+    /// Register member keys from a JSON object of member ID to hex-encoded Ed25519 public key
+    pub fn load_member_keys_file(&mut self, path: &Path) -> Result<usize> {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| CryptoError::InvalidMemberKeys(format!("{}: {}", path.display(), e)))?;
+        let keys: HashMap<String, String> = serde_json::from_str(&data)
+            .map_err(|e| CryptoError::InvalidMemberKeys(format!("{}: {}", path.display(), e)))?;
+
+        for (member_id, hex_key) in &keys {
+            let public_key = hex::decode(hex_key).ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+                .ok_or_else(|| CryptoError::InvalidMemberKeys(format!("bad public key for {}", member_id)))?;
+            self.register_member_key(member_id, public_key)?;
+        }
+        Ok(keys.len())
+    }
+
     /// Verify ZKP settlement proof
     pub fn verify_settlement_proof(
         &self,
@@ -202,19 +237,28 @@ impl CryptoVerifier {
                 }
             }
             SignatureType::BLS => {
-                if signature.signature_data.len() != 96 {
-                    error!("❌ Invalid BLS signature length: {}", signature.signature_data.len());
-                    return Err(CryptoError::InvalidSignature);
-                }
-                if signature.public_key.len() != 48 {
-                    error!("❌ Invalid BLS public key length: {}", signature.public_key.len());
-                    return Err(CryptoError::InvalidSignature);
-                }
+                error!("❌ BLS signatures are not supported, rejecting signature from: {}", signature.signer_id);
+                return Err(CryptoError::InvalidSignature);
             }
         }
-        
-        // In a real implementation, this would verify the actual signature
-        // For demo purposes, simulate successful verification
+
+        // The key carried in the signature must be the one the member registered
+        let member_key = self.member_keys.get(&signature.signer_id).ok_or_else(|| {
+            error!("❌ No registered signing key for: {}", signature.signer_id);
+            CryptoError::InvalidConsortiumMember(signature.signer_id.clone())
+        })?;
+        if member_key.as_bytes()[..] != signature.public_key[..] {
+            error!("❌ Signature key does not belong to: {}", signature.signer_id);
+            return Err(CryptoError::InvalidSignature);
+        }
+
+        let ed25519_signature = Signature::from_slice(&signature.signature_data)
+            .map_err(|_| CryptoError::InvalidSignature)?;
+        if member_key.verify(signature.message_hash.as_bytes(), &ed25519_signature).is_err() {
+            error!("❌ Signature from {} does not verify", signature.signer_id);
+            return Err(CryptoError::SignatureVerificationFailed);
+        }
+
         info!("✅ Consortium signature verified for: {}", signature.signer_id);
         Ok(true)
     }
@@ -378,6 +422,32 @@ impl CryptoVerifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn member_key(member_id: &str) -> SigningKey {
+        let seed = Blake2bHash::hash(member_id.as_bytes());
+        SigningKey::from_bytes(seed.as_bytes())
+    }
+
+    fn verifier_with_member_keys() -> CryptoVerifier {
+        let mut verifier = CryptoVerifier::new_5party_consortium();
+        for member in verifier.consortium_members.clone() {
+            verifier.register_member_key(&member, member_key(&member).verifying_key()).unwrap();
+        }
+        verifier
+    }
+
+    fn signed_by(member_id: &str, message: &[u8]) -> ConsortiumSignature {
+        let signing_key = member_key(member_id);
+        let message_hash = Blake2bHash::hash(message);
+        ConsortiumSignature {
+            signer_id: member_id.to_string(),
+            signature_data: signing_key.sign(message_hash.as_bytes()).to_bytes().to_vec(),
+            public_key: signing_key.verifying_key().as_bytes().to_vec(),
+            message_hash,
+            signature_type: SignatureType::Ed25519,
+        }
+    }
     
     #[test]
     fn test_crypto_verifier_creation() {
@@ -414,15 +484,9 @@ mod tests {
     
     #[test]
     fn test_consortium_signature_verification() {
-        let verifier = CryptoVerifier::new_5party_consortium();
+        let verifier = verifier_with_member_keys();
         
-        let valid_signature = ConsortiumSignature {
-            signer_id: "T-Mobile-DE".to_string(),
-            signature_data: vec![0u8; 64], // Mock Ed25519 signature
-            public_key: vec![1u8; 32],     // Mock Ed25519 public key
-            message_hash: Blake2bHash::hash(b"test message"),
-            signature_type: SignatureType::Ed25519,
-        };
+        let valid_signature = signed_by("T-Mobile-DE", b"test message");
         
         assert!(verifier.verify_consortium_signature(&valid_signature).is_ok());
         
@@ -434,33 +498,45 @@ mod tests {
         
         assert!(verifier.verify_consortium_signature(&invalid_signature).is_err());
     }
+
+    #[test]
+    fn test_forged_signature_rejected() {
+        let verifier = verifier_with_member_keys();
+        let genuine = signed_by("T-Mobile-DE", b"BCE-001");
+
+        // Well-formed but meaningless signature bytes
+        let zeroed = ConsortiumSignature { signature_data: vec![0u8; 64], ..genuine.clone() };
+        assert!(verifier.verify_consortium_signature(&zeroed).is_err());
+
+        // A genuine signature moved onto another message
+        let moved = ConsortiumSignature { message_hash: Blake2bHash::hash(b"BCE-002"), ..genuine.clone() };
+        assert!(verifier.verify_consortium_signature(&moved).is_err());
+
+        // Signed with a key the forger controls, claiming to be T-Mobile-DE
+        let forger = SigningKey::from_bytes(&[7u8; 32]);
+        let forged = ConsortiumSignature {
+            signature_data: forger.sign(genuine.message_hash.as_bytes()).to_bytes().to_vec(),
+            public_key: forger.verifying_key().as_bytes().to_vec(),
+            ..genuine.clone()
+        };
+        assert!(verifier.verify_consortium_signature(&forged).is_err());
+
+        // Another member's valid signature cannot be passed off as T-Mobile-DE's
+        let borrowed = ConsortiumSignature { signer_id: "T-Mobile-DE".to_string(), ..signed_by("Vodafone-UK", b"BCE-001") };
+        assert!(verifier.verify_consortium_signature(&borrowed).is_err());
+
+        // Without a registered key nothing verifies
+        assert!(CryptoVerifier::new_5party_consortium().verify_consortium_signature(&genuine).is_err());
+    }
     
     #[test]
     fn test_multi_party_signature_verification() {
-        let verifier = CryptoVerifier::new_5party_consortium();
+        let verifier = verifier_with_member_keys();
         
         let signatures = vec![
-            ConsortiumSignature {
-                signer_id: "T-Mobile-DE".to_string(),
-                signature_data: vec![0u8; 64],
-                public_key: vec![1u8; 32],
-                message_hash: Blake2bHash::hash(b"test"),
-                signature_type: SignatureType::Ed25519,
-            },
-            ConsortiumSignature {
-                signer_id: "Vodafone-UK".to_string(),
-                signature_data: vec![0u8; 64],
-                public_key: vec![2u8; 32],
-                message_hash: Blake2bHash::hash(b"test"),
-                signature_type: SignatureType::Ed25519,
-            },
-            ConsortiumSignature {
-                signer_id: "Orange-FR".to_string(),
-                signature_data: vec![0u8; 64],
-                public_key: vec![3u8; 32],
-                message_hash: Blake2bHash::hash(b"test"),
-                signature_type: SignatureType::Ed25519,
-            },
+            signed_by("T-Mobile-DE", b"test"),
+            signed_by("Vodafone-UK", b"test"),
+            signed_by("Orange-FR", b"test"),
         ];
         
         // Should succeed with 3/3 required
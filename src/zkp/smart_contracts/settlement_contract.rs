@@ -29,14 +29,14 @@ impl SettlementContractCompiler {
 
             // Verify privacy proof using 5-party circuit
             Instruction::VerifyProof,
-            Instruction::JumpIf(25), // Jump to success if proof valid
+            Instruction::JumpIf(12), // Jump to success if proof valid
 
             // Proof verification failed
             Instruction::Log("5-Party privacy proof verification failed".to_string()),
             Instruction::Push(0),
             Instruction::Halt,
 
-            // Proof verification succeeded (address 25)
+            // Proof verification succeeded (address 12)
             Instruction::Log("5-Party privacy proof verified".to_string()),
 
             // Validate consortium member
@@ -53,14 +53,14 @@ impl SettlementContractCompiler {
             Instruction::Add,
             Instruction::Push(2),
             Instruction::Eq,
-            Instruction::JumpIf(45), // Jump to signature check
+            Instruction::JumpIf(26), // Jump to signature check
 
             // Invalid consortium member
             Instruction::Log("Invalid consortium member".to_string()),
             Instruction::Push(0),
             Instruction::Halt,
 
-            // Check network signatures (address 45)
+            // Check network signatures (address 26)
             Instruction::Push(5), // home_network_sig offset
             Instruction::Load(Blake2bHash::zero()),
             Instruction::Push(6), // visited_network_sig offset
@@ -73,14 +73,14 @@ impl SettlementContractCompiler {
             Instruction::Add,
             Instruction::Push(2),
             Instruction::Eq,
-            Instruction::JumpIf(60), // Jump to success
+            Instruction::JumpIf(40), // Jump to success
 
             // Signature verification failed
             Instruction::Log("Network signature verification failed".to_string()),
             Instruction::Push(0),
             Instruction::Halt,
 
-            // All verifications passed (address 60)
+            // All verifications passed (address 40)
             Instruction::Log("5-Party BCE batch validated successfully".to_string()),
             Instruction::Push(1),
             Instruction::Halt,
@@ -123,15 +123,7 @@ impl SettlementContractCompiler {
             Instruction::Load(Blake2bHash::from_bytes([28; 32])), // SFR->Orange
             Instruction::Load(Blake2bHash::from_bytes([29; 32])), // SFR->Telefónica
 
-            // Calculate total bilateral amount (sum all 20 amounts)
-            // This is simplified - in practice would be a loop
-            Instruction::Add, Instruction::Add, Instruction::Add, Instruction::Add, // First 4
-            Instruction::Add, Instruction::Add, Instruction::Add, Instruction::Add, // Next 4
-            Instruction::Add, Instruction::Add, Instruction::Add, Instruction::Add, // Next 4
-            Instruction::Add, Instruction::Add, Instruction::Add, Instruction::Add, // Next 4
-            Instruction::Add, Instruction::Add, Instruction::Add, // Last 3 (19 adds total)
-
-            // Apply 5-party multilateral netting
+            // Net the 20 bilateral amounts into the total that actually changes hands
             Instruction::CalculateMultilateralNetting,
 
             // Store net result
@@ -155,14 +147,14 @@ impl SettlementContractCompiler {
 
             // Verify 5-party settlement calculation proof
             Instruction::VerifyProof,
-            Instruction::JumpIf(15), // Jump if proof valid
+            Instruction::JumpIf(8), // Jump if proof valid
 
             // Settlement proof invalid
             Instruction::Log("5-Party settlement proof verification failed".to_string()),
             Instruction::Push(0),
             Instruction::Halt,
 
-            // Settlement proof valid (address 15)
+            // Settlement proof valid (address 8)
             Instruction::Log("5-Party settlement proof verified".to_string()),
 
             // Load signatures from all 5 consortium members
@@ -188,14 +180,14 @@ impl SettlementContractCompiler {
 
             // Verify that at least 3 of 5 signatures are valid (consortium quorum)
             Instruction::CheckMultiPartySignatures(3),
-            Instruction::JumpIf(45), // Jump to execution
+            Instruction::JumpIf(29), // Jump to execution
 
             // Insufficient valid signatures
             Instruction::Log("Insufficient consortium signatures (need 3/5)".to_string()),
             Instruction::Push(0),
             Instruction::Halt,
 
-            // Execute settlement (address 45)
+            // Execute settlement (address 29)
             Instruction::Log("Executing 5-party settlement transfer".to_string()),
 
             // Load net settlement details
@@ -214,6 +206,16 @@ impl SettlementContractCompiler {
     }
 }
 
+/// Consortium operator ids and the names the 5-party netting contract keys their
+/// bilateral amounts by, in storage slot order
+pub const NETTING_OPERATORS: [(&str, &str); 5] = [
+    ("tmobile-de", "T-Mobile-DE"),
+    ("vodafone-uk", "Vodafone-UK"),
+    ("orange-fr", "Orange-FR"),
+    ("telefonica-es", "Telefónica-ES"),
+    ("sfr-fr", "SFR-FR"),
+];
+
 /// Executable settlement contract for 5-party consortium
 #[derive(Clone)]
 pub struct ExecutableSettlementContract {
@@ -231,7 +233,65 @@ pub enum ContractType {
     CustomContract(String),
 }
 
+/// Parses the deployable contract types; custom contracts have no compiler output
+impl std::str::FromStr for ContractType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bce_validator" => Ok(ContractType::BceValidator),
+            "netting" => Ok(ContractType::NettingCalculator),
+            "executor" => Ok(ContractType::SettlementExecutor),
+            other => Err(format!("unknown contract type '{}' (expected bce_validator, netting or executor)", other)),
+        }
+    }
+}
+
 impl ExecutableSettlementContract {
+    /// Create the compiled contract of `contract_type`; netting contracts start from
+    /// `bilateral_amounts`, which must cover every pair of netting operators exactly once.
+    /// The other types ignore them.
+    pub fn for_type(
+        contract_type: &ContractType,
+        contract_id: Blake2bHash,
+        bilateral_amounts: &[(String, String, u64)],
+    ) -> Result<Self, String> {
+        match contract_type {
+            ContractType::BceValidator => Ok(Self::new_5party_bce_validator(contract_id)),
+            ContractType::NettingCalculator => {
+                Self::validate_netting_amounts(bilateral_amounts)?;
+                Ok(Self::new_5party_netting_contract(contract_id, bilateral_amounts))
+            }
+            ContractType::SettlementExecutor => Ok(Self::new_5party_settlement_executor(contract_id)),
+            ContractType::CustomContract(name) => Err(format!("custom contract '{}' has no compiled bytecode", name)),
+        }
+    }
+
+    /// Check that `bilateral_amounts` names each of the 20 ordered pairs of netting
+    /// operators exactly once and nothing else
+    pub fn validate_netting_amounts(bilateral_amounts: &[(String, String, u64)]) -> Result<(), String> {
+        let is_operator = |name: &str| NETTING_OPERATORS.iter().any(|(_, operator)| *operator == name);
+        let mut pairs = std::collections::HashSet::new();
+        for (from, to, _) in bilateral_amounts {
+            if from == to || !is_operator(from) || !is_operator(to) {
+                return Err(format!("unknown netting pair {} -> {}", from, to));
+            }
+            if !pairs.insert((from.as_str(), to.as_str())) {
+                return Err(format!("netting pair {} -> {} appears twice", from, to));
+            }
+        }
+
+        let missing: Vec<String> = NETTING_OPERATORS.iter()
+            .flat_map(|(_, from)| NETTING_OPERATORS.iter().map(move |(_, to)| (*from, *to)))
+            .filter(|(from, to)| from != to && !pairs.contains(&(*from, *to)))
+            .map(|(from, to)| format!("{} -> {}", from, to))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("missing netting pairs: {}", missing.join(", ")));
+        }
+        Ok(())
+    }
+
     /// Create new 5-party BCE validation contract
     pub fn new_5party_bce_validator(contract_id: Blake2bHash) -> Self {
         Self {
@@ -492,6 +552,15 @@ mod tests {
         assert!(matches!(contracts[2].contract_type, ContractType::SettlementExecutor));
     }
 
+    #[test]
+    fn test_compiled_contracts_pass_bytecode_validation() {
+        use crate::zkp::smart_contracts::vm::validate_bytecode;
+
+        assert!(validate_bytecode(&SettlementContractCompiler::compile_5party_bce_validator()).is_ok());
+        assert!(validate_bytecode(&SettlementContractCompiler::compile_5party_netting_contract()).is_ok());
+        assert!(validate_bytecode(&SettlementContractCompiler::compile_5party_settlement_executor()).is_ok());
+    }
+
     #[test]
     fn test_consortium_pairs_generation() {
         let pairs = FivePartySettlementFactory::get_all_consortium_pairs();
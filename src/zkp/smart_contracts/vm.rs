@@ -1,5 +1,6 @@
 // Smart Contract Virtual Machine for 5-party SP consortium
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use log::{info, error};
@@ -7,6 +8,7 @@ use log::{info, error};
 use crate::hash::Blake2bHash;
 use crate::zkp::smart_contracts::crypto_verifier::{CryptoVerifier, BCEPrivacyInputs, SettlementProofInputs};
 use crate::zkp::{ConsortiumSignature, SignatureType};
+use crate::zkp::circuits::NET_POSITION_OFFSET;
 
 #[derive(Error, Debug)]
 pub enum VmError {
@@ -24,10 +26,28 @@ pub enum VmError {
     SignatureVerificationFailed,
     #[error("Execution halted")]
     ExecutionHalted,
+    #[error("Out of gas: {required} required, {remaining} remaining")]
+    OutOfGas { required: u64, remaining: u64 },
+    #[error("Contract not found: {0}")]
+    ContractNotFound(Blake2bHash),
+    #[error("Call depth limit of {0} exceeded")]
+    CallDepthExceeded(usize),
+    #[error("No reachable Halt instruction")]
+    MissingHalt,
+    #[error("Net position of party {0} exceeds the netting offset")]
+    NetPositionOutOfRange(usize),
+    #[error("Arithmetic overflow in {0}")]
+    ArithmeticOverflow(&'static str),
 }
 
 type Result<T> = std::result::Result<T, VmError>;
 
+/// Maximum nesting of contract-to-contract calls
+pub const MAX_CALL_DEPTH: usize = 8;
+
+/// Number of bilateral amounts consumed by CalculateMultilateralNetting (5 parties x 4 counterparties)
+pub const NETTING_BILATERAL_COUNT: usize = 20;
+
 /// Instructions for the smart contract VM - Enhanced for 5-party consortium
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Instruction {
@@ -73,6 +93,88 @@ pub enum Instruction {
     ValidateConsortiumMember(String),
     CheckMultiPartySignatures(u8), // Check N signatures
     CalculateMultilateralNetting,
+
+    // Contract-to-contract invocation
+    Call(Blake2bHash),
+}
+
+/// Default per-execution gas limit (1M gas)
+pub const DEFAULT_GAS_LIMIT: u64 = 1_000_000;
+
+/// Gas charged for a single instruction, weighted by its execution cost
+pub fn gas_cost(instruction: &Instruction) -> u64 {
+    match instruction {
+        // Stack operations
+        Instruction::Push(_) | Instruction::Pop | Instruction::Dup | Instruction::Swap => 1,
+
+        // Arithmetic and comparison operations
+        Instruction::Add | Instruction::Sub => 3,
+        Instruction::Mul | Instruction::Div | Instruction::Mod => 5,
+        Instruction::Eq | Instruction::Lt | Instruction::Gt => 3,
+
+        // Control flow
+        Instruction::Jump(_) => 8,
+        Instruction::JumpIf(_) => 10,
+        Instruction::Halt => 0,
+
+        // Storage operations
+        Instruction::Load(_) => 200,
+        Instruction::Store(_) => 5_000,
+
+        // Cryptographic operations
+        Instruction::VerifyProof => 100_000,
+        Instruction::CheckSignature => 25_000,
+
+        // Settlement-specific operations
+        Instruction::CalculateSettlement => 50,
+        Instruction::GetTimestamp => 2,
+
+        // Logging - charged per byte of message
+        Instruction::Log(message) => 10 + message.len() as u64,
+
+        // 5-party consortium specific
+        Instruction::ValidateConsortiumMember(_) => 100,
+        Instruction::CheckMultiPartySignatures(count) => 25_000 * (*count as u64),
+        Instruction::CalculateMultilateralNetting => 50_000,
+
+        // Contract-to-contract invocation - sub-execution gas is charged separately
+        Instruction::Call(_) => 700,
+    }
+}
+
+/// Validate contract bytecode before deployment: every jump target must be
+/// in range and at least one Halt must be reachable from the entry point
+pub fn validate_bytecode(bytecode: &[Instruction]) -> Result<()> {
+    for (position, instruction) in bytecode.iter().enumerate() {
+        if let Instruction::Jump(target) | Instruction::JumpIf(target) = instruction {
+            if *target >= bytecode.len() {
+                return Err(VmError::InvalidInstruction(position));
+            }
+        }
+    }
+
+    // Walk all control flow paths from the entry point looking for a Halt
+    let mut visited = vec![false; bytecode.len()];
+    let mut to_visit = vec![0usize];
+
+    while let Some(position) = to_visit.pop() {
+        if position >= bytecode.len() || visited[position] {
+            continue;
+        }
+        visited[position] = true;
+
+        match &bytecode[position] {
+            Instruction::Halt => return Ok(()),
+            Instruction::Jump(target) => to_visit.push(*target),
+            Instruction::JumpIf(target) => {
+                to_visit.push(*target);
+                to_visit.push(position + 1);
+            }
+            _ => to_visit.push(position + 1),
+        }
+    }
+
+    Err(VmError::MissingHalt)
 }
 
 /// Smart Contract Virtual Machine State
@@ -85,6 +187,9 @@ pub struct SmartContractVM {
     
     /// Contract storage
     storage: HashMap<Blake2bHash, u64>,
+
+    /// Storage keys written by Store during execution
+    written_keys: HashSet<Blake2bHash>,
     
     /// Execution logs
     logs: Vec<String>,
@@ -109,6 +214,15 @@ pub struct SmartContractVM {
 
     /// Crypto verifier for real ZKP and signature verification
     crypto_verifier: CryptoVerifier,
+
+    /// Execution context supplying deterministic block data
+    context: Option<ExecutionContext>,
+
+    /// Deployed contract bytecode reachable through Call
+    contract_registry: Arc<HashMap<Blake2bHash, Vec<Instruction>>>,
+
+    /// Current contract-to-contract call depth
+    call_depth: usize,
 }
 
 impl SmartContractVM {
@@ -126,14 +240,18 @@ impl SmartContractVM {
             stack: Vec::new(),
             pc: 0,
             storage: HashMap::new(),
+            written_keys: HashSet::new(),
             logs: Vec::new(),
             bytecode,
-            gas_limit: Some(1_000_000), // 1M gas limit
+            gas_limit: Some(DEFAULT_GAS_LIMIT),
             gas_used: 0,
             consortium_members,
             result: None,
             halted: false,
             crypto_verifier,
+            context: None,
+            contract_registry: Arc::new(HashMap::new()),
+            call_depth: 0,
         }
     }
     
@@ -144,20 +262,44 @@ impl SmartContractVM {
         vm
     }
     
+    /// Create VM with initial storage bound to a block execution context
+    pub fn with_context(
+        bytecode: Vec<Instruction>,
+        initial_storage: HashMap<Blake2bHash, u64>,
+        crypto_verifier: CryptoVerifier,
+        context: ExecutionContext,
+    ) -> Self {
+        let mut vm = Self::with_storage(bytecode, initial_storage, crypto_verifier);
+        vm.context = Some(context);
+        vm
+    }
+
+    /// Override the gas limit (None = unlimited)
+    pub fn set_gas_limit(&mut self, gas_limit: Option<u64>) {
+        self.gas_limit = gas_limit;
+    }
+
+    /// Make deployed contracts available to the Call instruction
+    pub fn set_contract_registry(&mut self, registry: Arc<HashMap<Blake2bHash, Vec<Instruction>>>) {
+        self.contract_registry = registry;
+    }
+
     /// Execute the smart contract
     pub fn execute(&mut self) -> Result<u64> {
         info!("🚀 Starting smart contract execution for 5-party consortium");
         
         while !self.halted && self.pc < self.bytecode.len() {
+            let cost = gas_cost(&self.bytecode[self.pc]);
             if let Some(gas_limit) = self.gas_limit {
-                if self.gas_used >= gas_limit {
-                    error!("Gas limit exceeded: {} >= {}", self.gas_used, gas_limit);
-                    return Err(VmError::StorageError("Gas limit exceeded".to_string()));
+                let remaining = gas_limit.saturating_sub(self.gas_used);
+                if cost > remaining {
+                    error!("Gas limit exceeded: {} required, {} remaining", cost, remaining);
+                    return Err(VmError::OutOfGas { required: cost, remaining });
                 }
             }
             
+            self.gas_used += cost;
             self.execute_instruction()?;
-            self.gas_used += 1;
         }
         
         if self.halted {
@@ -285,6 +427,7 @@ impl SmartContractVM {
             Instruction::Store(key) => {
                 let value = self.stack.pop().ok_or(VmError::StackUnderflow)?;
                 self.storage.insert(key, value);
+                self.written_keys.insert(key);
             }
             
             Instruction::VerifyProof => {
@@ -414,7 +557,11 @@ impl SmartContractVM {
             }
             
             Instruction::GetTimestamp => {
-                let timestamp = chrono::Utc::now().timestamp() as u64;
+                // Block timestamp keeps execution deterministic across validators
+                let timestamp = match self.context {
+                    Some(ref context) => context.timestamp,
+                    None => chrono::Utc::now().timestamp() as u64,
+                };
                 self.stack.push(timestamp);
             }
             
@@ -447,17 +594,73 @@ impl SmartContractVM {
             }
             
             Instruction::CalculateMultilateralNetting => {
-                // Simplified 5-party netting calculation
-                // In practice, this would load all bilateral amounts and calculate net positions
-                let total_bilateral = self.stack.pop().ok_or(VmError::StackUnderflow)?;
-                
-                // Simulate significant netting savings (typically 60-90% reduction)
-                let netting_efficiency = 75; // 75% reduction
-                let net_amount = (total_bilateral * (100 - netting_efficiency)) / 100;
-                
+                // Pop the 20 bilateral amounts; they were pushed in circuit order
+                // (party p's outgoing at 4p..4p+3), so the last one is on top
+                if self.stack.len() < NETTING_BILATERAL_COUNT {
+                    return Err(VmError::StackUnderflow);
+                }
+                let bilateral = self.stack.split_off(self.stack.len() - NETTING_BILATERAL_COUNT);
+
+                // Stack values are u64, so net positions carry the same offset the
+                // settlement circuit uses: position = offset + outgoing - incoming
+                let offset = NET_POSITION_OFFSET as u64;
+                let mut outgoing = [0u64; 5];
+                let mut incoming = [0u64; 5];
+                for from in 0..5 {
+                    let receivers = (0..5).filter(|to| *to != from);
+                    for (slot, to) in receivers.enumerate() {
+                        let amount = bilateral[from * 4 + slot];
+                        outgoing[from] = outgoing[from].checked_add(amount)
+                            .ok_or(VmError::ArithmeticOverflow("multilateral netting"))?;
+                        incoming[to] = incoming[to].checked_add(amount)
+                            .ok_or(VmError::ArithmeticOverflow("multilateral netting"))?;
+                    }
+                }
+
+                let mut net_amount = 0u64;
+                for party in 0..5 {
+                    let position = offset.checked_add(outgoing[party])
+                        .and_then(|position| position.checked_sub(incoming[party]))
+                        .filter(|position| *position < 2 * offset)
+                        .ok_or(VmError::NetPositionOutOfRange(party))?;
+                    // Only creditors' positions count; debtors' mirror them
+                    if position > offset {
+                        net_amount += position - offset;
+                    }
+                }
+
+                let gross_total = outgoing.iter().try_fold(0u64, |total, amount| total.checked_add(*amount))
+                    .ok_or(VmError::ArithmeticOverflow("multilateral netting"))?;
                 self.stack.push(net_amount);
-                info!("🔄 5-party multilateral netting: {} -> {} ({}% reduction)", 
-                      total_bilateral, net_amount, netting_efficiency);
+                info!("🔄 5-party multilateral netting: {} gross -> {} net", gross_total, net_amount);
+            }
+
+            Instruction::Call(target) => {
+                if self.call_depth >= MAX_CALL_DEPTH {
+                    error!("❌ Call depth limit reached calling {}", target);
+                    return Err(VmError::CallDepthExceeded(MAX_CALL_DEPTH));
+                }
+
+                let bytecode = self.contract_registry.get(&target)
+                    .cloned()
+                    .ok_or(VmError::ContractNotFound(target))?;
+                let input = self.stack.pop().ok_or(VmError::StackUnderflow)?;
+
+                // Sub-execution runs on the remaining gas of the caller
+                let mut callee = SmartContractVM::new(bytecode, self.crypto_verifier.clone());
+                callee.stack.push(input);
+                callee.gas_limit = self.gas_limit.map(|limit| limit.saturating_sub(self.gas_used));
+                callee.context = self.context.clone();
+                callee.contract_registry = self.contract_registry.clone();
+                callee.call_depth = self.call_depth + 1;
+
+                let call_result = callee.execute();
+                self.gas_used += callee.gas_used;
+                self.logs.extend(callee.logs.into_iter().map(|log| format!("[{}] {}", target, log)));
+
+                let result = call_result?;
+                self.stack.push(result);
+                info!("📞 Contract call to {} returned {}", target, result);
             }
         }
         
@@ -480,6 +683,13 @@ impl SmartContractVM {
         &self.storage
     }
     
+    /// Get the storage entries written by the contract, leaving out untouched initial storage
+    pub fn get_written_storage(&self) -> HashMap<Blake2bHash, u64> {
+        self.written_keys.iter()
+            .filter_map(|key| self.storage.get(key).map(|value| (*key, *value)))
+            .collect()
+    }
+    
     /// Get gas usage
     pub fn get_gas_used(&self) -> u64 {
         self.gas_used
@@ -494,6 +704,11 @@ impl SmartContractVM {
     pub fn get_result(&self) -> Option<u64> {
         self.result
     }
+
+    /// Get execution context
+    pub fn get_context(&self) -> Option<&ExecutionContext> {
+        self.context.as_ref()
+    }
 }
 
 /// Contract execution context
@@ -512,12 +727,22 @@ impl ExecutionContext {
         caller: String,
         contract_address: Blake2bHash,
         block_number: u64,
+    ) -> Self {
+        Self::for_block(caller, contract_address, block_number, chrono::Utc::now().timestamp() as u64)
+    }
+
+    /// Create execution context pinned to a block's timestamp
+    pub fn for_block(
+        caller: String,
+        contract_address: Blake2bHash,
+        block_number: u64,
+        timestamp: u64,
     ) -> Self {
         Self {
             caller,
             contract_address,
             block_number,
-            timestamp: chrono::Utc::now().timestamp() as u64,
+            timestamp,
             consortium_members: vec![
                 "T-Mobile-DE".to_string(),
                 "Vodafone-UK".to_string(),
@@ -537,6 +762,24 @@ mod tests {
         CryptoVerifier::new(true) // Enable ZKP for testing
     }
     
+    #[test]
+    fn test_written_storage_excludes_initial_entries() {
+        let seeded = Blake2bHash::hash(b"seeded");
+        let written = Blake2bHash::hash(b"written");
+        let bytecode = vec![
+            Instruction::Load(seeded),
+            Instruction::Store(written),
+            Instruction::Push(0),
+            Instruction::Halt,
+        ];
+
+        let mut vm = SmartContractVM::with_storage(bytecode, HashMap::from([(seeded, 7)]), create_test_crypto_verifier());
+        vm.execute().unwrap();
+
+        assert_eq!(vm.get_written_storage(), HashMap::from([(written, 7)]));
+        assert_eq!(vm.get_storage().len(), 2);
+    }
+
     #[test]
     fn test_basic_arithmetic() {
         let bytecode = vec![
@@ -550,7 +793,27 @@ mod tests {
         let result = vm.execute().unwrap();
         
         assert_eq!(result, 15);
-        assert_eq!(vm.get_gas_used(), 4);
+        assert_eq!(vm.get_gas_used(), 5); // Push + Push + Add + Halt
+    }
+
+    #[test]
+    fn test_proof_heavy_program_exhausts_gas() {
+        // 1M gas limit covers only 9 proof verifications at 100k each plus stack ops
+        let mut bytecode = Vec::new();
+        for _ in 0..20 {
+            bytecode.push(Instruction::Push(0));
+            bytecode.push(Instruction::Push(0));
+            bytecode.push(Instruction::VerifyProof);
+            bytecode.push(Instruction::Pop);
+        }
+        bytecode.push(Instruction::Halt);
+
+        let mut vm = SmartContractVM::new(bytecode, create_test_crypto_verifier());
+        let result = vm.execute();
+
+        assert!(matches!(result, Err(VmError::OutOfGas { .. })));
+        assert!(!vm.is_halted());
+        assert!(vm.get_gas_used() <= 1_000_000);
     }
     
     #[test]
@@ -585,18 +848,154 @@ mod tests {
     
     #[test]
     fn test_multilateral_netting() {
+        let bilateral: [u64; 20] = [
+            // T-Mobile outgoing: to Vodafone, Orange, Telefónica, SFR
+            50000, 75000, 25000, 30000,
+            // Vodafone outgoing: to T-Mobile, Orange, Telefónica, SFR
+            40000, 60000, 20000, 25000,
+            // Orange outgoing: to T-Mobile, Vodafone, Telefónica, SFR
+            35000, 45000, 15000, 20000,
+            // Telefónica outgoing: to T-Mobile, Vodafone, Orange, SFR
+            30000, 40000, 18000, 22000,
+            // SFR outgoing: to T-Mobile, Vodafone, Orange, Telefónica
+            28000, 38000, 16000, 18000,
+        ];
+        let mut bytecode: Vec<Instruction> = bilateral.iter().map(|amount| Instruction::Push(*amount)).collect();
+        bytecode.push(Instruction::CalculateMultilateralNetting);
+        bytecode.push(Instruction::Halt);
+
+        let mut vm = SmartContractVM::new(bytecode, create_test_crypto_verifier());
+        let result = vm.execute().unwrap();
+
+        // Net positions: T-Mobile +47000, Vodafone -28000, Orange -54000,
+        // Telefónica +32000, SFR +3000 -> creditors receive 82000 in total
+        assert_eq!(result, 82000);
+        assert_eq!(vm.stack, vec![82000]); // All 20 inputs consumed
+    }
+
+    #[test]
+    fn test_multilateral_netting_requires_all_bilateral_amounts() {
         let bytecode = vec![
-            Instruction::Push(1000000), // €10,000 total bilateral
+            Instruction::Push(1000000),
             Instruction::CalculateMultilateralNetting,
             Instruction::Halt,
         ];
-        
+
         let mut vm = SmartContractVM::new(bytecode, create_test_crypto_verifier());
-        let result = vm.execute().unwrap();
-        
-        assert_eq!(result, 250000); // 75% reduction -> €2,500 net
+        assert!(matches!(vm.execute(), Err(VmError::StackUnderflow)));
+    }
+
+    #[test]
+    fn test_multilateral_netting_overflow_is_an_error() {
+        let mut bytecode: Vec<Instruction> = (0..NETTING_BILATERAL_COUNT).map(|_| Instruction::Push(u64::MAX / 2)).collect();
+        bytecode.push(Instruction::CalculateMultilateralNetting);
+        bytecode.push(Instruction::Halt);
+
+        let mut vm = SmartContractVM::new(bytecode, create_test_crypto_verifier());
+        assert!(matches!(vm.execute(), Err(VmError::ArithmeticOverflow(_))));
     }
     
+    #[test]
+    fn test_timestamp_from_execution_context_is_deterministic() {
+        let bytecode = vec![
+            Instruction::GetTimestamp,
+            Instruction::Push(60),
+            Instruction::Add,
+            Instruction::Halt,
+        ];
+        let context = ExecutionContext::for_block(
+            "tmobile-de".to_string(),
+            Blake2bHash::hash(b"contract"),
+            7,
+            1734652800,
+        );
+
+        let mut first = SmartContractVM::with_context(bytecode.clone(), HashMap::new(), create_test_crypto_verifier(), context.clone());
+        let mut second = SmartContractVM::with_context(bytecode, HashMap::new(), create_test_crypto_verifier(), context);
+
+        let first_result = first.execute().unwrap();
+        let second_result = second.execute().unwrap();
+
+        assert_eq!(first_result, 1734652860);
+        assert_eq!(first_result, second_result);
+    }
+
+    #[test]
+    fn test_nested_contract_call() {
+        let callee = Blake2bHash::hash(b"doubler");
+        let registry = HashMap::from([(callee, vec![
+            Instruction::Push(2),
+            Instruction::Mul,
+            Instruction::Halt,
+        ])]);
+
+        let bytecode = vec![
+            Instruction::Push(21),
+            Instruction::Call(callee),
+            Instruction::Halt,
+        ];
+
+        let mut vm = SmartContractVM::new(bytecode, create_test_crypto_verifier());
+        vm.set_contract_registry(Arc::new(registry));
+        let result = vm.execute().unwrap();
+
+        assert_eq!(result, 42);
+        // Caller: Push + Call + Halt, callee: Push + Mul + Halt
+        assert_eq!(vm.get_gas_used(), 1 + 700 + 1 + 5);
+    }
+
+    #[test]
+    fn test_recursive_call_exceeds_depth_limit() {
+        let recursive = Blake2bHash::hash(b"recursive");
+        let registry = HashMap::from([(recursive, vec![
+            Instruction::Dup,
+            Instruction::Call(recursive),
+            Instruction::Halt,
+        ])]);
+
+        let bytecode = vec![
+            Instruction::Push(1),
+            Instruction::Call(recursive),
+            Instruction::Halt,
+        ];
+
+        let mut vm = SmartContractVM::new(bytecode, create_test_crypto_verifier());
+        vm.set_contract_registry(Arc::new(registry));
+
+        assert!(matches!(vm.execute(), Err(VmError::CallDepthExceeded(MAX_CALL_DEPTH))));
+    }
+
+    #[test]
+    fn test_validate_bytecode_rejects_bad_jump_target() {
+        let bytecode = vec![
+            Instruction::Push(1),
+            Instruction::JumpIf(10),
+            Instruction::Halt,
+        ];
+
+        assert!(matches!(validate_bytecode(&bytecode), Err(VmError::InvalidInstruction(1))));
+    }
+
+    #[test]
+    fn test_validate_bytecode_rejects_missing_halt() {
+        let no_halt = vec![
+            Instruction::Push(1),
+            Instruction::Push(2),
+            Instruction::Add,
+        ];
+        assert!(matches!(validate_bytecode(&no_halt), Err(VmError::MissingHalt)));
+
+        // Halt exists but can never be reached
+        let unreachable_halt = vec![
+            Instruction::Push(1),
+            Instruction::Jump(0),
+            Instruction::Halt,
+        ];
+        assert!(matches!(validate_bytecode(&unreachable_halt), Err(VmError::MissingHalt)));
+
+        assert!(validate_bytecode(&[Instruction::Push(1), Instruction::Halt]).is_ok());
+    }
+
     #[test]
     fn test_control_flow() {
         let bytecode = vec![
@@ -1,18 +1,25 @@
 // Trusted setup ceremony adapted for 5-node SP consortium
-use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_ec::{pairing::Pairing, CurveGroup, VariableBaseMSM};
+use ark_ff::Field;
+use ark_groth16::{ProvingKey, VerifyingKey};
 use ark_serialize::{CanonicalSerialize, CanonicalDeserialize};
-use ark_snark::SNARK;
 use ark_std::rand::{RngCore, CryptoRng};
-use std::collections::HashMap;
+use ark_std::UniformRand;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey as ParticipantKey};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
+use tokio::sync::Semaphore;
 use log::{info, warn, error};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::hash::Blake2bHash;
-use super::circuits::{BCEPrivacyCircuit, SettlementCalculationCircuit};
+use super::circuits::{BalanceConservationCircuit, BCEPrivacyCircuit, BlockTotalCircuit, RateValidationCircuit, SettlementCalculationCircuit};
+use super::powers_of_tau::PowersOfTau;
 
 #[derive(Error, Debug)]
 pub enum TrustedSetupError {
@@ -22,10 +29,53 @@ pub enum TrustedSetupError {
     Io(#[from] std::io::Error),
     #[error("Invalid proof")]
     InvalidProof,
+    #[error("Ceremony error: {0}")]
+    Ceremony(String),
 }
 
 type Result<T> = std::result::Result<T, TrustedSetupError>;
 
+/// Number of key backups kept before the oldest are pruned
+pub const DEFAULT_KEY_BACKUP_RETENTION: usize = 5;
+
+/// Default number of circuit setups run at once, shared across provider ceremonies
+pub const DEFAULT_SETUP_CONCURRENCY: usize = 4;
+
+/// Phase-1 accumulator the circuit keys are derived from, kept next to them
+pub const POWERS_OF_TAU_FILE: &str = "powers_of_tau.bin";
+
+/// Files that make up a key set and are copied on backup/restore
+const KEY_FILES: [&str; 17] = [
+    POWERS_OF_TAU_FILE,
+    "balance_conservation.pk",
+    "balance_conservation.vk",
+    "balance_conservation.version",
+    "block_total.pk",
+    "block_total.vk",
+    "block_total.version",
+    "cdr_privacy.pk",
+    "cdr_privacy.vk",
+    "cdr_privacy.version",
+    "rate_validation.pk",
+    "rate_validation.vk",
+    "rate_validation.version",
+    "settlement_calculation.pk",
+    "settlement_calculation.vk",
+    "settlement_calculation.version",
+    "ceremony_transcript.json",
+];
+
+/// Constraint layout version of a circuit, written next to its keys. Keys made for another
+/// layout cannot prove or verify the circuit, so loading them is refused and the ceremony has
+/// to be rerun. Keys written without a version file are version 1.
+pub fn circuit_key_version(circuit_id: &str) -> u32 {
+    match circuit_id {
+        // Generalized from a fixed 5-party layout to N parties
+        "settlement_calculation" => 2,
+        _ => 1,
+    }
+}
+
 /// Trusted setup ceremony coordinator for 5-node SP consortium
 pub struct TrustedSetupCeremony {
     /// Circuit identifiers to ceremony data
@@ -36,6 +86,39 @@ pub struct TrustedSetupCeremony {
 
     /// Storage path for keys
     keys_dir: PathBuf,
+
+    /// How many key backups to keep under `keys_dir/backups`
+    backup_retention: usize,
+
+    /// Bounds how many circuit setups run in parallel
+    setup_permits: Arc<Semaphore>,
+
+    /// Registered consortium public keys, used to verify contribution signatures
+    participant_keys: HashMap<String, ParticipantKey>,
+
+    /// Parameters accumulating participant contributions, until finalized
+    contribution_round: Option<ContributionRound>,
+
+    /// Verified phase-1 accumulator, loaded from `keys_dir` unless set explicitly
+    powers_of_tau: Option<Arc<PowersOfTau>>,
+}
+
+/// Multi-party contribution round in progress
+struct ContributionRound {
+    transcript: CeremonyTranscript,
+    /// Proving keys re-randomized by every contribution so far, by circuit
+    proving_keys: BTreeMap<String, ProvingKey<Bn254>>,
+    /// Chain hash of each circuit's latest contribution
+    last_hashes: HashMap<String, Blake2bHash>,
+    /// Contribution applied but not yet signed by its participant
+    pending: Option<PendingContribution>,
+}
+
+/// Updated keys held back until the participant's signatures check out
+struct PendingContribution {
+    participant_id: String,
+    proving_keys: BTreeMap<String, ProvingKey<Bn254>>,
+    contributions: Vec<ParticipantContribution>,
 }
 
 /// Configuration for the trusted setup ceremony - adapted for 5 validators
@@ -89,6 +172,9 @@ pub struct CeremonyTranscript {
     pub contributions: Vec<ParticipantContribution>,
     pub final_parameters_hash: Option<Blake2bHash>,
     pub verification_status: VerificationStatus,
+    /// Hash of the phase-1 accumulator the keys were derived from
+    #[serde(default)]
+    pub powers_of_tau_hash: Option<Blake2bHash>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -122,11 +208,90 @@ impl TrustedSetupCeremony {
             ceremony_complete: false,
         });
 
+        circuits.insert("rate_validation".to_string(), CircuitSetup {
+            circuit_id: "rate_validation".to_string(),
+            circuit_description: "Rate Validation Circuit - proves private rates lie in the consortium's agreed band".to_string(),
+            parameters_hash: None,
+            proving_key: None,
+            verifying_key: None,
+            ceremony_complete: false,
+        });
+
+        circuits.insert("block_total".to_string(), CircuitSetup {
+            circuit_id: "block_total".to_string(),
+            circuit_description: "Block Total Circuit - proves a block's total is the net of its committed record charges".to_string(),
+            parameters_hash: None,
+            proving_key: None,
+            verifying_key: None,
+            ceremony_complete: false,
+        });
+
+        circuits.insert("balance_conservation".to_string(), CircuitSetup {
+            circuit_id: "balance_conservation".to_string(),
+            circuit_description: "Balance Conservation Circuit - proves a block's committed operator balances sum to zero".to_string(),
+            parameters_hash: None,
+            proving_key: None,
+            verifying_key: None,
+            ceremony_complete: false,
+        });
+
         Self {
             circuits,
             config,
             keys_dir,
+            backup_retention: DEFAULT_KEY_BACKUP_RETENTION,
+            setup_permits: Arc::new(Semaphore::new(DEFAULT_SETUP_CONCURRENCY)),
+            participant_keys: HashMap::new(),
+            contribution_round: None,
+            powers_of_tau: None,
+        }
+    }
+
+    /// Register a participant's consortium public key for transcript verification
+    pub fn register_participant_key(&mut self, participant_id: &str, public_key: ParticipantKey) {
+        self.participant_keys.insert(participant_id.to_string(), public_key);
+    }
+
+    /// Use a phase-1 accumulator for the next ceremony, after checking it is well formed,
+    /// and store it in `keys_dir` alongside the keys derived from it
+    pub async fn set_powers_of_tau(&mut self, powers_of_tau: PowersOfTau) -> Result<()> {
+        let powers_of_tau = Arc::new(powers_of_tau);
+        check_powers_of_tau(powers_of_tau.clone()).await?;
+
+        let mut bytes = Vec::new();
+        powers_of_tau.serialize_compressed(&mut bytes)
+            .map_err(|e| TrustedSetupError::Serialization(format!("Powers of tau serialization error: {}", e)))?;
+        fs::create_dir_all(&self.keys_dir).await?;
+        fs::write(self.keys_dir.join(POWERS_OF_TAU_FILE), &bytes).await?;
+
+        self.powers_of_tau = Some(powers_of_tau);
+        Ok(())
+    }
+
+    /// The phase-1 accumulator keys are derived from. There is no fallback: whoever
+    /// generated τ alone could forge proofs, so it must come from a public ceremony.
+    pub async fn load_powers_of_tau(&self) -> Result<Arc<PowersOfTau>> {
+        if let Some(powers_of_tau) = &self.powers_of_tau {
+            return Ok(powers_of_tau.clone());
         }
+
+        let path = self.keys_dir.join(POWERS_OF_TAU_FILE);
+        let bytes = fs::read(&path).await
+            .map_err(|e| TrustedSetupError::Ceremony(format!("No powers of tau at {:?}: {}", path, e)))?;
+        let powers_of_tau = Arc::new(PowersOfTau::deserialize_compressed(&bytes[..])
+            .map_err(|e| TrustedSetupError::Serialization(format!("Powers of tau deserialization error: {}", e)))?);
+        check_powers_of_tau(powers_of_tau.clone()).await?;
+        Ok(powers_of_tau)
+    }
+
+    /// Set how many circuit setups may run in parallel (at least one)
+    pub fn set_setup_concurrency(&mut self, concurrency: usize) {
+        self.setup_permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    }
+
+    /// Set how many key backups are retained (at least one is always kept)
+    pub fn set_backup_retention(&mut self, retention: usize) {
+        self.backup_retention = retention.max(1);
     }
 
     /// Initialize ceremony with 5-node SP consortium configuration
@@ -158,11 +323,20 @@ impl TrustedSetupCeremony {
         Self::new(keys_dir, config)
     }
 
-    /// Generate individual keys for each provider (more realistic approach)
-    pub async fn generate_individual_provider_keys<R: RngCore + CryptoRng>(
+    /// Generate individual keys for each provider (more realistic approach), all
+    /// derived from the same phase-1 powers of tau. `participant_keys` are the members'
+    /// consortium public keys and `sign` asks a member to sign a contribution.
+    pub async fn generate_individual_provider_keys<R, S>(
         base_keys_dir: PathBuf,
+        powers_of_tau: &PowersOfTau,
+        participant_keys: &HashMap<String, ParticipantKey>,
+        sign: S,
         rng: &mut R
-    ) -> Result<HashMap<String, PathBuf>> {
+    ) -> Result<HashMap<String, PathBuf>>
+    where
+        R: RngCore + CryptoRng,
+        S: Fn(&str, &[u8]) -> Option<Vec<u8>> + Clone,
+    {
         info!("🔐 Generating individual ZKP keys for each SP provider");
 
         let providers = vec![
@@ -173,7 +347,9 @@ impl TrustedSetupCeremony {
             ("sfr-fr", "SFR-FR"),
         ];
 
-        let mut provider_key_dirs = HashMap::new();
+        // All provider ceremonies share one concurrency limit so setups don't oversubscribe the CPU
+        let setup_permits = Arc::new(Semaphore::new(DEFAULT_SETUP_CONCURRENCY));
+        let mut ceremonies = Vec::new();
 
         for (provider_id, provider_name) in providers {
             info!("🔑 Generating keys for {}", provider_name);
@@ -184,30 +360,91 @@ impl TrustedSetupCeremony {
 
             // Generate individual ceremony for this provider
             let mut ceremony = Self::sp_5node_consortium_ceremony(provider_keys_dir.clone());
+            ceremony.setup_permits = setup_permits.clone();
+            ceremony.set_powers_of_tau(powers_of_tau.clone()).await?;
+            for (participant_id, public_key) in participant_keys {
+                ceremony.register_participant_key(participant_id, *public_key);
+            }
+            let sign = sign.clone();
 
-            // Run ceremony and generate keys
-            let transcript = ceremony.run_ceremony(rng).await?;
+            // Each ceremony gets its own rng seeded from the caller's, so runs can overlap
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
 
-            info!("✅ Generated individual keys for {} at {:?}", provider_name, provider_keys_dir);
-            provider_key_dirs.insert(provider_id.to_string(), provider_keys_dir);
+            ceremonies.push(async move {
+                ceremony.run_ceremony(sign, &mut StdRng::from_seed(seed)).await?;
+                info!("✅ Generated individual keys for {} at {:?}", provider_name, provider_keys_dir);
+                Ok::<_, TrustedSetupError>((provider_id.to_string(), provider_keys_dir))
+            });
         }
 
+        let provider_key_dirs: HashMap<String, PathBuf> = futures::future::try_join_all(ceremonies).await?
+            .into_iter()
+            .collect();
+
         info!("🎯 Individual key generation complete for all {} providers", provider_key_dirs.len());
         Ok(provider_key_dirs)
     }
 
-    /// Run the full trusted setup ceremony for 5-node consortium
-    pub async fn run_ceremony<R: RngCore + CryptoRng>(
+    /// Run the full trusted setup ceremony for 5-node consortium, with every participant
+    /// contributing in turn. `sign` hands a contribution message to the participant, which
+    /// signs it with its own consortium key, or returns `None` if it sits the round out.
+    pub async fn run_ceremony<R, S>(
         &mut self,
+        mut sign: S,
         rng: &mut R
-    ) -> Result<CeremonyTranscript> {
+    ) -> Result<CeremonyTranscript>
+    where
+        R: RngCore + CryptoRng,
+        S: FnMut(&str, &[u8]) -> Option<Vec<u8>>,
+    {
         info!("🔐 Starting 5-Node SP Consortium Trusted Setup Ceremony");
         info!("👥 All participants: {:?}", self.config.all_participants);
         info!("✅ Required participants: {:?}", self.config.required_participants);
         info!("📋 Circuits to setup: {:?}", self.circuits.keys().collect::<Vec<_>>());
 
+        self.begin_contributions().await?;
+
+        for participant in self.config.all_participants.clone() {
+            if !self.participant_keys.contains_key(&participant) {
+                warn!("⚠️  No consortium key registered for {}, skipping its contribution", participant);
+                continue;
+            }
+
+            let contributions = self.contribute(&participant, rng)?;
+            let signatures: Option<Vec<Vec<u8>>> = contributions.iter()
+                .map(|contribution| sign(&participant, &contribution.signing_message()))
+                .collect();
+            match signatures {
+                Some(signatures) => {
+                    self.submit_contribution_signatures(&participant, signatures)?;
+                }
+                None => {
+                    warn!("⚠️  {} did not sign its contribution, skipping it", participant);
+                    self.abandon_contribution();
+                }
+            }
+        }
+
+        let transcript = self.finalize_contributions().await?;
+
+        info!("✅ 5-Node consortium trusted setup ceremony completed successfully");
+        info!("🔑 Keys generated for {} circuits", self.circuits.len());
+        info!("👥 Ceremony included {} participants", transcript.participants.len());
+        info!("📜 Ceremony transcript saved for verification");
+
+        Ok(transcript)
+    }
+
+    /// Start a multi-party ceremony (phase 2) by deriving initial parameters for every
+    /// circuit from the phase-1 powers of tau. Each `contribute` then multiplies the
+    /// circuits' δ by a fresh participant secret, so the final keys are sound as long as
+    /// one phase-1 and one phase-2 contributor discarded their secrets.
+    pub async fn begin_contributions(&mut self) -> Result<()> {
+        let powers_of_tau = self.load_powers_of_tau().await?;
+
         let ceremony_id = format!("sp-5node-consortium-{}", chrono::Utc::now().timestamp());
-        let mut transcript = CeremonyTranscript {
+        let transcript = CeremonyTranscript {
             ceremony_id: ceremony_id.clone(),
             start_time: chrono::Utc::now().timestamp() as u64,
             end_time: None,
@@ -215,153 +452,213 @@ impl TrustedSetupCeremony {
             contributions: Vec::new(),
             final_parameters_hash: None,
             verification_status: VerificationStatus::Pending,
+            powers_of_tau_hash: Some(powers_of_tau.hash()?),
         };
 
         // Ensure keys directory exists
         fs::create_dir_all(&self.keys_dir).await
             .map_err(|e| TrustedSetupError::Serialization(format!("Failed to create keys directory: {}", e)))?;
 
-        // Setup each circuit
-        for circuit_id in self.circuits.keys().cloned().collect::<Vec<_>>() {
-            info!("⚙️  Setting up circuit for 5-node consortium: {}", circuit_id);
-
-            match circuit_id.as_str() {
-                "cdr_privacy" => {
-                    self.setup_cdr_privacy_circuit(rng, &mut transcript).await?;
-                }
-                "settlement_calculation" => {
-                    self.setup_settlement_circuit(rng, &mut transcript).await?;
-                }
-                _ => {
-                    warn!("Unknown circuit: {}", circuit_id);
-                }
-            }
+        let mut setups = tokio::task::JoinSet::new();
+        for circuit_id in self.circuit_ids() {
+            let setup_permits = self.setup_permits.clone();
+            let powers_of_tau = powers_of_tau.clone();
+
+            setups.spawn(async move {
+                let _permit = setup_permits.acquire_owned().await
+                    .map_err(|e| TrustedSetupError::Serialization(format!("Setup limiter closed: {}", e)))?;
+                info!("⚙️  Setting up circuit for 5-node consortium: {}", circuit_id);
+
+                let setup_id = circuit_id.clone();
+                let proving_key = tokio::task::spawn_blocking(move || initial_circuit_keys(&setup_id, &powers_of_tau)).await
+                    .map_err(|e| TrustedSetupError::Serialization(format!("Setup task failed: {}", e)))??;
+                Ok::<_, TrustedSetupError>((circuit_id, proving_key))
+            });
         }
 
-        transcript.end_time = Some(chrono::Utc::now().timestamp() as u64);
-        transcript.verification_status = VerificationStatus::Verified;
-
-        // Save ceremony transcript
-        self.save_ceremony_transcript(&transcript).await?;
+        let mut proving_keys = BTreeMap::new();
+        while let Some(joined) = setups.join_next().await {
+            let (circuit_id, proving_key) = joined
+                .map_err(|e| TrustedSetupError::Serialization(format!("Setup task failed: {}", e)))??;
+            proving_keys.insert(circuit_id, proving_key);
+        }
 
-        info!("✅ 5-Node consortium trusted setup ceremony completed successfully");
-        info!("🔑 Keys generated for {} circuits", self.circuits.len());
-        info!("👥 Ceremony included {} participants", transcript.participants.len());
-        info!("📜 Ceremony transcript saved for verification");
+        info!("🎬 Contribution round {} open for {} circuits", ceremony_id, proving_keys.len());
+        self.contribution_round = Some(ContributionRound {
+            transcript,
+            proving_keys,
+            last_hashes: HashMap::new(),
+            pending: None,
+        });
+        Ok(())
+    }
 
-        Ok(transcript)
+    /// Circuits this ceremony produces keys for, in a fixed order
+    fn circuit_ids(&self) -> Vec<String> {
+        let mut circuit_ids: Vec<String> = self.circuits.keys().cloned().collect();
+        circuit_ids.sort();
+        circuit_ids
     }
 
-    /// Setup CDR privacy circuit with real parameters for 5-node consortium
-    async fn setup_cdr_privacy_circuit<R: RngCore + CryptoRng>(
+    /// Apply a participant's randomized contribution to every circuit's parameters. The
+    /// contributions come back unsigned: the participant signs each `signing_message()`
+    /// with its consortium key and returns them through `submit_contribution_signatures`.
+    pub fn contribute<R: RngCore + CryptoRng>(
         &mut self,
-        rng: &mut R,
-        transcript: &mut CeremonyTranscript,
-    ) -> Result<()> {
-        info!("🔒 Generating CDR Privacy Circuit parameters for 5-node consortium...");
-
-        // Create empty circuit for parameter generation
-        let circuit = BCEPrivacyCircuit::<Fr>::empty();
-
-        // Generate parameters - this is the computationally expensive part
-        info!("⚡ Running setup computation (this may take several minutes)...");
-        let (proving_key, verifying_key) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
-            .map_err(|_| TrustedSetupError::InvalidProof)?;
+        participant_id: &str,
+        rng: &mut R
+    ) -> Result<Vec<ParticipantContribution>> {
+        let round = self.contribution_round.as_mut()
+            .ok_or_else(|| TrustedSetupError::Ceremony("No contribution round in progress".to_string()))?;
 
-        // Calculate parameters hash for verification
-        let mut vk_bytes = Vec::new();
-        verifying_key.serialize_compressed(&mut vk_bytes)
-            .map_err(|e| TrustedSetupError::Serialization(format!("VK serialization error: {}", e)))?;
+        if !self.config.all_participants.iter().any(|p| p == participant_id) {
+            return Err(TrustedSetupError::Ceremony(format!("{} is not a consortium member", participant_id)));
+        }
+        if round.transcript.participants.iter().any(|p| p == participant_id) {
+            return Err(TrustedSetupError::Ceremony(format!("{} has already contributed", participant_id)));
+        }
+        if !self.participant_keys.contains_key(participant_id) {
+            return Err(TrustedSetupError::Ceremony(format!("No consortium key registered for {}", participant_id)));
+        }
+        if let Some(pending) = &round.pending {
+            return Err(TrustedSetupError::Ceremony(format!("Waiting for {} to sign its contribution", pending.participant_id)));
+        }
 
-        let params_hash = Blake2bHash::hash(&vk_bytes);
+        let mut proving_keys = BTreeMap::new();
+        let mut contributions = Vec::new();
+        for (circuit_id, proving_key) in &round.proving_keys {
+            let updated = apply_delta_contribution(proving_key, Fr::rand(rng))?;
+            if !is_valid_delta_update(proving_key, &updated, rng) {
+                return Err(TrustedSetupError::Ceremony(format!("Contribution to {} failed consistency checks", circuit_id)));
+            }
 
-        // Update circuit setup
-        if let Some(setup) = self.circuits.get_mut("cdr_privacy") {
-            setup.proving_key = Some(proving_key.clone());
-            setup.verifying_key = Some(verifying_key.clone());
-            setup.parameters_hash = Some(params_hash);
-            setup.ceremony_complete = true;
+            contributions.push(ParticipantContribution {
+                participant_id: participant_id.to_string(),
+                circuit_id: circuit_id.clone(),
+                contribution_hash: verifying_key_hash(&updated.vk)?,
+                previous_hash: round.last_hashes.get(circuit_id).copied().unwrap_or_default(),
+                timestamp: chrono::Utc::now().timestamp() as u64,
+                signature: Vec::new(),
+            });
+            proving_keys.insert(circuit_id.clone(), updated);
         }
 
-        // Save keys to disk
-        self.save_circuit_keys("cdr_privacy", &proving_key, &verifying_key).await?;
+        round.pending = Some(PendingContribution {
+            participant_id: participant_id.to_string(),
+            proving_keys,
+            contributions: contributions.clone(),
+        });
+        Ok(contributions)
+    }
 
-        // Add to transcript with all 5 consortium participants
-        let contribution = ParticipantContribution {
-            participant_id: "5Node-Bootstrap-Coordinator".to_string(),
-            circuit_id: "cdr_privacy".to_string(),
-            contribution_hash: params_hash,
-            previous_hash: Blake2bHash::default(),
-            timestamp: chrono::Utc::now().timestamp() as u64,
-            signature: vec![], // In real ceremony, would be signed by all participants
+    /// Accept a participant's signatures over its pending contributions, in the order
+    /// `contribute` returned them, and chain the contributions into the transcript
+    pub fn submit_contribution_signatures(
+        &mut self,
+        participant_id: &str,
+        signatures: Vec<Vec<u8>>
+    ) -> Result<Vec<ParticipantContribution>> {
+        let round = self.contribution_round.as_mut()
+            .ok_or_else(|| TrustedSetupError::Ceremony("No contribution round in progress".to_string()))?;
+        let public_key = self.participant_keys.get(participant_id)
+            .ok_or_else(|| TrustedSetupError::Ceremony(format!("No consortium key registered for {}", participant_id)))?;
+
+        let pending = match round.pending.as_ref() {
+            Some(pending) if pending.participant_id == participant_id => pending,
+            _ => return Err(TrustedSetupError::Ceremony(format!("No pending contribution from {}", participant_id))),
         };
+        if signatures.len() != pending.contributions.len() {
+            return Err(TrustedSetupError::Ceremony(format!(
+                "Expected {} signatures from {}, got {}",
+                pending.contributions.len(), participant_id, signatures.len()
+            )));
+        }
 
-        transcript.contributions.push(contribution);
+        // Reject the whole batch before touching the round if any signature is bad
+        for (contribution, signature) in pending.contributions.iter().zip(&signatures) {
+            let signature = Signature::from_slice(signature)
+                .map_err(|_| TrustedSetupError::Ceremony(format!("Malformed signature from {}", participant_id)))?;
+            public_key.verify(&contribution.signing_message(), &signature)
+                .map_err(|_| TrustedSetupError::Ceremony(format!(
+                    "Invalid signature from {} on {}", participant_id, contribution.circuit_id
+                )))?;
+        }
 
-        // Record all 5 consortium participants as having contributed
-        for participant in &self.config.all_participants {
-            if !transcript.participants.contains(participant) {
-                transcript.participants.push(participant.clone());
-                info!("👤 Recorded participation from: {}", participant);
-            }
+        let pending = round.pending.take().expect("pending contribution checked above");
+        let mut contributions = Vec::new();
+        for (mut contribution, signature) in pending.contributions.into_iter().zip(signatures) {
+            contribution.signature = signature;
+            round.last_hashes.insert(contribution.circuit_id.clone(), contribution.chain_hash());
+            round.transcript.contributions.push(contribution.clone());
+            contributions.push(contribution);
         }
+        round.proving_keys = pending.proving_keys;
 
-        info!("✅ CDR Privacy Circuit setup complete for 5-node consortium");
-        info!("📊 Parameters hash: {:?}", params_hash);
+        round.transcript.participants.push(participant_id.to_string());
+        info!("👤 Recorded contribution from: {}", participant_id);
+        Ok(contributions)
+    }
 
-        Ok(())
+    /// Drop a contribution its participant declined to sign
+    pub fn abandon_contribution(&mut self) {
+        if let Some(pending) = self.contribution_round.as_mut().and_then(|round| round.pending.take()) {
+            warn!("🗑️  Discarded unsigned contribution from {}", pending.participant_id);
+        }
     }
 
-    /// Setup settlement calculation circuit for 5-party netting
-    async fn setup_settlement_circuit<R: RngCore + CryptoRng>(
-        &mut self,
-        rng: &mut R,
-        transcript: &mut CeremonyTranscript,
-    ) -> Result<()> {
-        info!("🔒 Generating Settlement Calculation Circuit parameters for 5-party netting...");
+    /// Write the accumulated keys and transcript once enough participants have contributed
+    pub async fn finalize_contributions(&mut self) -> Result<CeremonyTranscript> {
+        let round = self.contribution_round.take()
+            .ok_or_else(|| TrustedSetupError::Ceremony("No contribution round in progress".to_string()))?;
+
+        if round.transcript.participants.len() < self.config.min_participants {
+            let contributed = round.transcript.participants.len();
+            self.contribution_round = Some(round);
+            return Err(TrustedSetupError::Ceremony(format!(
+                "Only {} of the required {} participants have contributed",
+                contributed, self.config.min_participants
+            )));
+        }
 
-        // Create empty circuit for 5-party settlements
-        let circuit = SettlementCalculationCircuit::<Fr>::empty();
+        let ContributionRound { mut transcript, proving_keys, .. } = round;
+        for (circuit_id, proving_key) in proving_keys {
+            let verifying_key = proving_key.vk.clone();
+            self.complete_circuit_setup(&circuit_id, proving_key, verifying_key).await?;
+        }
 
-        // Generate parameters
-        info!("⚡ Running setup computation for 5-party netting...");
-        let (proving_key, verifying_key) = Groth16::<Bn254>::circuit_specific_setup(circuit, rng)
-            .map_err(|_| TrustedSetupError::InvalidProof)?;
+        transcript.end_time = Some(chrono::Utc::now().timestamp() as u64);
+        transcript.verification_status = VerificationStatus::Verified;
 
-        // Calculate hash
-        let mut vk_bytes = Vec::new();
-        verifying_key.serialize_compressed(&mut vk_bytes)
-            .map_err(|e| TrustedSetupError::Serialization(format!("VK serialization error: {}", e)))?;
+        // Save ceremony transcript
+        self.save_ceremony_transcript(&transcript).await?;
+        Ok(transcript)
+    }
 
-        let params_hash = Blake2bHash::hash(&vk_bytes);
+    /// Store freshly generated keys for a circuit, returning the parameters hash
+    async fn complete_circuit_setup(
+        &mut self,
+        circuit_id: &str,
+        proving_key: ProvingKey<Bn254>,
+        verifying_key: VerifyingKey<Bn254>,
+    ) -> Result<Blake2bHash> {
+        // Calculate parameters hash for verification
+        let params_hash = verifying_key_hash(&verifying_key)?;
 
-        // Update setup
-        if let Some(setup) = self.circuits.get_mut("settlement_calculation") {
-            setup.proving_key = Some(proving_key.clone());
-            setup.verifying_key = Some(verifying_key.clone());
+        // Save keys to disk
+        self.save_circuit_keys(circuit_id, &proving_key, &verifying_key).await?;
+
+        // Update circuit setup
+        if let Some(setup) = self.circuits.get_mut(circuit_id) {
+            setup.proving_key = Some(proving_key);
+            setup.verifying_key = Some(verifying_key);
             setup.parameters_hash = Some(params_hash);
             setup.ceremony_complete = true;
         }
 
-        // Save keys
-        self.save_circuit_keys("settlement_calculation", &proving_key, &verifying_key).await?;
-
-        // Add to transcript
-        let contribution = ParticipantContribution {
-            participant_id: "5Node-Bootstrap-Coordinator".to_string(),
-            circuit_id: "settlement_calculation".to_string(),
-            contribution_hash: params_hash,
-            previous_hash: Blake2bHash::default(),
-            timestamp: chrono::Utc::now().timestamp() as u64,
-            signature: vec![],
-        };
-
-        transcript.contributions.push(contribution);
-
-        info!("✅ 5-Party Settlement Calculation Circuit setup complete");
+        info!("✅ Circuit {} setup complete for 5-node consortium", circuit_id);
         info!("📊 Parameters hash: {:?}", params_hash);
 
-        Ok(())
+        Ok(params_hash)
     }
 
     /// Save circuit keys to disk
@@ -389,6 +686,9 @@ impl TrustedSetupCeremony {
         fs::write(&vk_path, &vk_bytes).await
             .map_err(|e| TrustedSetupError::Serialization(format!("Failed to write VK: {}", e)))?;
 
+        let version_path = self.keys_dir.join(format!("{}.version", circuit_id));
+        fs::write(&version_path, circuit_key_version(circuit_id).to_string()).await?;
+
         info!("💾 Saved keys for {} to {:?}", circuit_id, self.keys_dir);
         info!("   📁 Proving key: {} bytes", pk_bytes.len());
         info!("   📁 Verifying key: {} bytes", vk_bytes.len());
@@ -401,6 +701,15 @@ impl TrustedSetupCeremony {
         let pk_path = self.keys_dir.join(format!("{}.pk", circuit_id));
         let vk_path = self.keys_dir.join(format!("{}.vk", circuit_id));
 
+        // Keys for an older constraint layout would fail every proof against the current circuit
+        let stored_version = self.stored_key_version(circuit_id).await?;
+        if stored_version != circuit_key_version(circuit_id) {
+            return Err(TrustedSetupError::Ceremony(format!(
+                "keys for {} were generated for circuit version {} but the circuit is version {}; rerun the trusted setup ceremony",
+                circuit_id, stored_version, circuit_key_version(circuit_id)
+            )));
+        }
+
         // Load proving key
         let pk_bytes = fs::read(&pk_path).await
             .map_err(|e| TrustedSetupError::Serialization(format!("Failed to read PK: {}", e)))?;
@@ -420,6 +729,18 @@ impl TrustedSetupCeremony {
         Ok((proving_key, verifying_key))
     }
 
+    /// Circuit version recorded with a circuit's keys
+    async fn stored_key_version(&self, circuit_id: &str) -> Result<u32> {
+        let version_path = self.keys_dir.join(format!("{}.version", circuit_id));
+        match fs::read_to_string(&version_path).await {
+            Ok(version) => version.trim().parse().map_err(|e| TrustedSetupError::Serialization(
+                format!("Invalid key version file {:?}: {}", version_path, e)
+            )),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(1),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Check if keys exist for a circuit
     pub async fn keys_exist(&self, circuit_id: &str) -> bool {
         let pk_path = self.keys_dir.join(format!("{}.pk", circuit_id));
@@ -454,8 +775,25 @@ impl TrustedSetupCeremony {
         let transcript: CeremonyTranscript = serde_json::from_str(&transcript_json)
             .map_err(|e| TrustedSetupError::Serialization(format!("Transcript deserialization error: {}", e)))?;
 
+        // Keys must be derived from a phase-1 accumulator, not generated by one party
+        let powers_of_tau = match transcript.powers_of_tau_hash {
+            Some(expected) => {
+                let powers_of_tau = self.load_powers_of_tau().await?;
+                if !powers_of_tau.hash()?.ct_eq(&expected) {
+                    error!("❌ Powers of tau do not match the ceremony transcript");
+                    return Ok(false);
+                }
+                powers_of_tau
+            }
+            None => {
+                error!("❌ Ceremony predates powers of tau; its keys were generated by a single party");
+                return Ok(false);
+            }
+        };
+
         // Verify all required circuits have keys
-        for circuit_id in ["cdr_privacy", "settlement_calculation"] {
+        for circuit_id in self.circuit_ids() {
+            let circuit_id = circuit_id.as_str();
             if !self.keys_exist(circuit_id).await {
                 error!("❌ Missing keys for circuit: {}", circuit_id);
                 return Ok(false);
@@ -465,25 +803,43 @@ impl TrustedSetupCeremony {
             let (pk, vk) = self.load_circuit_keys(circuit_id).await?;
 
             // Verify key consistency
-            let mut vk_bytes = Vec::new();
-            vk.serialize_compressed(&mut vk_bytes)
-                .map_err(|e| TrustedSetupError::Serialization(format!("VK serialization error: {}", e)))?;
+            let current_hash = verifying_key_hash(&vk)?;
 
-            let current_hash = Blake2bHash::hash(&vk_bytes);
-
-            // Find contribution in transcript
+            // The keys on disk are the result of the circuit's latest contribution
             let contribution = transcript.contributions.iter()
+                .rev()
                 .find(|c| c.circuit_id == circuit_id)
                 .ok_or_else(|| TrustedSetupError::InvalidProof)?;
 
-            if contribution.contribution_hash != current_hash {
+            // Constant-time: the expected hash comes from an on-disk transcript
+            if !contribution.contribution_hash.ct_eq(&current_hash) {
                 error!("❌ Key hash mismatch for circuit: {}", circuit_id);
                 return Ok(false);
             }
 
+            // Only δ may differ from the keys the powers of tau yield for this circuit
+            let setup_id = circuit_id.to_string();
+            let phase1 = powers_of_tau.clone();
+            let derived = tokio::task::spawn_blocking(move || {
+                let initial = initial_circuit_keys(&setup_id, &phase1)?;
+                Ok::<_, TrustedSetupError>(is_valid_delta_update(&initial, &pk, &mut ark_std::rand::thread_rng()))
+            }).await.map_err(|e| TrustedSetupError::Ceremony(format!("Key derivation check failed: {}", e)))??;
+            if !derived {
+                error!("❌ Keys for circuit {} are not derived from the powers of tau", circuit_id);
+                return Ok(false);
+            }
+
             info!("✅ Circuit {} keys verified for 5-node consortium", circuit_id);
         }
 
+        // Every contribution must be signed by a registered participant and chain to its predecessor
+        if self.config.verify_contributions {
+            if let Err(reason) = self.verify_contribution_signatures(&transcript) {
+                error!("❌ Contribution verification failed: {}", reason);
+                return Ok(false);
+            }
+        }
+
         // Verify ceremony completeness - require at least minimum participants
         if transcript.participants.len() < self.config.min_participants {
             error!("❌ Insufficient participants: {} < {}",
@@ -521,6 +877,157 @@ impl TrustedSetupCeremony {
         }
     }
 
+    /// Check each contribution's signature against the registered participant keys
+    fn verify_contribution_signatures(&self, transcript: &CeremonyTranscript) -> std::result::Result<(), String> {
+        let mut previous_hashes: HashMap<&str, Blake2bHash> = HashMap::new();
+
+        for contribution in &transcript.contributions {
+            let public_key = self.participant_keys.get(&contribution.participant_id)
+                .ok_or_else(|| format!("unregistered participant {}", contribution.participant_id))?;
+
+            let expected_previous = previous_hashes.get(contribution.circuit_id.as_str()).copied().unwrap_or_default();
+            if !contribution.previous_hash.ct_eq(&expected_previous) {
+                return Err(format!("contribution from {} to {} is out of order",
+                                   contribution.participant_id, contribution.circuit_id));
+            }
+
+            let signature = Signature::from_slice(&contribution.signature)
+                .map_err(|_| format!("missing or malformed signature from {}", contribution.participant_id))?;
+            public_key.verify(&contribution_message(&contribution.contribution_hash, &contribution.previous_hash), &signature)
+                .map_err(|_| format!("invalid signature from {} on {}", contribution.participant_id, contribution.circuit_id))?;
+
+            previous_hashes.insert(contribution.circuit_id.as_str(), contribution.chain_hash());
+        }
+
+        Ok(())
+    }
+
+    /// Directory holding timestamped key backups
+    pub fn backups_dir(&self) -> PathBuf {
+        self.keys_dir.join("backups")
+    }
+
+    /// Copy the current keys into a new timestamped backup directory and
+    /// prune backups beyond the retention limit
+    pub async fn backup_keys(&self) -> Result<PathBuf> {
+        let backup_dir = self.snapshot_keys().await?;
+        self.prune_key_backups().await?;
+        Ok(backup_dir)
+    }
+
+    /// Copy the current keys into a new timestamped backup directory
+    async fn snapshot_keys(&self) -> Result<PathBuf> {
+        let backups_dir = self.backups_dir();
+        fs::create_dir_all(&backups_dir).await?;
+
+        // Timestamps sort lexicographically; add a suffix if two backups land in the same millisecond
+        let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f").to_string();
+        let mut backup_dir = backups_dir.join(&timestamp);
+        let mut suffix = 1;
+        while backup_dir.exists() {
+            backup_dir = backups_dir.join(format!("{}-{}", timestamp, suffix));
+            suffix += 1;
+        }
+        fs::create_dir_all(&backup_dir).await?;
+
+        let copied = Self::copy_key_files(&self.keys_dir, &backup_dir).await?;
+        info!("🗄️  Backed up {} key files to {:?}", copied, backup_dir);
+        Ok(backup_dir)
+    }
+
+    /// List available key backups, oldest first
+    pub async fn list_key_backups(&self) -> Result<Vec<String>> {
+        let backups_dir = self.backups_dir();
+        if !backups_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        let mut entries = fs::read_dir(&backups_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_dir() {
+                backups.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        backups.sort();
+        Ok(backups)
+    }
+
+    /// Regenerate keys, backing up the current ones first
+    pub async fn rotate_keys<R, S>(
+        &mut self,
+        sign: S,
+        rng: &mut R
+    ) -> Result<(PathBuf, CeremonyTranscript)>
+    where
+        R: RngCore + CryptoRng,
+        S: FnMut(&str, &[u8]) -> Option<Vec<u8>>,
+    {
+        info!("🔄 Rotating ZKP keys for 5-node consortium");
+        let backup_dir = self.backup_keys().await?;
+        let transcript = self.run_ceremony(sign, rng).await?;
+        Ok((backup_dir, transcript))
+    }
+
+    /// Swap in keys from another directory, backing up the current ones first.
+    /// The incoming keys must deserialize before anything is replaced.
+    pub async fn reload_keys(&self, source_dir: &Path) -> Result<PathBuf> {
+        info!("🔄 Reloading ZKP keys from {:?}", source_dir);
+        let incoming = Self::new(source_dir.to_path_buf(), self.config.clone());
+        for circuit_id in self.circuit_ids() {
+            incoming.load_circuit_keys(&circuit_id).await?;
+        }
+
+        // Prune only after copying: the incoming keys may themselves be an old backup
+        let backup_dir = self.snapshot_keys().await?;
+        Self::copy_key_files(source_dir, &self.keys_dir).await?;
+        self.prune_key_backups().await?;
+
+        info!("✅ ZKP keys reloaded (previous keys in {:?})", backup_dir);
+        Ok(backup_dir)
+    }
+
+    /// Restore keys from a backup (the most recent one if none is named). Goes through
+    /// `reload_keys`, so the keys being replaced are backed up first.
+    pub async fn restore_keys(&self, backup_id: Option<&str>) -> Result<String> {
+        let backups = self.list_key_backups().await?;
+        let backup_id = match backup_id {
+            Some(id) => backups.iter().find(|b| b.as_str() == id).cloned(),
+            None => backups.last().cloned(),
+        }.ok_or_else(|| TrustedSetupError::Serialization("Key backup not found".to_string()))?;
+
+        self.reload_keys(&self.backups_dir().join(&backup_id)).await?;
+        info!("♻️  Restored ZKP keys from backup {}", backup_id);
+        Ok(backup_id)
+    }
+
+    /// Remove the oldest backups beyond the retention limit
+    async fn prune_key_backups(&self) -> Result<()> {
+        let backups = self.list_key_backups().await?;
+        if backups.len() <= self.backup_retention {
+            return Ok(());
+        }
+
+        for backup_id in &backups[..backups.len() - self.backup_retention] {
+            fs::remove_dir_all(self.backups_dir().join(backup_id)).await?;
+            info!("🧹 Pruned old key backup {}", backup_id);
+        }
+        Ok(())
+    }
+
+    /// Copy whichever key files exist in `from` into `to`
+    async fn copy_key_files(from: &Path, to: &Path) -> Result<usize> {
+        let mut copied = 0;
+        for file in KEY_FILES {
+            let source = from.join(file);
+            if source.exists() {
+                fs::copy(&source, to.join(file)).await?;
+                copied += 1;
+            }
+        }
+        Ok(copied)
+    }
+
     /// Get 5-node consortium configuration
     pub fn get_consortium_config(&self) -> &CeremonyConfig {
         &self.config
@@ -535,8 +1042,8 @@ impl TrustedSetupCeremony {
     pub async fn export_verifying_keys(&self) -> Result<HashMap<String, Vec<u8>>> {
         let mut vk_exports = HashMap::new();
 
-        for circuit_id in ["cdr_privacy", "settlement_calculation"] {
-            if self.keys_exist(circuit_id).await {
+        for circuit_id in self.circuit_ids() {
+            if self.keys_exist(&circuit_id).await {
                 let vk_path = self.keys_dir.join(format!("{}.vk", circuit_id));
                 let vk_bytes = fs::read(&vk_path).await
                     .map_err(|e| TrustedSetupError::Serialization(format!("Failed to read VK: {}", e)))?;
@@ -550,25 +1057,228 @@ impl TrustedSetupCeremony {
     }
 }
 
+impl ParticipantContribution {
+    /// Bytes the participant signs with its consortium key
+    pub fn signing_message(&self) -> Vec<u8> {
+        contribution_message(&self.contribution_hash, &self.previous_hash)
+    }
+
+    /// Hash the next contribution to the same circuit must reference as its previous_hash
+    pub fn chain_hash(&self) -> Blake2bHash {
+        let mut data = contribution_message(&self.contribution_hash, &self.previous_hash);
+        data.extend_from_slice(&self.signature);
+        Blake2bHash::hash(&data)
+    }
+}
+
+/// Bytes a participant signs: its contribution hash followed by the previous hash
+fn contribution_message(contribution_hash: &Blake2bHash, previous_hash: &Blake2bHash) -> Vec<u8> {
+    let mut message = contribution_hash.as_bytes().to_vec();
+    message.extend_from_slice(previous_hash.as_bytes());
+    message
+}
+
+/// Hash identifying a set of circuit parameters
+fn verifying_key_hash(verifying_key: &VerifyingKey<Bn254>) -> Result<Blake2bHash> {
+    let mut vk_bytes = Vec::new();
+    verifying_key.serialize_compressed(&mut vk_bytes)
+        .map_err(|e| TrustedSetupError::Serialization(format!("VK serialization error: {}", e)))?;
+    Ok(Blake2bHash::hash(&vk_bytes))
+}
+
+/// Multiply δ by a participant's secret, dividing the δ-scaled queries by it to keep the key valid
+fn apply_delta_contribution(proving_key: &ProvingKey<Bn254>, secret: Fr) -> Result<ProvingKey<Bn254>> {
+    let secret_inverse = secret.inverse()
+        .ok_or_else(|| TrustedSetupError::Ceremony("Contribution secret must be non-zero".to_string()))?;
+
+    let mut updated = proving_key.clone();
+    updated.delta_g1 = (proving_key.delta_g1 * secret).into_affine();
+    updated.vk.delta_g2 = (proving_key.vk.delta_g2 * secret).into_affine();
+
+    let h_query: Vec<G1Projective> = proving_key.h_query.iter().map(|p| *p * secret_inverse).collect();
+    updated.h_query = G1Projective::normalize_batch(&h_query);
+    let l_query: Vec<G1Projective> = proving_key.l_query.iter().map(|p| *p * secret_inverse).collect();
+    updated.l_query = G1Projective::normalize_batch(&l_query);
+
+    Ok(updated)
+}
+
+/// Check `updated` is `previous` with only δ re-randomized, without knowing the secret
+fn is_valid_delta_update<R: RngCore>(previous: &ProvingKey<Bn254>, updated: &ProvingKey<Bn254>, rng: &mut R) -> bool {
+    let unchanged = previous.vk.alpha_g1 == updated.vk.alpha_g1
+        && previous.vk.beta_g2 == updated.vk.beta_g2
+        && previous.vk.gamma_g2 == updated.vk.gamma_g2
+        && previous.vk.gamma_abc_g1 == updated.vk.gamma_abc_g1
+        && previous.beta_g1 == updated.beta_g1
+        && previous.a_query == updated.a_query
+        && previous.b_g1_query == updated.b_g1_query
+        && previous.b_g2_query == updated.b_g2_query
+        && previous.h_query.len() == updated.h_query.len()
+        && previous.l_query.len() == updated.l_query.len();
+    if !unchanged {
+        return false;
+    }
+
+    // δ moved by the same factor in G1 and G2: e(δ'₁, δ₂) = e(δ₁, δ'₂)
+    if Bn254::pairing(updated.delta_g1, previous.vk.delta_g2) != Bn254::pairing(previous.delta_g1, updated.vk.delta_g2) {
+        return false;
+    }
+
+    // Queries divided by that factor, checked on a random linear combination: e(Σrᵢqᵢ', δ'₂) = e(Σrᵢqᵢ, δ₂)
+    let previous_queries: Vec<_> = previous.h_query.iter().chain(&previous.l_query).copied().collect();
+    let updated_queries: Vec<_> = updated.h_query.iter().chain(&updated.l_query).copied().collect();
+    let scalars: Vec<Fr> = (0..previous_queries.len()).map(|_| Fr::rand(rng)).collect();
+
+    let previous_combined = G1Projective::msm_unchecked(&previous_queries, &scalars);
+    let updated_combined = G1Projective::msm_unchecked(&updated_queries, &scalars);
+    Bn254::pairing(updated_combined, updated.vk.delta_g2) == Bn254::pairing(previous_combined, previous.vk.delta_g2)
+}
+
+/// Check a phase-1 accumulator off the async runtime; the pairing checks are slow for large ones
+async fn check_powers_of_tau(powers_of_tau: Arc<PowersOfTau>) -> Result<()> {
+    let well_formed = tokio::task::spawn_blocking(move || powers_of_tau.is_well_formed(&mut ark_std::rand::thread_rng())).await
+        .map_err(|e| TrustedSetupError::Ceremony(format!("Powers of tau check failed: {}", e)))?;
+    if !well_formed {
+        return Err(TrustedSetupError::Ceremony("Powers of tau are not well formed".to_string()));
+    }
+    Ok(())
+}
+
+/// Phase-2 starting keys for one circuit; this is the computationally expensive part
+fn initial_circuit_keys(circuit_id: &str, powers_of_tau: &PowersOfTau) -> Result<ProvingKey<Bn254>> {
+    match circuit_id {
+        "cdr_privacy" => {
+            info!("🔒 Deriving CDR Privacy Circuit parameters for 5-node consortium...");
+            powers_of_tau.circuit_keys(BCEPrivacyCircuit::<Fr>::empty())
+        }
+        "settlement_calculation" => {
+            info!("🔒 Deriving Settlement Calculation Circuit parameters for 5-party netting...");
+            powers_of_tau.circuit_keys(SettlementCalculationCircuit::<Fr>::empty())
+        }
+        "rate_validation" => {
+            info!("🔒 Deriving Rate Validation Circuit parameters...");
+            powers_of_tau.circuit_keys(RateValidationCircuit::<Fr>::empty())
+        }
+        "block_total" => {
+            info!("🔒 Deriving Block Total Circuit parameters...");
+            powers_of_tau.circuit_keys(BlockTotalCircuit::<Fr>::empty())
+        }
+        "balance_conservation" => {
+            info!("🔒 Deriving Balance Conservation Circuit parameters...");
+            powers_of_tau.circuit_keys(BalanceConservationCircuit::<Fr>::empty())
+        }
+        #[cfg(test)]
+        "test_square_chain_short" => powers_of_tau.circuit_keys(tests::SquareChainCircuit(4)),
+        #[cfg(test)]
+        "test_square_chain_long" => powers_of_tau.circuit_keys(tests::SquareChainCircuit(16)),
+        _ => Err(TrustedSetupError::Serialization(format!("Unknown circuit: {}", circuit_id))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_groth16::Groth16;
+    use ark_snark::SNARK;
+    use ark_relations::{lc, r1cs::{ConstraintSynthesizer, ConstraintSystemRef}};
+    use ed25519_dalek::{Signer, SigningKey};
     use tempfile::tempdir;
-    use ark_std::test_rng;
+
+    /// Restrict a ceremony to the small circuits so tests need only a small phase-1
+    fn use_small_circuits(ceremony: &mut TrustedSetupCeremony) {
+        ceremony.circuits.remove("block_total");
+        ceremony.circuits.remove("balance_conservation");
+        ceremony.circuits.remove("rate_validation");
+    }
+
+    /// Squares a witness the given number of times; a stand-in for the production circuits
+    /// in tests that only exercise the ceremony mechanics
+    pub(super) struct SquareChainCircuit(pub usize);
+
+    impl ConstraintSynthesizer<Fr> for SquareChainCircuit {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> ark_relations::r1cs::Result<()> {
+            let mut value = Fr::from(3u64);
+            let mut current = cs.new_witness_variable(|| Ok(value))?;
+            for _ in 0..self.0 {
+                value.square_in_place();
+                let next = cs.new_witness_variable(|| Ok(value))?;
+                cs.enforce_constraint(lc!() + current, lc!() + current, lc!() + next)?;
+                current = next;
+            }
+            Ok(())
+        }
+    }
+
+    /// Replace a ceremony's circuits with two square chains of different sizes
+    fn use_test_circuits(ceremony: &mut TrustedSetupCeremony) {
+        ceremony.circuits = ["test_square_chain_short", "test_square_chain_long"].iter()
+            .map(|circuit_id| (circuit_id.to_string(), CircuitSetup {
+                circuit_id: circuit_id.to_string(),
+                circuit_description: "Square chain test circuit".to_string(),
+                parameters_hash: None,
+                proving_key: None,
+                verifying_key: None,
+                ceremony_complete: false,
+            }))
+            .collect();
+    }
+
+    /// Consortium keys the members hold themselves; the ceremony only ever sees the public halves
+    fn member_signing_keys() -> HashMap<String, SigningKey> {
+        let mut rng = StdRng::seed_from_u64(42);
+        ["T-Mobile-DE", "Vodafone-UK", "Orange-FR", "Telefónica-ES", "SFR-FR"].iter()
+            .map(|participant| {
+                let mut secret = [0u8; 32];
+                rng.fill_bytes(&mut secret);
+                (participant.to_string(), SigningKey::from_bytes(&secret))
+            })
+            .collect()
+    }
+
+    fn register_members(ceremony: &mut TrustedSetupCeremony) {
+        for (participant, signing_key) in member_signing_keys() {
+            ceremony.register_participant_key(&participant, signing_key.verifying_key());
+        }
+    }
+
+    /// Stands in for each member signing on its own infrastructure
+    fn members_sign(participant_id: &str, message: &[u8]) -> Option<Vec<u8>> {
+        member_signing_keys().get(participant_id).map(|key| key.sign(message).to_bytes().to_vec())
+    }
+
+    fn contribute_signed(ceremony: &mut TrustedSetupCeremony, participant_id: &str, rng: &mut StdRng) -> Result<()> {
+        let signatures = ceremony.contribute(participant_id, rng)?.iter()
+            .map(|contribution| members_sign(participant_id, &contribution.signing_message()).unwrap())
+            .collect();
+        ceremony.submit_contribution_signatures(participant_id, signatures)?;
+        Ok(())
+    }
+
+    /// Consortium ceremony over the small circuits, on a locally contributed phase-1
+    async fn test_ceremony(keys_dir: PathBuf) -> TrustedSetupCeremony {
+        let mut ceremony = TrustedSetupCeremony::sp_5node_consortium_ceremony(keys_dir);
+        use_small_circuits(&mut ceremony);
+        register_members(&mut ceremony);
+
+        let mut powers_of_tau = PowersOfTau::new(128);
+        powers_of_tau.contribute(&mut StdRng::seed_from_u64(0));
+        ceremony.set_powers_of_tau(powers_of_tau).await.unwrap();
+        ceremony
+    }
 
     #[tokio::test]
     async fn test_5node_trusted_setup_ceremony() {
         let temp_dir = tempdir().unwrap();
         let keys_dir = temp_dir.path().to_path_buf();
 
-        let mut ceremony = TrustedSetupCeremony::sp_5node_consortium_ceremony(keys_dir);
-        let mut rng = test_rng();
+        let mut ceremony = test_ceremony(keys_dir).await;
+        let mut rng = StdRng::seed_from_u64(0);
 
         // Run ceremony
-        let transcript = ceremony.run_ceremony(&mut rng).await.unwrap();
+        let transcript = ceremony.run_ceremony(members_sign, &mut rng).await.unwrap();
 
         assert!(matches!(transcript.verification_status, VerificationStatus::Verified));
-        assert_eq!(transcript.contributions.len(), 2); // Two circuits
+        assert_eq!(transcript.contributions.len(), 10); // Two circuits, signed by all 5 members
         assert_eq!(transcript.participants.len(), 5); // All 5 consortium members
 
         // Verify all 5 participants are included
@@ -583,15 +1293,210 @@ mod tests {
         // Verify keys exist
         assert!(ceremony.keys_exist("cdr_privacy").await);
         assert!(ceremony.keys_exist("settlement_calculation").await);
+        let powers_of_tau = ceremony.load_powers_of_tau().await.unwrap();
+        assert_eq!(transcript.powers_of_tau_hash, Some(powers_of_tau.hash().unwrap()));
 
         // Test key loading
         let (pk, vk) = ceremony.load_circuit_keys("cdr_privacy").await.unwrap();
-        assert!(!pk.vk.gamma.is_zero());
-        assert!(!vk.gamma.is_zero());
+        assert_eq!(pk.vk, vk);
 
         // Verify ceremony
         let verification_result = ceremony.verify_ceremony().await.unwrap();
         assert!(verification_result);
+
+        // Settlement keys from before the N-party circuit carry no version and are refused
+        std::fs::remove_file(temp_dir.path().join("settlement_calculation.version")).unwrap();
+        assert!(matches!(
+            ceremony.load_circuit_keys("settlement_calculation").await,
+            Err(TrustedSetupError::Ceremony(ref msg)) if msg.contains("rerun the trusted setup ceremony")
+        ));
+        assert!(ceremony.load_circuit_keys("cdr_privacy").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keys_creates_restorable_backup() {
+        let temp_dir = tempdir().unwrap();
+        let keys_dir = temp_dir.path().to_path_buf();
+
+        let mut ceremony = test_ceremony(keys_dir.clone()).await;
+        ceremony.set_backup_retention(2);
+        let mut rng = StdRng::seed_from_u64(0);
+        ceremony.run_ceremony(members_sign, &mut rng).await.unwrap();
+        let original_vk = std::fs::read(keys_dir.join("cdr_privacy.vk")).unwrap();
+
+        // Rotation backs up the working keys and replaces them
+        let (backup_dir, _) = ceremony.rotate_keys(members_sign, &mut rng).await.unwrap();
+        assert_eq!(std::fs::read(backup_dir.join("cdr_privacy.vk")).unwrap(), original_vk);
+        let rotated_vk = std::fs::read(keys_dir.join("cdr_privacy.vk")).unwrap();
+        assert_ne!(rotated_vk, original_vk);
+
+        // Restoring the latest backup reverts to the prior working keys
+        let restored = ceremony.restore_keys(None).await.unwrap();
+        assert_eq!(std::fs::read(keys_dir.join("cdr_privacy.vk")).unwrap(), original_vk);
+        assert!(ceremony.verify_ceremony().await.unwrap());
+
+        // The keys it replaced were backed up first, so the restore can be undone
+        let backups = ceremony.list_key_backups().await.unwrap();
+        assert_eq!(backups.len(), 2);
+        let latest = ceremony.backups_dir().join(backups.last().unwrap());
+        assert_eq!(std::fs::read(latest.join("cdr_privacy.vk")).unwrap(), rotated_vk);
+
+        // Restoring the oldest backup at the retention limit still finds it before pruning
+        ceremony.restore_keys(Some(&restored)).await.unwrap();
+        assert_eq!(std::fs::read(keys_dir.join("cdr_privacy.vk")).unwrap(), original_vk);
+
+        // Only the configured number of backups is retained
+        ceremony.backup_keys().await.unwrap();
+        ceremony.backup_keys().await.unwrap();
+        assert_eq!(ceremony.list_key_backups().await.unwrap().len(), 2);
+        assert!(ceremony.restore_keys(Some("missing")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_setup_matches_sequential() {
+        let sequential_dir = tempdir().unwrap();
+        let parallel_dir = tempdir().unwrap();
+
+        let mut sequential = test_ceremony(sequential_dir.path().to_path_buf()).await;
+        use_test_circuits(&mut sequential);
+        sequential.set_setup_concurrency(1);
+        let mut parallel = test_ceremony(parallel_dir.path().to_path_buf()).await;
+        use_test_circuits(&mut parallel);
+        parallel.set_setup_concurrency(2);
+
+        let sequential_transcript = sequential.run_ceremony(members_sign, &mut StdRng::seed_from_u64(7)).await.unwrap();
+        let parallel_transcript = parallel.run_ceremony(members_sign, &mut StdRng::seed_from_u64(7)).await.unwrap();
+
+        for circuit_id in sequential.circuit_ids() {
+            let vk_file = format!("{}.vk", circuit_id);
+            assert_eq!(
+                std::fs::read(sequential_dir.path().join(&vk_file)).unwrap(),
+                std::fs::read(parallel_dir.path().join(&vk_file)).unwrap(),
+            );
+        }
+
+        let contributions = |transcript: &CeremonyTranscript| transcript.contributions.iter()
+            .map(|c| (c.circuit_id.clone(), c.contribution_hash))
+            .collect::<Vec<_>>();
+        assert_eq!(contributions(&sequential_transcript), contributions(&parallel_transcript));
+        assert_eq!(sequential_transcript.participants, parallel_transcript.participants);
+
+        assert!(sequential.verify_ceremony().await.unwrap());
+        assert!(parallel.verify_ceremony().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_multi_party_contributions_produce_valid_keys() {
+        use ark_relations::r1cs::ConstraintSystem;
+
+        let temp_dir = tempdir().unwrap();
+        let mut ceremony = test_ceremony(temp_dir.path().to_path_buf()).await;
+        let mut rng = StdRng::seed_from_u64(3);
+
+        ceremony.begin_contributions().await.unwrap();
+        let initial_delta = ceremony.contribution_round.as_ref().unwrap()
+            .proving_keys["settlement_calculation"].delta_g1;
+
+        contribute_signed(&mut ceremony, "T-Mobile-DE", &mut rng).unwrap();
+        contribute_signed(&mut ceremony, "Vodafone-UK", &mut rng).unwrap();
+        assert!(ceremony.contribute("Vodafone-UK", &mut rng).is_err());
+        assert!(ceremony.contribute("Unknown-Operator", &mut rng).is_err());
+
+        // A contribution signed by someone other than the participant is refused and can be retried
+        let forger = SigningKey::from_bytes(&[7u8; 32]);
+        let forged = ceremony.contribute("Orange-FR", &mut rng).unwrap().iter()
+            .map(|contribution| forger.sign(&contribution.signing_message()).to_bytes().to_vec())
+            .collect();
+        assert!(ceremony.submit_contribution_signatures("Orange-FR", forged).is_err());
+        assert!(ceremony.contribute("SFR-FR", &mut rng).is_err());
+        ceremony.abandon_contribution();
+
+        // Two contributions are below the 3-participant minimum
+        assert!(matches!(ceremony.finalize_contributions().await, Err(TrustedSetupError::Ceremony(_))));
+
+        contribute_signed(&mut ceremony, "Orange-FR", &mut rng).unwrap();
+        let transcript = ceremony.finalize_contributions().await.unwrap();
+
+        assert_eq!(transcript.participants, vec!["T-Mobile-DE", "Vodafone-UK", "Orange-FR"]);
+        assert_eq!(transcript.contributions.len(), 6);
+        let settlement_hashes: Vec<_> = transcript.contributions.iter()
+            .filter(|c| c.circuit_id == "settlement_calculation")
+            .map(|c| c.contribution_hash)
+            .collect();
+        assert_eq!(settlement_hashes.len(), 3);
+        assert!(settlement_hashes[0] != settlement_hashes[1] && settlement_hashes[1] != settlement_hashes[2]);
+        assert!(ceremony.verify_ceremony().await.unwrap());
+
+        // The contributed keys still prove and verify
+        let (pk, vk) = ceremony.load_circuit_keys("settlement_calculation").await.unwrap();
+        assert_ne!(pk.delta_g1, initial_delta);
+
+        let mut bilateral = [0u64; 20];
+        bilateral[0] = 50_000;
+        bilateral[4] = 20_000;
+        let circuit = SettlementCalculationCircuit::<Fr>::from_bilateral(bilateral, [1; 8], 54321);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone()).unwrap();
+        let public_inputs = cs.borrow().unwrap().instance_assignment[1..].to_vec();
+
+        let proof = Groth16::<Bn254>::prove(&pk, circuit, &mut rng).unwrap();
+        assert!(Groth16::<Bn254>::verify(&vk, &public_inputs, &proof).unwrap());
+    }
+
+    async fn tamper_transcript(keys_dir: &Path, tamper: impl FnOnce(&mut CeremonyTranscript)) {
+        let path = keys_dir.join("ceremony_transcript.json");
+        let mut transcript: CeremonyTranscript = serde_json::from_str(&fs::read_to_string(&path).await.unwrap()).unwrap();
+        tamper(&mut transcript);
+        fs::write(&path, serde_json::to_string(&transcript).unwrap()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signed_transcript_verifies() {
+        let temp_dir = tempdir().unwrap();
+        let mut ceremony = test_ceremony(temp_dir.path().to_path_buf()).await;
+        let transcript = ceremony.run_ceremony(members_sign, &mut StdRng::seed_from_u64(1)).await.unwrap();
+
+        assert!(transcript.contributions.iter().all(|c| c.signature.len() == 64));
+        assert!(ceremony.verify_ceremony().await.unwrap());
+
+        // A verifier with only the registered public keys accepts the transcript too
+        let mut verifier = TrustedSetupCeremony::new(temp_dir.path().to_path_buf(), ceremony.config.clone());
+        use_small_circuits(&mut verifier);
+        register_members(&mut verifier);
+        assert!(verifier.verify_ceremony().await.unwrap());
+
+        // Without the members' public keys the signatures cannot be checked
+        let mut unkeyed = TrustedSetupCeremony::new(temp_dir.path().to_path_buf(), ceremony.config.clone());
+        use_small_circuits(&mut unkeyed);
+        assert!(!unkeyed.verify_ceremony().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_forged_contribution_fails_verification() {
+        let temp_dir = tempdir().unwrap();
+        let keys_dir = temp_dir.path().to_path_buf();
+        let mut ceremony = test_ceremony(keys_dir.clone()).await;
+        ceremony.run_ceremony(members_sign, &mut StdRng::seed_from_u64(1)).await.unwrap();
+
+        // Orange's contribution signed by someone else's key
+        tamper_transcript(&keys_dir, |transcript| {
+            let forger = SigningKey::from_bytes(&[7u8; 32]);
+            let contribution = transcript.contributions.iter_mut()
+                .find(|c| c.participant_id == "Orange-FR")
+                .unwrap();
+            contribution.signature = forger
+                .sign(&contribution_message(&contribution.contribution_hash, &contribution.previous_hash))
+                .to_bytes()
+                .to_vec();
+        }).await;
+        assert!(!ceremony.verify_ceremony().await.unwrap());
+
+        // A contribution with its signature stripped
+        ceremony.run_ceremony(members_sign, &mut StdRng::seed_from_u64(2)).await.unwrap();
+        assert!(ceremony.verify_ceremony().await.unwrap());
+        tamper_transcript(&keys_dir, |transcript| transcript.contributions[0].signature.clear()).await;
+        assert!(!ceremony.verify_ceremony().await.unwrap());
     }
 
     #[tokio::test] 
@@ -608,4 +1513,31 @@ mod tests {
         assert!(config.all_participants.contains(&"Telefónica-ES".to_string()));
         assert!(config.all_participants.contains(&"SFR-FR".to_string()));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_keys_must_derive_from_powers_of_tau() {
+        // Without a phase-1 accumulator there is nothing to derive keys from
+        let empty_dir = tempdir().unwrap();
+        let mut ceremony = TrustedSetupCeremony::sp_5node_consortium_ceremony(empty_dir.path().to_path_buf());
+        assert!(matches!(ceremony.begin_contributions().await, Err(TrustedSetupError::Ceremony(_))));
+
+        // A malformed accumulator is refused up front
+        let mut malformed = PowersOfTau::new(128);
+        malformed.contribute(&mut StdRng::seed_from_u64(0));
+        malformed.tau_g1[3] = malformed.tau_g1[4];
+        assert!(ceremony.set_powers_of_tau(malformed).await.is_err());
+
+        let temp_dir = tempdir().unwrap();
+        let mut ceremony = test_ceremony(temp_dir.path().to_path_buf()).await;
+        ceremony.run_ceremony(members_sign, &mut StdRng::seed_from_u64(4)).await.unwrap();
+        assert!(ceremony.verify_ceremony().await.unwrap());
+
+        // Swapping in a different phase-1 breaks the link between transcript and keys
+        let mut other = PowersOfTau::new(128);
+        other.contribute(&mut StdRng::seed_from_u64(5));
+        let mut replaced = TrustedSetupCeremony::sp_5node_consortium_ceremony(temp_dir.path().to_path_buf());
+        use_small_circuits(&mut replaced);
+        replaced.set_powers_of_tau(other).await.unwrap();
+        assert!(!replaced.verify_ceremony().await.unwrap());
+    }
+}